@@ -1,6 +1,6 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use battlesnake::game_state::GameState;
-use battlesnake::heuristic::{calculate_snake_control, calculate_control_percentages};
+use battlesnake::heuristic::{calculate_snake_control, calculate_snake_control_with, calculate_control_percentages, calculate_reachable_areas, calculate_hazard_control, ControlOptions};
 
 fn create_sample_game_state(size: usize, num_snakes: usize) -> GameState {
     let mut game = GameState::new(size, size);
@@ -29,6 +29,14 @@ fn benchmark_snake_control(c: &mut Criterion) {
                 b.iter(|| calculate_snake_control(black_box(&game_state)))
             });
             
+            group.bench_function(format!("food_aware_control_{}x{}_{}snakes", size, size, num_snakes), |b| {
+                b.iter(|| calculate_snake_control_with(black_box(&game_state), &ControlOptions { food_aware: true, ..ControlOptions::default() }))
+            });
+            
+            group.bench_function(format!("hazard_control_{}x{}_{}snakes", size, size, num_snakes), |b| {
+                b.iter(|| calculate_hazard_control(black_box(&game_state)))
+            });
+            
             group.bench_function(format!("percentages_{}x{}_{}snakes", size, size, num_snakes), |b| {
                 b.iter(|| calculate_control_percentages(black_box(&game_state)))
             });
@@ -38,5 +46,21 @@ fn benchmark_snake_control(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, benchmark_snake_control);
+fn benchmark_reachable_areas(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Reachable Areas");
+    
+    for size in [11, 19].iter() {
+        for &num_snakes in &[2, 4] {
+            let game_state = create_sample_game_state(*size, num_snakes);
+            
+            group.bench_function(format!("areas_{}x{}_{}snakes", size, size, num_snakes), |b| {
+                b.iter(|| calculate_reachable_areas(black_box(&game_state)))
+            });
+        }
+    }
+    
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_snake_control, benchmark_reachable_areas);
 criterion_main!(benches);
\ No newline at end of file
@@ -0,0 +1,77 @@
+//! Search throughput by parallelism strategy and thread count.
+//!
+//! Each configuration searches a fixed position for a fixed time and
+//! reports iterations per second, and the speedup over the same strategy on
+//! one thread. Root-parallel iterations include every thread's tree.
+//! Run with `cargo bench --bench parallel_benchmark`.
+
+use battlesnake::config::SearchConfig;
+use battlesnake::game_state::GameState;
+use battlesnake::search::{Parallelism, SearchMode, MCTS};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+const SEARCH_TIME: Duration = Duration::from_millis(500);
+const REPEATS: u32 = 3;
+const THREAD_COUNTS: [usize; 4] = [1, 2, 4, 8];
+
+fn create_sample_game_state() -> GameState {
+    let mut game = GameState::new(11, 11);
+    game.add_snake("snake1".to_string(), vec![12, 13, 14], 100);
+    game.add_snake("snake2".to_string(), vec![108, 107, 106], 100);
+    game.add_snake("snake3".to_string(), vec![30, 41, 52], 100);
+    game.add_snake("snake4".to_string(), vec![90, 79, 68], 100);
+    game.add_food(60);
+    game
+}
+
+/// Iterations per second of fresh searches with the given configuration.
+fn iterations_per_second(
+    game_state: &GameState,
+    mode: SearchMode,
+    parallelism: Parallelism,
+    num_threads: usize,
+) -> f64 {
+    let mut iterations = 0;
+    let mut elapsed = Duration::ZERO;
+    for _ in 0..REPEATS {
+        let config = SearchConfig {
+            mode,
+            parallelism,
+            ..SearchConfig::default()
+        };
+        let mcts = MCTS::new(game_state.clone(), config);
+        let start = Instant::now();
+        mcts.run(SEARCH_TIME, num_threads);
+        elapsed += start.elapsed();
+        iterations += mcts.iterations.load(Ordering::Relaxed);
+    }
+    iterations as f64 / elapsed.as_secs_f64()
+}
+
+fn main() {
+    let game_state = create_sample_game_state();
+    println!("{} cpus", num_cpus::get());
+
+    for mode in [SearchMode::Sequential, SearchMode::Simultaneous] {
+        for parallelism in [
+            Parallelism::SharedTree,
+            Parallelism::RootParallel,
+            Parallelism::LeafParallel,
+        ] {
+            let mut single_thread = None;
+            for num_threads in THREAD_COUNTS {
+                let rate = iterations_per_second(&game_state, mode, parallelism, num_threads);
+                let baseline = *single_thread.get_or_insert(rate);
+                println!(
+                    "{:?}/{:?}/{} threads: {:.0} iterations/s, {:.2}x",
+                    mode,
+                    parallelism,
+                    num_threads,
+                    rate,
+                    rate / baseline
+                );
+            }
+        }
+    }
+}
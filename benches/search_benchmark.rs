@@ -0,0 +1,69 @@
+//! Single-threaded search throughput, the playout rate.
+//!
+//! Each position is searched for a fixed time in both modes and reports
+//! iterations per second. The walled-in position keeps a snake cut off from
+//! the rest of the board, so the `endgame` solver has work to do in most
+//! nodes. Run with `cargo bench --bench search_benchmark`.
+
+use battlesnake::config::SearchConfig;
+use battlesnake::game_state::GameState;
+use battlesnake::search::{SearchMode, MCTS};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+const SEARCH_TIME: Duration = Duration::from_millis(1000);
+const REPEATS: u32 = 3;
+
+fn open_board() -> GameState {
+    let mut game = GameState::new(11, 11);
+    game.add_snake("snake1".to_string(), vec![12, 13, 14], 100);
+    game.add_snake("snake2".to_string(), vec![108, 107, 106], 100);
+    game.add_snake("snake3".to_string(), vec![30, 41, 52], 100);
+    game.add_snake("snake4".to_string(), vec![90, 79, 68], 100);
+    game.add_food(60);
+    game
+}
+
+/// snake2 is shut into the three left columns by snake1's body.
+fn walled_in() -> GameState {
+    let mut game = GameState::new(11, 11);
+    game.add_snake(
+        "snake1".to_string(),
+        (0..11).map(|row| row * 11 + 3).collect(),
+        100,
+    );
+    game.add_snake("snake2".to_string(), vec![0, 11, 22], 100);
+    game.add_food(60);
+    game
+}
+
+/// Iterations per second of fresh single-threaded searches.
+fn iterations_per_second(game_state: &GameState, mode: SearchMode) -> f64 {
+    let mut iterations = 0;
+    let mut elapsed = Duration::ZERO;
+    for _ in 0..REPEATS {
+        let config = SearchConfig {
+            mode,
+            ..SearchConfig::default()
+        };
+        let mcts = MCTS::new(game_state.clone(), config);
+        let start = Instant::now();
+        mcts.run(SEARCH_TIME, 1);
+        elapsed += start.elapsed();
+        iterations += mcts.iterations.load(Ordering::Relaxed);
+    }
+    iterations as f64 / elapsed.as_secs_f64()
+}
+
+fn main() {
+    for (name, game_state) in [("open board", open_board()), ("walled in", walled_in())] {
+        for mode in [SearchMode::Sequential, SearchMode::Simultaneous] {
+            println!(
+                "{}/{:?}: {:.0} iterations/s",
+                name,
+                mode,
+                iterations_per_second(&game_state, mode)
+            );
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use battlesnake::bitboard::{Bitboard11, Bitboard19};
+use battlesnake::game_state::{Direction, GameState};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::seq::SliceRandom;
+
+fn create_sample_game_state(size: usize, num_snakes: usize) -> GameState {
+    let mut game = GameState::new(size, size);
+    for i in 0..num_snakes {
+        let start = i * (size * size / num_snakes);
+        game.add_snake(
+            format!("snake{}", i),
+            vec![start, start + 1, start + 2],
+            100,
+        );
+    }
+    game.add_food(size * size / 2);
+    game.add_hazard(size - 1);
+    game.add_hazard(size * size - size);
+    game
+}
+
+/// Plays random safe moves for up to 50 turns, as a rollout would.
+fn playout_game_state(game_state: &GameState) -> u32 {
+    let mut state = game_state.clone();
+    let mut rng = rand::thread_rng();
+    while !state.is_game_over() && state.turn < 50 {
+        let moves: Vec<Direction> = (0..state.snakes.len())
+            .map(|i| {
+                *state
+                    .get_safe_moves(i)
+                    .choose(&mut rng)
+                    .unwrap_or(&Direction::Up)
+            })
+            .collect();
+        state.step(&moves);
+    }
+    state.turn
+}
+
+macro_rules! playout_bitboard {
+    ($name:ident, $state:ty) => {
+        fn $name(bitboard: &$state) -> u32 {
+            let mut state = bitboard.clone();
+            let mut rng = rand::thread_rng();
+            let mut moves = [Direction::Up; battlesnake::bitboard::MAX_SNAKES];
+            while !state.is_game_over() && state.turn < 50 {
+                for (i, direction) in moves.iter_mut().enumerate().take(state.snake_count()) {
+                    let mut safe = [Direction::Up; 4];
+                    let mut count = 0;
+                    for safe_move in state.safe_moves(i) {
+                        safe[count] = safe_move;
+                        count += 1;
+                    }
+                    *direction = *safe[..count].choose(&mut rng).unwrap_or(&Direction::Up);
+                }
+                if state.step(&moves[..state.snake_count()]).is_err() {
+                    break;
+                }
+            }
+            state.turn
+        }
+    };
+}
+
+playout_bitboard!(playout_bitboard11, Bitboard11);
+playout_bitboard!(playout_bitboard19, Bitboard19);
+
+fn benchmark_playouts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Playouts");
+
+    for &num_snakes in &[2, 4] {
+        let game_state = create_sample_game_state(11, num_snakes);
+        let bitboard = Bitboard11::from_game_state(&game_state).unwrap();
+
+        group.bench_function(format!("game_state_11x11_{}snakes", num_snakes), |b| {
+            b.iter(|| playout_game_state(black_box(&game_state)))
+        });
+        group.bench_function(format!("bitboard_11x11_{}snakes", num_snakes), |b| {
+            b.iter(|| playout_bitboard11(black_box(&bitboard)))
+        });
+
+        let game_state = create_sample_game_state(19, num_snakes);
+        let bitboard = Bitboard19::from_game_state(&game_state).unwrap();
+
+        group.bench_function(format!("game_state_19x19_{}snakes", num_snakes), |b| {
+            b.iter(|| playout_game_state(black_box(&game_state)))
+        });
+        group.bench_function(format!("bitboard_19x19_{}snakes", num_snakes), |b| {
+            b.iter(|| playout_bitboard19(black_box(&bitboard)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_playouts);
+criterion_main!(benches);
@@ -0,0 +1,459 @@
+//! Deterministic iterative-deepening alpha-beta search, an alternative to
+//! `MCTS` for duels and endgames where sampling wastes time the position
+//! could be searched exhaustively in.
+//!
+//! Snakes move simultaneously, so each turn is searched as if our snake
+//! chose first and the others replied knowing its move. How the others
+//! reply depends on the number of snakes alive:
+//!
+//! - With one opponent the search is paranoid: the opponent minimises our
+//!   score, which is ordinary two-player alpha-beta.
+//! - With more, `MultiplayerStrategy` picks between paranoid, max-n (every
+//!   snake maximises its own score, without pruning) and best-reply (only
+//!   the single most harmful opponent deviates from its best-ordered move,
+//!   which keeps the branching factor close to a duel's).
+//!
+//! Leaves are scored with the same evaluation as `MCTS`, and moves are tried
+//! in order of `calculate_move_control` so the best ones are found early.
+//! Food spawning and royale shrinking are random, so the search plays on
+//! with neither to stay deterministic.
+
+use crate::config::SearchConfig;
+use crate::evaluation::{evaluator_for, Evaluator};
+use crate::game_state::{Direction, GameState};
+use crate::heuristic::calculate_move_control;
+use crate::search::MCTS;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How the other snakes reply when more than one of them is alive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MultiplayerStrategy {
+    /// Every other snake plays to minimise our score, as one opponent.
+    Paranoid,
+    /// Every snake plays to maximise its own score.
+    MaxN,
+    /// One opponent at a time plays to minimise our score while the rest
+    /// play their best-ordered move.
+    #[default]
+    BestReply,
+}
+
+/// A root move and its value for our snake from the deepest completed
+/// iteration. Only the best move's value is exact; pruned moves' values are
+/// upper bounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveValue {
+    pub direction: Direction,
+    pub value: f32,
+}
+
+/// The outcome of an `AlphaBeta` search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlphaBetaResult {
+    pub best_move: Option<Direction>,
+    /// Our snake's score after `best_move` with best play.
+    pub value: f32,
+    /// The deepest search, in turns, that completed.
+    pub depth: u32,
+    /// Positions visited across all iterations.
+    pub nodes: u64,
+    /// Whether the search reached the end of every line, so searching deeper
+    /// would change nothing.
+    pub solved: bool,
+    /// Our root moves, best first.
+    pub moves: Vec<MoveValue>,
+}
+
+/// Prints the depth and value, then one line per root move.
+impl fmt::Display for AlphaBetaResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:?} at depth {}{} ({} nodes): {:.3}",
+            self.best_move,
+            self.depth,
+            if self.solved { ", solved" } else { "" },
+            self.nodes,
+            self.value
+        )?;
+        for report in &self.moves {
+            writeln!(f, "  {:?}: {:.3}", report.direction, report.value)?;
+        }
+        Ok(())
+    }
+}
+
+/// An iterative-deepening alpha-beta search for one snake.
+pub struct AlphaBeta {
+    config: SearchConfig,
+    evaluator: Arc<dyn Evaluator>,
+}
+
+/// State of one iteration.
+struct Iteration {
+    /// The snake searched for.
+    us: usize,
+    depth: u32,
+    deadline: Instant,
+    nodes: u64,
+    /// Whether any line was cut off by the depth limit rather than ending.
+    hit_horizon: bool,
+}
+
+impl Iteration {
+    /// Counts a node, returning `None` once the deadline has passed so the
+    /// iteration unwinds.
+    fn visit(&mut self) -> Option<()> {
+        self.nodes += 1;
+        if Instant::now() >= self.deadline {
+            None
+        } else {
+            Some(())
+        }
+    }
+}
+
+impl AlphaBeta {
+    pub fn new(config: &SearchConfig) -> Self {
+        AlphaBeta {
+            config: config.clone(),
+            evaluator: evaluator_for(config),
+        }
+    }
+
+    /// Scores leaves with `evaluator` instead of the config's heuristic.
+    pub fn set_evaluator(&mut self, evaluator: Arc<dyn Evaluator>) {
+        self.evaluator = evaluator;
+    }
+
+    /// Searches `state` for the snake with `snake_id`, one turn deeper per
+    /// iteration up to `alpha_beta.max_depth`. No iteration starts after
+    /// `soft` and the search stops at `hard`, keeping the deepest completed
+    /// iteration.
+    pub fn search(
+        &self,
+        state: &GameState,
+        snake_id: &str,
+        soft: Duration,
+        hard: Duration,
+    ) -> AlphaBetaResult {
+        let start = Instant::now();
+        let state = &deterministic(state);
+
+        let mut result = AlphaBetaResult {
+            best_move: None,
+            value: self.config.terminal.loss,
+            depth: 0,
+            nodes: 0,
+            solved: false,
+            moves: Vec::new(),
+        };
+
+        let us = match state.snake_index(snake_id) {
+            Some(us) if state.snakes[us].health > 0 => us,
+            _ => return result,
+        };
+        let mut root_moves = self.ordered_moves(state, us, 2);
+        result.best_move = root_moves.first().copied();
+        if state.is_game_over() {
+            return result;
+        }
+
+        for depth in 1..=self.config.alpha_beta.max_depth {
+            if start.elapsed() >= soft {
+                break;
+            }
+            let mut iteration = Iteration {
+                us,
+                depth,
+                deadline: start + hard,
+                nodes: 0,
+                hit_horizon: false,
+            };
+            let moves = self.search_root(state, &root_moves, &mut iteration);
+            result.nodes += iteration.nodes;
+            let Some(moves) = moves else {
+                break;
+            };
+
+            // The next iteration starts with this one's best move
+            root_moves = moves.iter().map(|report| report.direction).collect();
+            result.best_move = Some(moves[0].direction);
+            result.value = moves[0].value;
+            result.depth = depth;
+            result.moves = moves;
+            result.solved = !iteration.hit_horizon;
+            if result.solved {
+                break;
+            }
+        }
+
+        result
+    }
+
+    /// Each of our root moves' values, best first, or `None` if the deadline
+    /// passed.
+    fn search_root(
+        &self,
+        state: &GameState,
+        root_moves: &[Direction],
+        iteration: &mut Iteration,
+    ) -> Option<Vec<MoveValue>> {
+        let us = iteration.us;
+        let mut moves = Vec::with_capacity(root_moves.len());
+        let mut alpha = f32::NEG_INFINITY;
+
+        for &direction in root_moves {
+            let value = if self.uses_max_n(state) {
+                let mut joint = self.default_moves(state);
+                joint[us] = direction;
+                let order = self.turn_order(state, us);
+                self.max_n(state, iteration.depth, &mut joint, &order, 1, iteration)?[us]
+            } else {
+                self.min_node(
+                    state,
+                    iteration.depth,
+                    direction,
+                    alpha,
+                    f32::INFINITY,
+                    iteration,
+                )?
+            };
+            alpha = alpha.max(value);
+            moves.push(MoveValue { direction, value });
+        }
+
+        // Stable, so equal moves keep their ordering
+        moves.sort_by(|a, b| b.value.total_cmp(&a.value));
+        Some(moves)
+    }
+
+    /// Our snake's best score from `state`, where it is about to choose a
+    /// move with `depth` turns left.
+    fn max_node(
+        &self,
+        state: &GameState,
+        depth: u32,
+        mut alpha: f32,
+        beta: f32,
+        iteration: &mut Iteration,
+    ) -> Option<f32> {
+        iteration.visit()?;
+        let us = iteration.us;
+        if let Some(scores) = self.leaf_scores(state, depth, iteration) {
+            return Some(scores[us]);
+        }
+
+        let mut best = f32::NEG_INFINITY;
+        for direction in self.ordered_moves(state, us, depth) {
+            let value = self.min_node(state, depth, direction, alpha, beta, iteration)?;
+            best = best.max(value);
+            alpha = alpha.max(value);
+            if alpha >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+
+    /// Our snake's score once the other snakes have replied to it playing
+    /// `our_move` from `state`.
+    fn min_node(
+        &self,
+        state: &GameState,
+        depth: u32,
+        our_move: Direction,
+        alpha: f32,
+        mut beta: f32,
+        iteration: &mut Iteration,
+    ) -> Option<f32> {
+        let mut best = f32::INFINITY;
+        for moves in self.replies(state, iteration.us, our_move, depth) {
+            let mut child = state.clone();
+            child.step(&moves);
+            let value = self.max_node(&child, depth - 1, alpha, beta, iteration)?;
+            best = best.min(value);
+            beta = beta.min(value);
+            if alpha >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+
+    /// Every snake's score from `state` when the snakes in `order` from
+    /// `next` on are still to choose their part of `joint`, each maximising
+    /// its own score.
+    fn max_n(
+        &self,
+        state: &GameState,
+        depth: u32,
+        joint: &mut Vec<Direction>,
+        order: &[usize],
+        next: usize,
+        iteration: &mut Iteration,
+    ) -> Option<Vec<f32>> {
+        if next == order.len() {
+            iteration.visit()?;
+            let mut child = state.clone();
+            child.step(joint);
+            let depth = depth - 1;
+            if let Some(scores) = self.leaf_scores(&child, depth, iteration) {
+                return Some(scores);
+            }
+            let mut joint = self.default_moves(&child);
+            let order = self.turn_order(&child, iteration.us);
+            return self.max_n(&child, depth, &mut joint, &order, 0, iteration);
+        }
+
+        let snake = order[next];
+        let mut best: Option<Vec<f32>> = None;
+        for direction in self.ordered_moves(state, snake, depth) {
+            joint[snake] = direction;
+            let scores = self.max_n(state, depth, joint, order, next + 1, iteration)?;
+            if best.as_ref().is_none_or(|best| scores[snake] > best[snake]) {
+                best = Some(scores);
+            }
+        }
+        best
+    }
+
+    /// Every snake's score if `state` ends the line: the game is over, our
+    /// snake is out, or there are no turns left to search.
+    fn leaf_scores(
+        &self,
+        state: &GameState,
+        depth: u32,
+        iteration: &mut Iteration,
+    ) -> Option<Vec<f32>> {
+        let game_over = state.is_game_over();
+        if !game_over && state.snakes[iteration.us].health > 0 && depth > 0 {
+            return None;
+        }
+        if depth == 0 && !game_over {
+            iteration.hit_horizon = true;
+        }
+
+        let mut scores = MCTS::default_policy(state, &self.config, self.evaluator.as_ref(), None);
+        if !game_over {
+            // Eliminated snakes have lost, whatever the heuristic says
+            for (score, snake) in scores.iter_mut().zip(&state.snakes) {
+                if snake.health == 0 {
+                    *score = self.config.terminal.loss;
+                }
+            }
+        }
+        Some(scores)
+    }
+
+    /// The other snakes' joint replies to `our_move`, most promising first.
+    fn replies(
+        &self,
+        state: &GameState,
+        us: usize,
+        our_move: Direction,
+        depth: u32,
+    ) -> Vec<Vec<Direction>> {
+        let mut base = self.default_moves(state);
+        base[us] = our_move;
+
+        let opponents: Vec<(usize, Vec<Direction>)> = self
+            .turn_order(state, us)
+            .into_iter()
+            .skip(1)
+            .map(|snake| (snake, self.ordered_moves(state, snake, depth)))
+            .collect();
+        for (snake, moves) in &opponents {
+            if let Some(&direction) = moves.first() {
+                base[*snake] = direction;
+            }
+        }
+
+        if opponents.len() > 1
+            && self.config.alpha_beta.multiplayer == MultiplayerStrategy::BestReply
+        {
+            // One opponent deviates at a time; everyone playing their first
+            // move is listed once
+            let mut replies = vec![base.clone()];
+            for (snake, moves) in &opponents {
+                for &direction in moves.iter().skip(1) {
+                    let mut reply = base.clone();
+                    reply[*snake] = direction;
+                    replies.push(reply);
+                }
+            }
+            return replies;
+        }
+
+        // Every combination of the opponents' moves
+        let mut replies = vec![base];
+        for (snake, moves) in &opponents {
+            replies = replies
+                .into_iter()
+                .flat_map(|reply| {
+                    moves.iter().map(move |&direction| {
+                        let mut reply = reply.clone();
+                        reply[*snake] = direction;
+                        reply
+                    })
+                })
+                .collect();
+        }
+        replies
+    }
+
+    /// Whether the snakes left in `state` are searched with max-n.
+    fn uses_max_n(&self, state: &GameState) -> bool {
+        self.config.alpha_beta.multiplayer == MultiplayerStrategy::MaxN
+            && state.snakes.iter().filter(|s| s.health > 0).count() > 2
+    }
+
+    /// The snakes still alive, `us` first.
+    fn turn_order(&self, state: &GameState, us: usize) -> Vec<usize> {
+        std::iter::once(us)
+            .chain((0..state.snakes.len()).filter(|&i| i != us))
+            .filter(|&i| state.snakes[i].health > 0)
+            .collect()
+    }
+
+    /// A move for every snake, for those that don't get to choose.
+    fn default_moves(&self, state: &GameState) -> Vec<Direction> {
+        (0..state.snakes.len())
+            .map(|i| state.default_move(i))
+            .collect()
+    }
+
+    /// The moves worth trying for `snake`, the ones leaving it the most of
+    /// the board first. Ordering costs a flood fill per move, so moves just
+    /// above the horizon keep their plain order. A snake with no safe moves
+    /// still has to move somewhere.
+    fn ordered_moves(&self, state: &GameState, snake: usize, depth: u32) -> Vec<Direction> {
+        let mut moves = state.get_safe_moves(snake);
+        if moves.is_empty() {
+            return vec![state.default_move(snake)];
+        }
+        if depth > 1 && moves.len() > 1 {
+            let mut scored: Vec<(Direction, f32)> = moves
+                .iter()
+                .map(|&direction| (direction, calculate_move_control(state, snake, direction)))
+                .collect();
+            scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+            moves = scored.into_iter().map(|(direction, _)| direction).collect();
+        }
+        moves
+    }
+}
+
+/// A copy of `state` that neither spawns food nor shrinks, so stepping it
+/// is deterministic.
+pub(crate) fn deterministic(state: &GameState) -> GameState {
+    let mut state = state.clone();
+    state.settings.food_spawn_chance = 0;
+    state.settings.minimum_food = 0;
+    state.settings.royale.shrink_every_n_turns = 0;
+    state
+}
@@ -0,0 +1,263 @@
+//! Choke points of the board.
+//!
+//! `BoardGraph` is the graph of free squares: every square no snake's body
+//! is on, joined to its free neighbours as the ruleset moves between them.
+//! `BoardAnalysis` finds its articulation points, the squares that split the
+//! free space in two when a snake moves onto them, the biconnected
+//! components they separate, and how wide the corridor through each square
+//! is. A snake that takes an articulation point cuts the squares beyond it
+//! off from the squares behind it, which is what cutting an opponent off
+//! comes down to.
+
+use crate::game_state::{Direction, GameState};
+use std::collections::VecDeque;
+
+/// The free squares of a board and how they connect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardGraph {
+    pub width: usize,
+    pub height: usize,
+    /// Whether each square is free of snake bodies.
+    pub free: Vec<bool>,
+    /// Each square's neighbour in each of `Direction::ALL`, or `usize::MAX`
+    /// off the board.
+    neighbours: Vec<[usize; 4]>,
+}
+
+impl BoardGraph {
+    /// The free squares of `game_state`. Every body square of a snake still
+    /// in the game, head and tail included, is taken.
+    pub fn from_game_state(game_state: &GameState) -> Self {
+        let board_size = game_state.width * game_state.height;
+        let mut free = vec![true; board_size];
+        for snake in &game_state.snakes {
+            if snake.health == 0 {
+                continue;
+            }
+            for part in &snake.body {
+                if part.index != usize::MAX {
+                    free[part.index] = false;
+                }
+            }
+        }
+        let neighbours = (0..board_size)
+            .map(|index| Direction::ALL.map(|direction| game_state.neighbour(index, direction)))
+            .collect();
+
+        BoardGraph {
+            width: game_state.width,
+            height: game_state.height,
+            free,
+            neighbours,
+        }
+    }
+
+    /// The free squares next to `index`.
+    pub fn free_neighbours(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.neighbours[index]
+            .into_iter()
+            .filter(|&next| next != usize::MAX && self.free[next])
+    }
+
+    /// The sizes of the connected regions of free squares, largest first,
+    /// with `blocked` taken as well if given.
+    pub fn region_sizes(&self, blocked: Option<usize>) -> Vec<usize> {
+        let mut seen = vec![false; self.free.len()];
+        if let Some(blocked) = blocked {
+            seen[blocked] = true;
+        }
+        let mut sizes = Vec::new();
+        let mut queue = VecDeque::new();
+
+        for start in 0..self.free.len() {
+            if !self.free[start] || seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            queue.push_back(start);
+            let mut size = 0;
+            while let Some(index) = queue.pop_front() {
+                size += 1;
+                for next in self.free_neighbours(index) {
+                    if !seen[next] {
+                        seen[next] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes
+    }
+}
+
+/// Articulation points, biconnected components and corridor widths of a
+/// `BoardGraph`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardAnalysis {
+    pub graph: BoardGraph,
+    /// Whether each square is an articulation point: a free square that
+    /// leaves more regions of free squares when taken.
+    pub articulation_points: Vec<bool>,
+    /// The squares of each biconnected component, sorted. Free squares with
+    /// no free neighbours belong to none.
+    pub components: Vec<Vec<usize>>,
+    /// For each free square, the shorter of the unbroken rows and columns of
+    /// free squares through it; 0 for taken squares. Squares in a corridor
+    /// one square wide have width 1.
+    pub corridor_width: Vec<usize>,
+}
+
+impl BoardAnalysis {
+    pub fn new(game_state: &GameState) -> Self {
+        Self::from_graph(BoardGraph::from_game_state(game_state))
+    }
+
+    pub fn from_graph(graph: BoardGraph) -> Self {
+        let (articulation_points, components) = biconnected_components(&graph);
+        let corridor_width = corridor_widths(&graph);
+        BoardAnalysis {
+            graph,
+            articulation_points,
+            components,
+            corridor_width,
+        }
+    }
+
+    /// The articulation points, in board order.
+    pub fn choke_points(&self) -> Vec<usize> {
+        (0..self.articulation_points.len())
+            .filter(|&index| self.articulation_points[index])
+            .collect()
+    }
+
+    /// Whether a snake moving onto `index` would split the free space.
+    pub fn splits_board(&self, index: usize) -> bool {
+        self.articulation_points[index]
+    }
+
+    /// The regions the free space would split into, largest first, if
+    /// `index` were taken.
+    pub fn regions_if_taken(&self, index: usize) -> Vec<usize> {
+        self.graph.region_sizes(Some(index))
+    }
+}
+
+/// Tarjan's algorithm, kept on an explicit stack so large boards can't
+/// overflow the call stack.
+fn biconnected_components(graph: &BoardGraph) -> (Vec<bool>, Vec<Vec<usize>>) {
+    let board_size = graph.free.len();
+    let mut discovered = vec![u32::MAX; board_size];
+    let mut low = vec![0u32; board_size];
+    let mut parent = vec![usize::MAX; board_size];
+    let mut articulation_points = vec![false; board_size];
+    let mut components = Vec::new();
+    let mut edges: Vec<(usize, usize)> = Vec::new();
+    let mut time = 0u32;
+
+    for root in 0..board_size {
+        if !graph.free[root] || discovered[root] != u32::MAX {
+            continue;
+        }
+        discovered[root] = time;
+        low[root] = time;
+        time += 1;
+        let mut root_children = 0;
+        // Each square on the search path with the next direction to try
+        let mut stack = vec![(root, 0usize)];
+
+        while let Some(&mut (v, ref mut next_direction)) = stack.last_mut() {
+            if *next_direction < Direction::ALL.len() {
+                let w = graph.neighbours[v][*next_direction];
+                *next_direction += 1;
+                if w == usize::MAX || !graph.free[w] {
+                    continue;
+                }
+                if discovered[w] == u32::MAX {
+                    parent[w] = v;
+                    discovered[w] = time;
+                    low[w] = time;
+                    time += 1;
+                    edges.push((v, w));
+                    if v == root {
+                        root_children += 1;
+                    }
+                    stack.push((w, 0));
+                } else if w != parent[v] && discovered[w] < discovered[v] {
+                    low[v] = low[v].min(discovered[w]);
+                    edges.push((v, w));
+                }
+                continue;
+            }
+
+            stack.pop();
+            let Some(&(u, _)) = stack.last() else {
+                continue;
+            };
+            low[u] = low[u].min(low[v]);
+            if low[v] >= discovered[u] {
+                if u != root {
+                    articulation_points[u] = true;
+                }
+                let mut component = Vec::new();
+                while let Some((a, b)) = edges.pop() {
+                    component.push(a);
+                    component.push(b);
+                    if (a, b) == (u, v) {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                component.dedup();
+                components.push(component);
+            }
+        }
+
+        if root_children > 1 {
+            articulation_points[root] = true;
+        }
+    }
+
+    (articulation_points, components)
+}
+
+fn corridor_widths(graph: &BoardGraph) -> Vec<usize> {
+    // The free squares in a line through `index`, going both ways, without
+    // wrapping past the start
+    let run = |index: usize, forward: usize, backward: usize, limit: usize| {
+        let mut length = 1;
+        for direction in [forward, backward] {
+            let mut current = index;
+            while length < limit {
+                let next = graph.neighbours[current][direction];
+                if next == usize::MAX || !graph.free[next] || next == index {
+                    break;
+                }
+                length += 1;
+                current = next;
+            }
+        }
+        length
+    };
+    let position = |direction: Direction| {
+        Direction::ALL
+            .iter()
+            .position(|&d| d == direction)
+            .expect("every direction is in Direction::ALL")
+    };
+    let (up, down) = (position(Direction::Up), position(Direction::Down));
+    let (left, right) = (position(Direction::Left), position(Direction::Right));
+
+    (0..graph.free.len())
+        .map(|index| {
+            if !graph.free[index] {
+                return 0;
+            }
+            let across = run(index, left, right, graph.width);
+            let along = run(index, up, down, graph.height);
+            across.min(along)
+        })
+        .collect()
+}
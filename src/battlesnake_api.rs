@@ -1,4 +1,5 @@
-use crate::game_state::{GameState, Position, Snake};
+use crate::game_state::{Direction, GameState, SnakeOrder};
+use crate::rules::{self, GameMode};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Debug)]
@@ -20,6 +21,10 @@ pub struct Game {
 pub struct Ruleset {
     pub name: String,
     pub version: String,
+    /// `game.ruleset.settings`. Anything missing from the request falls back
+    /// to the reference engine's defaults.
+    #[serde(default)]
+    pub settings: rules::Settings,
 }
 
 #[derive(Deserialize, Debug)]
@@ -39,53 +44,124 @@ pub struct Battlesnake {
     pub body: Vec<Coord>,
     pub head: Coord,
     pub length: usize,
+    #[serde(default)]
+    pub squad: String,
+    /// Round trip of our previous response as measured by the engine, in
+    /// milliseconds. Empty on the first turn and "0" after a timeout.
+    #[serde(default)]
+    pub latency: String,
 }
 
+/// A square as the API sees it: `x` grows to the right and `y` grows up, so
+/// `(0, 0)` is the bottom left corner.
 #[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Coord {
     pub x: usize,
     pub y: usize,
 }
 
+impl Coord {
+    /// The index of this square on a `GameState` board, which is row-major
+    /// with row 0 at the top. API up is then `Direction::Up` on the board.
+    pub fn to_index(self, width: usize, height: usize) -> usize {
+        (height - 1 - self.y) * width + self.x
+    }
+
+    /// The square at `index` on a `GameState` board; the inverse of `to_index`.
+    pub fn from_index(index: usize, width: usize, height: usize) -> Coord {
+        Coord {
+            x: index % width,
+            y: height - 1 - index / width,
+        }
+    }
+}
+
+/// The API name of a board direction, as sent in `MoveResponse`.
+pub fn direction_to_move(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+/// The board direction an API move name stands for; the inverse of
+/// `direction_to_move`.
+pub fn direction_from_move(name: &str) -> Option<Direction> {
+    match name {
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
 #[derive(Serialize)]
 pub struct MoveResponse {
     pub r#move: String,
     pub shout: Option<String>,
 }
 
+impl MoveResponse {
+    pub fn new(direction: Direction, shout: String) -> Self {
+        MoveResponse {
+            r#move: direction_to_move(direction).to_string(),
+            shout: Some(shout),
+        }
+    }
+}
+
 impl BattlesnakeRequest {
-    pub fn to_game_state(&self) -> GameState {
-        let mut game_state = GameState::new(self.board.width, self.board.height);
-        let width = self.board.width;
-        let height = self.board.height;
+    /// The order of `board.snakes`, which `to_game_state` changes.
+    pub fn snake_order(&self) -> SnakeOrder {
+        SnakeOrder::new(self.board.snakes.iter().map(|s| s.id.clone()).collect())
+    }
 
-        // Helper function to convert (x, y) to index
-        fn coord_to_index(x: usize, y: usize, width: usize) -> usize {
-            y * width + x
-        }
+    /// The request as a `GameState`, with `you` moved to index 0 so that it
+    /// is the first to move in a sequential search. The other snakes keep
+    /// their order from `board.snakes`; `snake_order` maps back to it.
+    /// Squares are converted with `Coord::to_index`, so board directions are
+    /// API directions.
+    pub fn to_game_state(&self) -> GameState {
+        let (width, height) = (self.board.width, self.board.height);
+        let mut game_state = GameState::new(width, height);
+        game_state.turn = self.turn;
+        game_state.mode = GameMode::from_name(&self.game.ruleset.name);
+        game_state.settings = self.game.ruleset.settings.clone();
 
-        // Add snakes
-        for snake in &self.board.snakes {
+        // Add snakes, us first
+        let (you, others): (Vec<&Battlesnake>, Vec<&Battlesnake>) = self
+            .board
+            .snakes
+            .iter()
+            .partition(|snake| snake.id == self.you.id);
+        for snake in you.into_iter().chain(others) {
             let body: Vec<usize> = snake
                 .body
                 .iter()
-                .map(|coord| coord_to_index(coord.x, coord.y, width))
+                .map(|coord| coord.to_index(width, height))
                 .collect();
             game_state.add_snake(snake.id.clone(), body, snake.health);
+            if !snake.squad.is_empty() {
+                if let Some(added) = game_state.snakes.last_mut() {
+                    added.squad = Some(snake.squad.clone());
+                }
+            }
         }
 
         // Add food
         for food in &self.board.food {
-            let index = coord_to_index(food.x, food.y, width);
-            game_state.add_food(index);
+            game_state.add_food(food.to_index(width, height));
         }
 
         // Add hazards
         for hazard in &self.board.hazards {
-            let index = coord_to_index(hazard.x, hazard.y, width);
-            game_state.add_hazard(index);
+            game_state.add_hazard(hazard.to_index(width, height));
         }
 
+        game_state.rehash();
         game_state
     }
 }
@@ -0,0 +1,999 @@
+//! A compact, allocation-free alternative to `GameState` for simulation.
+//!
+//! Occupancy, food and hazards are stored as bitboards and snake bodies as
+//! fixed-size ring buffers, so cloning a state is a plain memory copy and a
+//! turn can be played without touching the heap. `Bitboard11` covers boards
+//! up to 11x11 with `u128` bitboards; `Bitboard19` covers boards up to 19x19.
+//!
+//! Turns are played by the game mode's `Ruleset` through `GameBoard`, the
+//! same rules `GameState` plays, with the same elimination order and
+//! attribution. Convert with `BitboardState::from_game_state` and
+//! `BitboardState::to_game_state`. A body can only hold `N` segments, so a
+//! turn that grows a snake past that fails, and the game has to be played on
+//! from a `GameState` instead.
+
+use crate::game_state::{Direction, Elimination, EliminationCause, GameState, Position, Snake};
+use crate::rules::{GameBoard, GameMode, Settings, SNAKE_MAX_HEALTH};
+use rand::Rng;
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitOrAssign, Not};
+use std::sync::Arc;
+
+/// The most snakes a bitboard state can hold.
+pub const MAX_SNAKES: usize = 8;
+
+/// Body cell of a segment that has left the board.
+const OFF_BOARD: u16 = u16::MAX;
+
+/// A set of board cells, one bit per cell index.
+pub trait BitSet:
+    Copy
+    + Default
+    + PartialEq
+    + fmt::Debug
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitOrAssign
+    + Not<Output = Self>
+    + Send
+    + Sync
+{
+    /// The number of cells the set can hold.
+    const CAPACITY: usize;
+
+    /// The set holding cells `0..len`.
+    fn first(len: usize) -> Self;
+    fn get(&self, index: usize) -> bool;
+    fn set(&mut self, index: usize);
+    fn clear(&mut self, index: usize);
+    fn count(&self) -> usize;
+    fn is_empty(&self) -> bool;
+    /// The cells in the set, lowest first.
+    fn ones(self) -> impl Iterator<Item = usize>;
+}
+
+impl BitSet for u128 {
+    const CAPACITY: usize = 128;
+
+    fn first(len: usize) -> Self {
+        if len >= 128 {
+            u128::MAX
+        } else {
+            (1u128 << len) - 1
+        }
+    }
+
+    fn get(&self, index: usize) -> bool {
+        index < 128 && (self >> index) & 1 == 1
+    }
+
+    fn set(&mut self, index: usize) {
+        *self |= 1u128 << index;
+    }
+
+    fn clear(&mut self, index: usize) {
+        if index < 128 {
+            *self &= !(1u128 << index);
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.count_ones() as usize
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == 0
+    }
+
+    fn ones(self) -> impl Iterator<Item = usize> {
+        let mut bits = self;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let index = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            Some(index)
+        })
+    }
+}
+
+/// A 384 cell bitboard, enough for a 19x19 board.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WideBits([u128; 3]);
+
+impl BitAnd for WideBits {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        WideBits([
+            self.0[0] & rhs.0[0],
+            self.0[1] & rhs.0[1],
+            self.0[2] & rhs.0[2],
+        ])
+    }
+}
+
+impl BitOr for WideBits {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        WideBits([
+            self.0[0] | rhs.0[0],
+            self.0[1] | rhs.0[1],
+            self.0[2] | rhs.0[2],
+        ])
+    }
+}
+
+impl BitOrAssign for WideBits {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = *self | rhs;
+    }
+}
+
+impl Not for WideBits {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        WideBits([!self.0[0], !self.0[1], !self.0[2]])
+    }
+}
+
+impl BitSet for WideBits {
+    const CAPACITY: usize = 384;
+
+    fn first(len: usize) -> Self {
+        let mut words = [0u128; 3];
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = u128::first(len.saturating_sub(i * 128));
+        }
+        WideBits(words)
+    }
+
+    fn get(&self, index: usize) -> bool {
+        index < Self::CAPACITY && self.0[index / 128].get(index % 128)
+    }
+
+    fn set(&mut self, index: usize) {
+        self.0[index / 128].set(index % 128);
+    }
+
+    fn clear(&mut self, index: usize) {
+        if index < Self::CAPACITY {
+            self.0[index / 128].clear(index % 128);
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.0.iter().map(|word| word.count()).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(|word| *word == 0)
+    }
+
+    fn ones(self) -> impl Iterator<Item = usize> {
+        self.0
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, word)| word.ones().map(move |index| i * 128 + index))
+    }
+}
+
+/// A snake body as a ring buffer of cell indices, head first.
+#[derive(Debug, Clone, Copy)]
+struct Body<const N: usize> {
+    cells: [u16; N],
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> Body<N> {
+    fn new() -> Self {
+        Body {
+            cells: [0; N],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    fn get(&self, i: usize) -> Option<u16> {
+        (i < self.len).then(|| self.cells[(self.start + i) % N])
+    }
+
+    fn head(&self) -> u16 {
+        self.get(0).unwrap_or(OFF_BOARD)
+    }
+
+    fn tail(&self) -> Option<u16> {
+        self.len.checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u16> + '_ {
+        (0..self.len).map(|i| self.cells[(self.start + i) % N])
+    }
+
+    /// Returns false, leaving the body as it is, if it is full.
+    fn push_front(&mut self, cell: u16) -> bool {
+        if self.len == N {
+            return false;
+        }
+        self.start = (self.start + N - 1) % N;
+        self.cells[self.start] = cell;
+        self.len += 1;
+        true
+    }
+
+    /// Returns false, leaving the body as it is, if it is full.
+    fn push_back(&mut self, cell: u16) -> bool {
+        if self.len == N {
+            return false;
+        }
+        self.cells[(self.start + self.len) % N] = cell;
+        self.len += 1;
+        true
+    }
+
+    fn pop_back(&mut self) -> Option<u16> {
+        let tail = self.tail()?;
+        self.len -= 1;
+        Some(tail)
+    }
+}
+
+/// `Elimination` with the responsible snake stored by index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitElimination {
+    pub cause: EliminationCause,
+    pub by: Option<u8>,
+    pub turn: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BitSnake<B: BitSet, const N: usize> {
+    body: Body<N>,
+    /// Every body cell except the head, so that a head landing on a set bit
+    /// is a body collision.
+    occupied: B,
+    health: u8,
+    eliminated: Option<BitElimination>,
+    /// Snakes with the same value are squadmates.
+    squad: u8,
+}
+
+impl<B: BitSet, const N: usize> BitSnake<B, N> {
+    fn new() -> Self {
+        BitSnake {
+            body: Body::new(),
+            occupied: B::default(),
+            health: 0,
+            eliminated: None,
+            squad: 0,
+        }
+    }
+
+    fn head(&self) -> u16 {
+        self.body.head()
+    }
+
+    fn length(&self) -> usize {
+        self.body.len
+    }
+
+    fn is_eliminated(&self) -> bool {
+        self.eliminated.is_some()
+    }
+
+    /// Adds a copy of the tail, as snakes grow when they eat. Returns false
+    /// if the body has no room for it.
+    fn grow(&mut self) -> bool {
+        let Some(tail) = self.body.tail() else {
+            return true;
+        };
+        if !self.body.push_back(tail) {
+            return false;
+        }
+        if tail != OFF_BOARD {
+            self.occupied.set(tail as usize);
+        }
+        true
+    }
+}
+
+/// The ids and squad names that only matter when converting back.
+#[derive(Debug, Default)]
+struct SnakeNames {
+    ids: Vec<String>,
+    squads: Vec<Option<String>>,
+}
+
+/// Why a `GameState` can't be represented as a bitboard state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitboardError {
+    BoardTooLarge { width: usize, height: usize },
+    TooManySnakes(usize),
+    SnakeTooLong { id: String, length: usize },
+}
+
+impl fmt::Display for BitboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitboardError::BoardTooLarge { width, height } => {
+                write!(f, "{}x{} board does not fit in the bitboard", width, height)
+            }
+            BitboardError::TooManySnakes(count) => {
+                write!(f, "{} snakes, at most {} supported", count, MAX_SNAKES)
+            }
+            BitboardError::SnakeTooLong { id, length } => {
+                write!(f, "snake {} has {} segments, too many to store", id, length)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BitboardError {}
+
+/// Game state with up to `B::CAPACITY` cells and bodies of up to `N`
+/// segments.
+#[derive(Debug, Clone)]
+pub struct BitboardState<B: BitSet, const N: usize> {
+    pub width: usize,
+    pub height: usize,
+    pub turn: u32,
+    pub mode: GameMode,
+    pub settings: Settings,
+    snakes: [BitSnake<B, N>; MAX_SNAKES],
+    num_snakes: usize,
+    food: B,
+    hazards: B,
+    /// Hazards stacked on each cell; most boards only ever have 0 or 1.
+    hazard_counts: [u8; N],
+    /// A snake that grew past `N` segments this turn.
+    overflowed: Option<usize>,
+    names: Arc<SnakeNames>,
+}
+
+/// Boards up to 11x11.
+pub type Bitboard11 = BitboardState<u128, 128>;
+
+/// Boards up to 19x19.
+pub type Bitboard19 = BitboardState<WideBits, 384>;
+
+impl<B: BitSet, const N: usize> BitboardState<B, N> {
+    pub fn from_game_state(game_state: &GameState) -> Result<Self, BitboardError> {
+        let cells = game_state.width * game_state.height;
+        if cells > B::CAPACITY || cells > N {
+            return Err(BitboardError::BoardTooLarge {
+                width: game_state.width,
+                height: game_state.height,
+            });
+        }
+        if game_state.snakes.len() > MAX_SNAKES {
+            return Err(BitboardError::TooManySnakes(game_state.snakes.len()));
+        }
+
+        let mut state = BitboardState {
+            width: game_state.width,
+            height: game_state.height,
+            turn: game_state.turn,
+            mode: game_state.mode,
+            settings: game_state.settings.clone(),
+            snakes: [BitSnake::new(); MAX_SNAKES],
+            num_snakes: game_state.snakes.len(),
+            food: B::default(),
+            hazards: B::default(),
+            hazard_counts: [0; N],
+            overflowed: None,
+            names: Arc::new(SnakeNames {
+                ids: game_state.snakes.iter().map(|s| s.id.clone()).collect(),
+                squads: game_state.snakes.iter().map(|s| s.squad.clone()).collect(),
+            }),
+        };
+
+        let cell = |index: usize| {
+            if index < cells {
+                index as u16
+            } else {
+                OFF_BOARD
+            }
+        };
+
+        for (i, snake) in game_state.snakes.iter().enumerate() {
+            if snake.length() > N {
+                return Err(BitboardError::SnakeTooLong {
+                    id: snake.id.clone(),
+                    length: snake.length(),
+                });
+            }
+
+            let bit_snake = &mut state.snakes[i];
+            for position in &snake.body {
+                bit_snake.body.push_back(cell(position.index));
+            }
+            for segment in bit_snake.body.iter().skip(1) {
+                if segment != OFF_BOARD {
+                    bit_snake.occupied.set(segment as usize);
+                }
+            }
+            bit_snake.health = snake.health;
+            bit_snake.squad = match &snake.squad {
+                Some(squad) => game_state
+                    .snakes
+                    .iter()
+                    .position(|other| other.squad.as_ref() == Some(squad))
+                    .unwrap_or(i) as u8,
+                None => i as u8,
+            };
+            bit_snake.eliminated = snake.eliminated.as_ref().map(|elimination| BitElimination {
+                cause: elimination.cause,
+                by: elimination.by.as_ref().and_then(|by| {
+                    game_state
+                        .snakes
+                        .iter()
+                        .position(|other| &other.id == by)
+                        .map(|index| index as u8)
+                }),
+                turn: elimination.turn,
+            });
+        }
+
+        for food in game_state.food.iter().filter(|p| p.index < cells) {
+            state.food.set(food.index);
+        }
+        for hazard in game_state.hazards.iter().filter(|p| p.index < cells) {
+            state.add_hazard(hazard.index);
+        }
+
+        Ok(state)
+    }
+
+    pub fn to_game_state(&self) -> GameState {
+        let index = |cell: u16| {
+            if cell == OFF_BOARD {
+                usize::MAX
+            } else {
+                cell as usize
+            }
+        };
+
+        let snakes = self.snakes[..self.num_snakes]
+            .iter()
+            .enumerate()
+            .map(|(i, snake)| Snake {
+                id: self.names.ids[i].clone(),
+                body: snake
+                    .body
+                    .iter()
+                    .map(|cell| Position { index: index(cell) })
+                    .collect::<VecDeque<_>>(),
+                health: snake.health,
+                eliminated: snake.eliminated.map(|elimination| Elimination {
+                    cause: elimination.cause,
+                    by: elimination.by.map(|by| self.names.ids[by as usize].clone()),
+                    turn: elimination.turn,
+                }),
+                squad: self.names.squads[i].clone(),
+            })
+            .collect();
+
+        let hazards = self
+            .hazards
+            .ones()
+            .flat_map(|cell| {
+                std::iter::repeat_n(Position { index: cell }, self.hazard_counts[cell] as usize)
+            })
+            .collect();
+
+        let mut game_state = GameState::new(self.width, self.height);
+        game_state.turn = self.turn;
+        game_state.mode = self.mode;
+        game_state.settings = self.settings.clone();
+        game_state.snakes = snakes;
+        game_state.food = self.food.ones().map(|index| Position { index }).collect();
+        game_state.hazards = hazards;
+        game_state.rehash();
+        game_state
+    }
+
+    pub fn snake_count(&self) -> usize {
+        self.num_snakes
+    }
+
+    pub fn snake_id(&self, snake_index: usize) -> &str {
+        &self.names.ids[snake_index]
+    }
+
+    /// The head's cell index, or `usize::MAX` if it has left the board.
+    pub fn head(&self, snake_index: usize) -> usize {
+        match self.snakes[snake_index].head() {
+            OFF_BOARD => usize::MAX,
+            cell => cell as usize,
+        }
+    }
+
+    pub fn health(&self, snake_index: usize) -> u8 {
+        self.snakes[snake_index].health
+    }
+
+    pub fn length(&self, snake_index: usize) -> usize {
+        self.snakes[snake_index].length()
+    }
+
+    pub fn elimination(&self, snake_index: usize) -> Option<BitElimination> {
+        self.snakes[snake_index].eliminated
+    }
+
+    pub fn is_food(&self, index: usize) -> bool {
+        self.food.get(index)
+    }
+
+    pub fn is_hazard(&self, index: usize) -> bool {
+        self.hazards.get(index)
+    }
+
+    pub fn add_food(&mut self, index: usize) {
+        self.food.set(index);
+    }
+
+    pub fn add_hazard(&mut self, index: usize) {
+        self.hazards.set(index);
+        self.hazard_counts[index] = self.hazard_counts[index].saturating_add(1);
+    }
+
+    /// Cells covered by any snake still in the game, heads included.
+    pub fn occupied(&self) -> B {
+        let mut occupied = B::default();
+        for snake in self.alive() {
+            occupied |= snake.occupied;
+            if snake.head() != OFF_BOARD {
+                occupied.set(snake.head() as usize);
+            }
+        }
+        occupied
+    }
+
+    /// Applies the movement stage of the rules to a single snake, as
+    /// `GameState::move_snake` does.
+    pub fn move_snake(&mut self, snake_index: usize, direction: Direction) {
+        if snake_index >= self.num_snakes {
+            return;
+        }
+
+        let head = self.snakes[snake_index].head();
+        let snake = &self.snakes[snake_index];
+        if snake.is_eliminated() || snake.health == 0 || head == OFF_BOARD {
+            return;
+        }
+
+        let new_head = match self.neighbour(head as usize, direction) {
+            usize::MAX => OFF_BOARD,
+            index => index as u16,
+        };
+
+        let snake = &mut self.snakes[snake_index];
+        if let Some(tail) = snake.body.pop_back() {
+            // Stacked tail segments keep the cell occupied
+            if snake.body.tail() != Some(tail) && tail != OFF_BOARD {
+                snake.occupied.clear(tail as usize);
+            }
+        }
+        // Popping the tail made room for the new head
+        snake.body.push_front(new_head);
+        if snake.body.len >= 2 {
+            snake.occupied.set(head as usize);
+        }
+    }
+
+    /// Runs every stage after movement under the game's ruleset.
+    ///
+    /// Fails if a snake grew past `N` segments, leaving the state part way
+    /// through the turn.
+    pub fn resolve_collisions(&mut self) -> Result<(), BitboardError> {
+        self.mode.ruleset().resolve_turn(self);
+        self.check_overflow()
+    }
+
+    /// Plays a full turn. `moves` holds one direction per snake index; a
+    /// snake without one uses its default move.
+    ///
+    /// Fails as `resolve_collisions` does.
+    pub fn step(&mut self, moves: &[Direction]) -> Result<(), BitboardError> {
+        self.mode.ruleset().step(self, moves);
+        self.check_overflow()
+    }
+
+    fn check_overflow(&mut self) -> Result<(), BitboardError> {
+        match self.overflowed.take() {
+            Some(snake_index) => Err(BitboardError::SnakeTooLong {
+                id: self.snake_id(snake_index).to_string(),
+                length: N + 1,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// See `GameState::default_move`.
+    pub fn default_move(&self, snake_index: usize) -> Direction {
+        let body = &self.snakes[snake_index].body;
+        let head = body.head();
+        if let Some(neck) = body.get(1) {
+            if neck != OFF_BOARD && neck != head {
+                for direction in Direction::ALL {
+                    if self.neighbour(neck as usize, direction) == head as usize {
+                        return direction;
+                    }
+                }
+            }
+        }
+        Direction::Up
+    }
+
+    /// Whether the game has finished under its ruleset.
+    pub fn is_game_over(&self) -> bool {
+        self.mode.ruleset().is_game_over(self)
+    }
+
+    /// The index reached by moving from `index` in `direction`, or
+    /// `usize::MAX` if that leaves the board.
+    pub fn neighbour(&self, index: usize, direction: Direction) -> usize {
+        self.mode.ruleset().neighbour(self, index, direction)
+    }
+
+    /// See `GameState::get_safe_moves`; yields moves without allocating.
+    pub fn safe_moves(&self, snake_index: usize) -> impl Iterator<Item = Direction> + '_ {
+        let snake = &self.snakes[snake_index];
+        let head = snake.head();
+        let neck = snake.body.get(1);
+        let can_move = snake_index < self.num_snakes && snake.health > 0 && head != OFF_BOARD;
+
+        Direction::ALL.into_iter().filter(move |&direction| {
+            if !can_move {
+                return false;
+            }
+            let next = self.neighbour(head as usize, direction);
+            next != usize::MAX && neck != Some(next as u16)
+        })
+    }
+
+    fn alive(&self) -> impl Iterator<Item = &BitSnake<B, N>> {
+        self.snakes[..self.num_snakes]
+            .iter()
+            .filter(|s| !s.is_eliminated())
+    }
+
+    fn eliminate_snakes_where(&mut self, can_collide: impl Fn(usize, usize) -> bool) {
+        let next_turn = self.turn + 1;
+        let num_snakes = self.num_snakes;
+
+        // Longest first, so that multi-snake collisions are attributed to the
+        // longest snake.
+        let mut by_length = [0usize; MAX_SNAKES];
+        for (i, slot) in by_length.iter_mut().enumerate() {
+            *slot = i;
+        }
+        let by_length = &mut by_length[..num_snakes];
+        by_length.sort_unstable_by_key(|&i| (Reverse(self.snakes[i].length()), i));
+
+        for snake in self.snakes[..num_snakes].iter_mut() {
+            if snake.is_eliminated() {
+                continue;
+            }
+            let cause = if snake.health == 0 {
+                EliminationCause::OutOfHealth
+            } else if snake.head() == OFF_BOARD {
+                EliminationCause::OutOfBounds
+            } else {
+                continue;
+            };
+            snake.eliminated = Some(BitElimination {
+                cause,
+                by: None,
+                turn: next_turn,
+            });
+        }
+
+        let mut collisions = [(0usize, EliminationCause::Collision, 0usize); MAX_SNAKES];
+        let mut num_collisions = 0;
+
+        for i in 0..num_snakes {
+            let snake = &self.snakes[i];
+            if snake.is_eliminated() {
+                continue;
+            }
+            let head = snake.head() as usize;
+
+            let collision = if snake.occupied.get(head) {
+                Some((EliminationCause::SelfCollision, i))
+            } else if let Some(&other) = by_length.iter().find(|&&other| {
+                let other_snake = &self.snakes[other];
+                other != i
+                    && !other_snake.is_eliminated()
+                    && can_collide(i, other)
+                    && other_snake.occupied.get(head)
+            }) {
+                Some((EliminationCause::Collision, other))
+            } else {
+                by_length
+                    .iter()
+                    .find(|&&other| {
+                        let other_snake = &self.snakes[other];
+                        other != i
+                            && !other_snake.is_eliminated()
+                            && other_snake.head() as usize == head
+                            && snake.length() <= other_snake.length()
+                    })
+                    .map(|&other| (EliminationCause::HeadToHeadCollision, other))
+            };
+
+            if let Some((cause, by)) = collision {
+                collisions[num_collisions] = (i, cause, by);
+                num_collisions += 1;
+            }
+        }
+
+        for &(i, cause, by) in &collisions[..num_collisions] {
+            self.snakes[i].eliminated = Some(BitElimination {
+                cause,
+                by: Some(by as u8),
+                turn: next_turn,
+            });
+        }
+
+        for snake in self.snakes[..num_snakes].iter_mut() {
+            if snake.is_eliminated() {
+                snake.health = 0;
+            }
+        }
+    }
+}
+
+impl<B: BitSet, const N: usize> GameBoard for BitboardState<B, N> {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    fn alive_snakes(&self) -> usize {
+        self.snakes[..self.num_snakes]
+            .iter()
+            .filter(|s| s.health > 0)
+            .count()
+    }
+
+    fn alive_squads(&self) -> usize {
+        let alive = &self.snakes[..self.num_snakes];
+        (0..alive.len())
+            .filter(|&i| {
+                alive[i].health > 0
+                    && !alive[..i]
+                        .iter()
+                        .any(|other| other.health > 0 && other.squad == alive[i].squad)
+            })
+            .count()
+    }
+
+    fn move_snakes(&mut self, moves: &[Direction]) {
+        for i in 0..self.num_snakes {
+            let direction = moves
+                .get(i)
+                .copied()
+                .unwrap_or_else(|| self.default_move(i));
+            self.move_snake(i, direction);
+        }
+    }
+
+    fn reduce_snake_health(&mut self) {
+        for snake in self.snakes[..self.num_snakes].iter_mut() {
+            if !snake.is_eliminated() {
+                snake.health = snake.health.saturating_sub(1);
+            }
+        }
+    }
+
+    fn damage_hazards(&mut self) {
+        let next_turn = self.turn + 1;
+        let damage = self.settings.hazard_damage_per_turn;
+
+        for snake in self.snakes[..self.num_snakes].iter_mut() {
+            let head = snake.head() as usize;
+            if snake.is_eliminated() || !self.hazards.get(head) || self.food.get(head) {
+                continue;
+            }
+
+            for _ in 0..self.hazard_counts[head] {
+                snake.health = snake.health.saturating_sub(damage);
+                if snake.health == 0 {
+                    snake.eliminated = Some(BitElimination {
+                        cause: EliminationCause::Hazard,
+                        by: None,
+                        turn: next_turn,
+                    });
+                    break;
+                }
+            }
+        }
+    }
+
+    fn feed_snakes(&mut self) {
+        let mut eaten = B::default();
+
+        for (i, snake) in self.snakes[..self.num_snakes].iter_mut().enumerate() {
+            let head = snake.head() as usize;
+            if snake.is_eliminated() || !self.food.get(head) {
+                continue;
+            }
+            if !snake.grow() {
+                self.overflowed = Some(i);
+            }
+            snake.health = SNAKE_MAX_HEALTH;
+            eaten.set(head);
+        }
+
+        self.food = self.food & !eaten;
+    }
+
+    fn eliminate_snakes(&mut self, squadmates_collide: bool) {
+        if squadmates_collide {
+            self.eliminate_snakes_where(|_, _| true);
+        } else {
+            let squads = self.snakes.map(|snake| snake.squad);
+            self.eliminate_snakes_where(|snake, other| squads[snake] != squads[other]);
+        }
+    }
+
+    fn spawn_food(&mut self) {
+        let current_food = self.food.count();
+        let minimum_food = self.settings.minimum_food;
+        let spawn_chance = self.settings.food_spawn_chance;
+        let mut rng = rand::thread_rng();
+
+        let to_spawn = if current_food < minimum_food {
+            minimum_food - current_food
+        } else if spawn_chance > 0 && rng.gen_range(0..100) < spawn_chance {
+            1
+        } else {
+            0
+        };
+        if to_spawn == 0 {
+            return;
+        }
+
+        let mut free = B::first(self.width * self.height) & !(self.occupied() | self.food);
+        for _ in 0..to_spawn {
+            let free_count = free.count();
+            if free_count == 0 {
+                break;
+            }
+            if let Some(index) = free.ones().nth(rng.gen_range(0..free_count)) {
+                self.food.set(index);
+                free.clear(index);
+            }
+        }
+    }
+
+    fn clear_food(&mut self) {
+        self.food = B::default();
+    }
+
+    fn grow_snakes(&mut self) {
+        for (i, snake) in self.snakes[..self.num_snakes].iter_mut().enumerate() {
+            if !snake.is_eliminated() {
+                if !snake.grow() {
+                    self.overflowed = Some(i);
+                }
+                snake.health = SNAKE_MAX_HEALTH;
+            }
+        }
+    }
+
+    fn shrink_safe_area(&mut self) {
+        let shrink_every_n_turns = self.settings.royale.shrink_every_n_turns;
+        if shrink_every_n_turns == 0
+            || self.turn == 0
+            || !self.turn.is_multiple_of(shrink_every_n_turns)
+        {
+            return;
+        }
+
+        let width = self.width;
+        let safe = B::first(width * self.height) & !self.hazards;
+        if safe.is_empty() {
+            return;
+        }
+
+        // Bounding box of the cells that are still safe
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = (isize::MAX, -1, isize::MAX, -1);
+        for i in safe.ones() {
+            let (x, y) = ((i % width) as isize, (i / width) as isize);
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+            min_y = min_y.min(y);
+            max_y = max_y.max(y);
+        }
+
+        match rand::thread_rng().gen_range(0..4) {
+            0 => min_x += 1,
+            1 => max_x -= 1,
+            2 => min_y += 1,
+            _ => max_y -= 1,
+        }
+
+        for i in safe.ones() {
+            let (x, y) = ((i % width) as isize, (i / width) as isize);
+            if x < min_x || x > max_x || y < min_y || y > max_y {
+                self.add_hazard(i);
+            }
+        }
+    }
+
+    fn share_squad_attributes(&mut self) {
+        let next_turn = self.turn + 1;
+        let squad_settings = self.settings.squad.clone();
+        let num_snakes = self.num_snakes;
+        let snakes = self.snakes;
+        let squad = |i: usize| {
+            snakes[..num_snakes]
+                .iter()
+                .filter(move |s| s.squad == snakes[i].squad)
+        };
+
+        for (i, snake) in self.snakes[..num_snakes].iter_mut().enumerate() {
+            if snake.is_eliminated() {
+                continue;
+            }
+
+            if squad_settings.shared_elimination && squad(i).any(|s| s.is_eliminated()) {
+                snake.eliminated = Some(BitElimination {
+                    cause: EliminationCause::Squad,
+                    by: None,
+                    turn: next_turn,
+                });
+                snake.health = 0;
+                continue;
+            }
+
+            let (health, length) = squad(i)
+                .filter(|s| !s.is_eliminated())
+                .fold((0, 0), |(health, length), s| {
+                    (health.max(s.health), length.max(s.length()))
+                });
+            if squad_settings.shared_health {
+                snake.health = health;
+            }
+            while squad_settings.shared_length && snake.length() < length {
+                if !snake.grow() {
+                    self.overflowed = Some(i);
+                    break;
+                }
+            }
+        }
+    }
+
+    fn advance_turn(&mut self) {
+        self.turn += 1;
+    }
+}
+
+impl<B: BitSet, const N: usize> TryFrom<&GameState> for BitboardState<B, N> {
+    type Error = BitboardError;
+
+    fn try_from(game_state: &GameState) -> Result<Self, Self::Error> {
+        Self::from_game_state(game_state)
+    }
+}
+
+impl<B: BitSet, const N: usize> From<&BitboardState<B, N>> for GameState {
+    fn from(state: &BitboardState<B, N>) -> Self {
+        state.to_game_state()
+    }
+}
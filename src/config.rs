@@ -0,0 +1,582 @@
+//! Search settings, loaded from a TOML or JSON file and environment
+//! variables.
+//!
+//! `SearchConfig::load` starts from the defaults, replaces them with the file
+//! named by `SEARCH_CONFIG` if it is set, then applies any of the individual
+//! variables below on top:
+//!
+//! | Variable                        | Field                           |
+//! |---------------------------------|---------------------------------|
+//! | `SEARCH_ENGINE`                 | `engine`                        |
+//! | `SEARCH_MODE`                   | `mode`                          |
+//! | `SEARCH_EXPLORATION_CONSTANT`   | `exploration_constant`          |
+//! | `SEARCH_FINAL_MOVE`             | `final_move`                    |
+//! | `SEARCH_PARALLELISM`            | `parallelism`                   |
+//! | `SEARCH_THREADS`                | `threads`                       |
+//! | `SEARCH_HEURISTIC`              | `heuristic`                     |
+//! | `CONTROL_FOOD_AWARE`            | `control.food_aware`            |
+//! | `CONTROL_RULE`                  | `control.rule`                  |
+//! | `COMPOSITE_TERRITORY_WEIGHT`    | `composite.territory`           |
+//! | `COMPOSITE_LENGTH_WEIGHT`       | `composite.length`              |
+//! | `COMPOSITE_HEALTH_WEIGHT`       | `composite.health`              |
+//! | `COMPOSITE_FOOD_WEIGHT`         | `composite.food`                |
+//! | `COMPOSITE_HAZARD_WEIGHT`       | `composite.hazard`              |
+//! | `NETWORK_WEIGHTS`               | `network.weights`               |
+//! | `SEARCH_MAX_NODES`              | `max_nodes`                     |
+//! | `SEARCH_MOVE_TIME_MS`           | `time.move_time_ms`             |
+//! | `TIME_SAFETY_MARGIN_MS`         | `time.safety_margin_ms`         |
+//! | `SEARCH_WIN_SCORE`              | `terminal.win`                  |
+//! | `SEARCH_LOSS_SCORE`             | `terminal.loss`                 |
+//! | `SEARCH_DRAW_SCORE`             | `terminal.draw`                 |
+//! | `ALPHA_BETA_MAX_DEPTH`          | `alpha_beta.max_depth`          |
+//! | `ALPHA_BETA_MULTIPLAYER`        | `alpha_beta.multiplayer`        |
+//! | `ALPHA_BETA_MAX_SNAKES`         | `alpha_beta.max_snakes`         |
+//! | `MATRIX_GAME_MAX_DEPTH`         | `matrix_game.max_depth`         |
+//! | `MATRIX_GAME_SOLVER_ITERATIONS` | `matrix_game.solver_iterations` |
+//!
+//! Enum values are written in kebab case, e.g. `SEARCH_PARALLELISM=root-parallel`.
+
+use crate::alphabeta::MultiplayerStrategy;
+use crate::game_state::GameState;
+use crate::heuristic::ControlOptions;
+use crate::search::{Parallelism, SearchMode};
+use crate::time_manager::TimeSettings;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Variable naming a config file to load before the other variables apply.
+pub const CONFIG_PATH_VAR: &str = "SEARCH_CONFIG";
+
+/// Which search picks the move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Engine {
+    /// `MCTS`, configured by the rest of `SearchConfig`.
+    #[default]
+    Mcts,
+    /// `AlphaBeta`, configured by `alpha_beta`.
+    AlphaBeta,
+    /// `AlphaBeta` once at most `alpha_beta.max_snakes` snakes are alive,
+    /// `MCTS` before then.
+    Auto,
+    /// `MatrixGameSearch`, configured by `matrix_game`, for duels; `MCTS`
+    /// with more snakes.
+    MatrixGame,
+}
+
+/// How the move to play is picked from the root statistics once the search
+/// stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FinalMovePolicy {
+    /// The most visited move.
+    #[default]
+    MaxVisits,
+    /// The visited move with the best mean score.
+    MaxMean,
+    /// The move that is both the most visited and the best by mean score,
+    /// or the most visited if those differ.
+    RobustMax,
+    /// The move with the best lower confidence bound: its mean score less
+    /// the exploration term UCB would add, so thinly searched moves need a
+    /// clearly better mean to be picked.
+    SecureChild,
+}
+
+/// Which evaluation scores the leaves of the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Heuristic {
+    /// Each snake's share of the board from `calculate_control_percentages`.
+    #[default]
+    Control,
+    /// `CompositeEvaluator`, weighted by `composite`.
+    Composite,
+    /// `NetworkEvaluator`, with the weights file named by `network`.
+    Network,
+}
+
+/// Results given to each snake at a finished game.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerminalScores {
+    /// The last snake standing.
+    pub win: f32,
+    /// Snakes that were eliminated while another survived.
+    pub loss: f32,
+    /// Every snake, when nobody is left or the game ends with several
+    /// survivors.
+    pub draw: f32,
+}
+
+impl Default for TerminalScores {
+    fn default() -> Self {
+        TerminalScores {
+            win: 1.0,
+            loss: 0.0,
+            draw: 0.0,
+        }
+    }
+}
+
+/// Weights of the terms a `CompositeEvaluator` adds up. Only their ratios
+/// matter; the score is their weighted mean.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompositeWeights {
+    pub territory: f32,
+    pub length: f32,
+    pub health: f32,
+    pub food: f32,
+    pub hazard: f32,
+}
+
+impl Default for CompositeWeights {
+    fn default() -> Self {
+        CompositeWeights {
+            territory: 1.0,
+            length: 0.5,
+            health: 0.25,
+            food: 0.25,
+            hazard: 0.25,
+        }
+    }
+}
+
+impl CompositeWeights {
+    pub fn total(&self) -> f32 {
+        self.territory + self.length + self.health + self.food + self.hazard
+    }
+}
+
+/// Settings for `Heuristic::Network`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// The JSON file holding the network's `NetworkWeights`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weights: Option<String>,
+}
+
+/// Settings for the `AlphaBeta` engine.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlphaBetaConfig {
+    /// The deepest iteration, in turns.
+    pub max_depth: u32,
+    /// How the other snakes reply once more than one of them is alive.
+    pub multiplayer: MultiplayerStrategy,
+    /// `Engine::Auto` switches to alpha-beta once at most this many snakes
+    /// are alive.
+    pub max_snakes: usize,
+}
+
+impl Default for AlphaBetaConfig {
+    fn default() -> Self {
+        AlphaBetaConfig {
+            max_depth: 64,
+            multiplayer: MultiplayerStrategy::default(),
+            max_snakes: 2,
+        }
+    }
+}
+
+/// Settings for the `MatrixGame` engine.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatrixGameConfig {
+    /// The deepest iteration, in turns.
+    pub max_depth: u32,
+    /// Regret matching rounds used to solve each position's matrix.
+    pub solver_iterations: u32,
+}
+
+impl Default for MatrixGameConfig {
+    fn default() -> Self {
+        MatrixGameConfig {
+            max_depth: 16,
+            solver_iterations: 200,
+        }
+    }
+}
+
+/// Time budget settings, in milliseconds. See `TimeSettings`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeConfig {
+    pub safety_margin_ms: u64,
+    pub min_budget_ms: u64,
+    pub soft_fraction: f32,
+    pub latency_smoothing: f32,
+    pub close_value_margin: f32,
+    pub min_search_time_ms: u64,
+    /// Search every move for exactly this long instead of budgeting from the
+    /// game's timeout.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub move_time_ms: Option<u64>,
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        let settings = TimeSettings::default();
+        TimeConfig {
+            safety_margin_ms: settings.safety_margin.as_millis() as u64,
+            min_budget_ms: settings.min_budget.as_millis() as u64,
+            soft_fraction: settings.soft_fraction,
+            latency_smoothing: settings.latency_smoothing,
+            close_value_margin: settings.close_value_margin,
+            min_search_time_ms: settings.min_search_time.as_millis() as u64,
+            move_time_ms: None,
+        }
+    }
+}
+
+impl TimeConfig {
+    pub fn settings(&self) -> TimeSettings {
+        TimeSettings {
+            safety_margin: Duration::from_millis(self.safety_margin_ms),
+            min_budget: Duration::from_millis(self.min_budget_ms),
+            soft_fraction: self.soft_fraction,
+            latency_smoothing: self.latency_smoothing,
+            close_value_margin: self.close_value_margin,
+            min_search_time: Duration::from_millis(self.min_search_time_ms),
+        }
+    }
+
+    /// The fixed time per move, if one is set.
+    pub fn move_time(&self) -> Option<Duration> {
+        self.move_time_ms.map(Duration::from_millis)
+    }
+}
+
+/// Everything that shapes a search. Missing fields take their defaults, so a
+/// config file only needs the settings it changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    pub engine: Engine,
+    pub mode: SearchMode,
+    /// The UCB exploration constant.
+    pub exploration_constant: f32,
+    pub final_move: FinalMovePolicy,
+    pub parallelism: Parallelism,
+    /// Search threads; all available cores if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threads: Option<usize>,
+    pub heuristic: Heuristic,
+    /// How board control is worked out for the heuristics.
+    pub control: ControlOptions,
+    /// Used when `heuristic` is `Heuristic::Composite`.
+    pub composite: CompositeWeights,
+    /// Used when `heuristic` is `Heuristic::Network`.
+    pub network: NetworkConfig,
+    /// The search stops once it has created this many nodes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_nodes: Option<u64>,
+    pub terminal: TerminalScores,
+    pub time: TimeConfig,
+    pub alpha_beta: AlphaBetaConfig,
+    pub matrix_game: MatrixGameConfig,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            engine: Engine::default(),
+            mode: SearchMode::default(),
+            exploration_constant: 1.414,
+            final_move: FinalMovePolicy::default(),
+            parallelism: Parallelism::default(),
+            threads: None,
+            heuristic: Heuristic::default(),
+            control: ControlOptions::default(),
+            composite: CompositeWeights::default(),
+            network: NetworkConfig::default(),
+            max_nodes: None,
+            terminal: TerminalScores::default(),
+            time: TimeConfig::default(),
+            alpha_beta: AlphaBetaConfig::default(),
+            matrix_game: MatrixGameConfig::default(),
+        }
+    }
+}
+
+impl SearchConfig {
+    /// The defaults, or the file named by `SEARCH_CONFIG`, with any other
+    /// variables applied on top.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match std::env::var(CONFIG_PATH_VAR) {
+            Ok(path) => SearchConfig::from_file(path)?,
+            Err(_) => SearchConfig::default(),
+        };
+        config.apply_env(|name| std::env::var(name).ok())?;
+        Ok(config)
+    }
+
+    /// The defaults with environment variables applied.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut config = SearchConfig::default();
+        config.apply_env(|name| std::env::var(name).ok())?;
+        Ok(config)
+    }
+
+    /// Reads a TOML file, or a JSON one if its extension is `.json`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::Io(format!("{}: {}", path.display(), e)))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => SearchConfig::from_json(&contents),
+            _ => SearchConfig::from_toml(&contents),
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, ConfigError> {
+        let config: SearchConfig =
+            toml::from_str(contents).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self, ConfigError> {
+        let config: SearchConfig =
+            serde_json::from_str(contents).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the values that parse but can't be searched with. Scores are
+    /// backed up as unsigned fixed point, so terminal scores must lie in
+    /// `0.0..=1.0` like the heuristic's.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(self.exploration_constant.is_finite() && self.exploration_constant >= 0.0) {
+            return Err(ConfigError::Invalid(format!(
+                "exploration_constant must be a non-negative number, got {}",
+                self.exploration_constant
+            )));
+        }
+        let terminal = [
+            ("win", self.terminal.win),
+            ("loss", self.terminal.loss),
+            ("draw", self.terminal.draw),
+        ];
+        for (name, score) in terminal {
+            if !(0.0..=1.0).contains(&score) {
+                return Err(ConfigError::Invalid(format!(
+                    "terminal.{} must be between 0 and 1, got {}",
+                    name, score
+                )));
+            }
+        }
+        let weights = [
+            ("territory", self.composite.territory),
+            ("length", self.composite.length),
+            ("health", self.composite.health),
+            ("food", self.composite.food),
+            ("hazard", self.composite.hazard),
+        ];
+        for (name, weight) in weights {
+            if !(weight.is_finite() && weight >= 0.0) {
+                return Err(ConfigError::Invalid(format!(
+                    "composite.{} must be a non-negative number, got {}",
+                    name, weight
+                )));
+            }
+        }
+        if self.composite.total() <= 0.0 {
+            return Err(ConfigError::Invalid(
+                "composite weights must not all be 0".to_string(),
+            ));
+        }
+        if self.heuristic == Heuristic::Network && self.network.weights.is_none() {
+            return Err(ConfigError::Invalid(
+                "the network heuristic needs network.weights".to_string(),
+            ));
+        }
+        if self.alpha_beta.max_depth == 0 {
+            return Err(ConfigError::Invalid(
+                "alpha_beta.max_depth must be at least 1".to_string(),
+            ));
+        }
+        if self.matrix_game.max_depth == 0 || self.matrix_game.solver_iterations == 0 {
+            return Err(ConfigError::Invalid(
+                "matrix_game.max_depth and solver_iterations must be at least 1".to_string(),
+            ));
+        }
+        if self.threads == Some(0) {
+            return Err(ConfigError::Invalid(
+                "threads must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Overrides fields from the variables listed in the module docs, looked
+    /// up with `var`.
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        let var = |name: &str| var(name).filter(|value| !value.trim().is_empty());
+
+        if let Some(value) = var("SEARCH_ENGINE") {
+            self.engine = parse_enum("SEARCH_ENGINE", &value)?;
+        }
+        if let Some(value) = var("SEARCH_MODE") {
+            self.mode = parse_enum("SEARCH_MODE", &value)?;
+        }
+        if let Some(value) = var("SEARCH_EXPLORATION_CONSTANT") {
+            self.exploration_constant = parse_value("SEARCH_EXPLORATION_CONSTANT", &value)?;
+        }
+        if let Some(value) = var("SEARCH_FINAL_MOVE") {
+            self.final_move = parse_enum("SEARCH_FINAL_MOVE", &value)?;
+        }
+        if let Some(value) = var("SEARCH_PARALLELISM") {
+            self.parallelism = parse_enum("SEARCH_PARALLELISM", &value)?;
+        }
+        if let Some(value) = var("SEARCH_THREADS") {
+            self.threads = Some(parse_value("SEARCH_THREADS", &value)?);
+        }
+        if let Some(value) = var("SEARCH_HEURISTIC") {
+            self.heuristic = parse_enum("SEARCH_HEURISTIC", &value)?;
+        }
+        if let Some(value) = var("CONTROL_FOOD_AWARE") {
+            self.control.food_aware = parse_value("CONTROL_FOOD_AWARE", &value)?;
+        }
+        if let Some(value) = var("CONTROL_RULE") {
+            self.control.rule = parse_enum("CONTROL_RULE", &value)?;
+        }
+        if let Some(value) = var("COMPOSITE_TERRITORY_WEIGHT") {
+            self.composite.territory = parse_value("COMPOSITE_TERRITORY_WEIGHT", &value)?;
+        }
+        if let Some(value) = var("COMPOSITE_LENGTH_WEIGHT") {
+            self.composite.length = parse_value("COMPOSITE_LENGTH_WEIGHT", &value)?;
+        }
+        if let Some(value) = var("COMPOSITE_HEALTH_WEIGHT") {
+            self.composite.health = parse_value("COMPOSITE_HEALTH_WEIGHT", &value)?;
+        }
+        if let Some(value) = var("COMPOSITE_FOOD_WEIGHT") {
+            self.composite.food = parse_value("COMPOSITE_FOOD_WEIGHT", &value)?;
+        }
+        if let Some(value) = var("COMPOSITE_HAZARD_WEIGHT") {
+            self.composite.hazard = parse_value("COMPOSITE_HAZARD_WEIGHT", &value)?;
+        }
+        if let Some(value) = var("NETWORK_WEIGHTS") {
+            self.network.weights = Some(value);
+        }
+        if let Some(value) = var("SEARCH_MAX_NODES") {
+            self.max_nodes = Some(parse_value("SEARCH_MAX_NODES", &value)?);
+        }
+        if let Some(value) = var("SEARCH_MOVE_TIME_MS") {
+            self.time.move_time_ms = Some(parse_value("SEARCH_MOVE_TIME_MS", &value)?);
+        }
+        if let Some(value) = var("TIME_SAFETY_MARGIN_MS") {
+            self.time.safety_margin_ms = parse_value("TIME_SAFETY_MARGIN_MS", &value)?;
+        }
+        if let Some(value) = var("SEARCH_WIN_SCORE") {
+            self.terminal.win = parse_value("SEARCH_WIN_SCORE", &value)?;
+        }
+        if let Some(value) = var("SEARCH_LOSS_SCORE") {
+            self.terminal.loss = parse_value("SEARCH_LOSS_SCORE", &value)?;
+        }
+        if let Some(value) = var("SEARCH_DRAW_SCORE") {
+            self.terminal.draw = parse_value("SEARCH_DRAW_SCORE", &value)?;
+        }
+        if let Some(value) = var("ALPHA_BETA_MAX_DEPTH") {
+            self.alpha_beta.max_depth = parse_value("ALPHA_BETA_MAX_DEPTH", &value)?;
+        }
+        if let Some(value) = var("ALPHA_BETA_MULTIPLAYER") {
+            self.alpha_beta.multiplayer = parse_enum("ALPHA_BETA_MULTIPLAYER", &value)?;
+        }
+        if let Some(value) = var("ALPHA_BETA_MAX_SNAKES") {
+            self.alpha_beta.max_snakes = parse_value("ALPHA_BETA_MAX_SNAKES", &value)?;
+        }
+        if let Some(value) = var("MATRIX_GAME_MAX_DEPTH") {
+            self.matrix_game.max_depth = parse_value("MATRIX_GAME_MAX_DEPTH", &value)?;
+        }
+        if let Some(value) = var("MATRIX_GAME_SOLVER_ITERATIONS") {
+            self.matrix_game.solver_iterations =
+                parse_value("MATRIX_GAME_SOLVER_ITERATIONS", &value)?;
+        }
+        self.validate()
+    }
+
+    /// The number of search threads to use.
+    pub fn num_threads(&self) -> usize {
+        self.threads.unwrap_or_else(num_cpus::get)
+    }
+
+    /// The engine to search `state` with: `Engine::Auto` resolved, and
+    /// `Engine::MatrixGame` only for duels.
+    pub fn engine_for(&self, state: &GameState) -> Engine {
+        let alive = state.snakes.iter().filter(|s| s.health > 0).count();
+        match self.engine {
+            Engine::MatrixGame if alive != 2 => Engine::Mcts,
+            Engine::Auto => {
+                if alive <= self.alpha_beta.max_snakes {
+                    Engine::AlphaBeta
+                } else {
+                    Engine::Mcts
+                }
+            }
+            engine => engine,
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("search configs always serialise")
+    }
+}
+
+/// Prints the config as the TOML that would load it.
+impl fmt::Display for SearchConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_toml())
+    }
+}
+
+fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T, ConfigError> {
+    value.trim().parse().map_err(|_| ConfigError::Env {
+        name: name.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// Parses a kebab-case enum name, also accepting snake case and any
+/// capitalisation.
+fn parse_enum<T: DeserializeOwned>(name: &str, value: &str) -> Result<T, ConfigError> {
+    let normalized = value.trim().to_ascii_lowercase().replace('_', "-");
+    serde_json::from_value(serde_json::Value::String(normalized)).map_err(|_| ConfigError::Env {
+        name: name.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// Why a `SearchConfig` couldn't be loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// The config file couldn't be read.
+    Io(String),
+    /// The config file isn't a valid config.
+    Parse(String),
+    /// An environment variable has a value that doesn't fit its field.
+    Env { name: String, value: String },
+    /// A setting is out of range.
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "couldn't read search config: {}", e),
+            ConfigError::Parse(e) => write!(f, "invalid search config: {}", e),
+            ConfigError::Env { name, value } => {
+                write!(f, "invalid value for {}: {:?}", name, value)
+            }
+            ConfigError::Invalid(e) => write!(f, "invalid search config: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
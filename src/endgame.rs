@@ -0,0 +1,421 @@
+//! Exact survival for snakes sealed into a region of their own.
+//!
+//! Once no other snake can reach a snake, the board share from
+//! `calculate_snake_control` stops mattering: all that counts is how long
+//! the snake can keep moving. `longest_survival` answers that with a
+//! depth-first search over the snake's own moves, tracking its body so that
+//! squares free up as the tail moves on, and food, hazards and health as the
+//! rules do. The search stops as soon as a path reaches the horizon, and
+//! abandons lines that can't outlast the best path found so far.
+//!
+//! `doomed_snakes` runs the solver for every snake, and `decided_scores`
+//! turns the results into a finished game where they settle it. `MCTS`
+//! treats such positions as terminal and marks doomed snakes as lost in its
+//! heuristic.
+
+use crate::config::TerminalScores;
+use crate::game_state::{Direction, GameState};
+use crate::rules::{GameMode, SNAKE_MAX_HEALTH};
+use std::collections::VecDeque;
+
+/// Regions larger than this aren't solved; they take too long and a snake
+/// with that much room is rarely lost.
+pub const MAX_REGION_CELLS: usize = 64;
+
+/// Positions the solver visits before giving up on a snake.
+pub const MAX_NODES: u64 = 10_000;
+
+/// The squares reachable from the head of the snake at `snake_index`
+/// without crossing its own body, if no other snake is among them.
+///
+/// Other snakes' bodies are crossed, since they will move, so a snake only
+/// counts as isolated when its region is closed off by walls and its own
+/// body.
+pub fn isolated_region(game_state: &GameState, snake_index: usize) -> Option<Vec<usize>> {
+    let snake = game_state.snakes.get(snake_index)?;
+    let head = snake.head().index;
+    if snake.health == 0 || head == usize::MAX {
+        return None;
+    }
+
+    let board_size = game_state.width * game_state.height;
+    let mut blocked = vec![false; board_size];
+    for part in &snake.body {
+        blocked[part.index] = true;
+    }
+    let mut others = vec![false; board_size];
+    for (i, other) in game_state.snakes.iter().enumerate() {
+        if i == snake_index || other.health == 0 {
+            continue;
+        }
+        for part in &other.body {
+            if part.index != usize::MAX {
+                others[part.index] = true;
+            }
+        }
+    }
+
+    let mut region = Vec::new();
+    let mut seen = vec![false; board_size];
+    let mut queue = VecDeque::from([head]);
+    seen[head] = true;
+    while let Some(index) = queue.pop_front() {
+        for direction in Direction::ALL {
+            let next = game_state.neighbour(index, direction);
+            if next == usize::MAX || seen[next] || blocked[next] {
+                continue;
+            }
+            if others[next] {
+                return None;
+            }
+            seen[next] = true;
+            region.push(next);
+            queue.push_back(next);
+        }
+    }
+    Some(region)
+}
+
+/// How long a snake can keep moving.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Survival {
+    /// The most turns the snake was found to survive, up to `horizon`.
+    pub turns: u32,
+    /// Surviving this long counts as surviving for good: the snake has had
+    /// time to use all of its region and has started chasing its tail.
+    pub horizon: u32,
+    /// Whether the search finished, so no path outlasts `turns`.
+    pub exact: bool,
+    /// The moves of the longest path found, or of the way out for a snake
+    /// that can leave its region.
+    pub path: Vec<Direction>,
+}
+
+impl Survival {
+    /// Whether the snake is eliminated within `turns` turns however it
+    /// moves.
+    pub fn is_doomed(&self) -> bool {
+        self.exact && self.turns < self.horizon
+    }
+}
+
+/// The longest the snake at `snake_index` can survive moving only through
+/// `region` and the squares its body leaves behind.
+pub fn longest_survival(game_state: &GameState, snake_index: usize, region: &[usize]) -> Survival {
+    let snake = &game_state.snakes[snake_index];
+    let board_size = game_state.width * game_state.height;
+    let horizon = (region.len() + snake.body.len()) as u32;
+
+    let mut allowed = vec![false; board_size];
+    let mut occupied = vec![0u8; board_size];
+    for &index in region {
+        allowed[index] = true;
+    }
+    for part in &snake.body {
+        allowed[part.index] = true;
+        occupied[part.index] += 1;
+    }
+    let mut food = vec![false; board_size];
+    for f in &game_state.food {
+        food[f.index] = allowed[f.index];
+    }
+    let mut hazards = vec![0u8; board_size];
+    for h in &game_state.hazards {
+        hazards[h.index] = hazards[h.index].saturating_add(1);
+    }
+
+    let mut solver = Solver {
+        game_state,
+        horizon,
+        allowed,
+        occupied,
+        food,
+        hazards,
+        body: snake.body.iter().map(|p| p.index).collect(),
+        tails_retract: game_state.ruleset().tails_retract(),
+        nodes: 0,
+        aborted: false,
+        path: Vec::new(),
+        best: 0,
+        best_path: Vec::new(),
+    };
+    solver.search(snake.health);
+
+    Survival {
+        turns: solver.best,
+        horizon,
+        exact: !solver.aborted,
+        path: solver.best_path,
+    }
+}
+
+/// For each snake, the turns it has left if it is isolated and doomed.
+/// Snakes that are out, have room to survive, or share their region are
+/// `None`.
+pub fn doomed_snakes(game_state: &GameState) -> Vec<Option<u32>> {
+    (0..game_state.snakes.len())
+        .map(|i| {
+            let region = isolated_region(game_state, i)?;
+            if region.len() > MAX_REGION_CELLS {
+                return None;
+            }
+            let survival = longest_survival(game_state, i, &region);
+            survival.is_doomed().then_some(survival.turns)
+        })
+        .collect()
+}
+
+/// The result of the game if `doomed` settles it: every snake left but one
+/// is doomed and that one is sure to outlast them, which makes it the
+/// winner, or every snake left is doomed, which makes the one lasting
+/// longest the winner, or a draw if several last equally long.
+///
+/// A snake with no opponents left, as in solo games, has nobody to outlast,
+/// so its game is only settled once it is doomed, as a loss.
+pub fn decided_scores(
+    game_state: &GameState,
+    doomed: &[Option<u32>],
+    scores: &TerminalScores,
+) -> Option<Vec<f32>> {
+    let alive: Vec<usize> = (0..game_state.snakes.len())
+        .filter(|&i| game_state.snakes[i].health > 0)
+        .collect();
+    if alive.iter().all(|&i| doomed[i].is_none()) {
+        return None;
+    }
+    if alive.len() == 1 || game_state.mode == GameMode::Solo {
+        return alive
+            .iter()
+            .all(|&i| doomed[i].is_some())
+            .then(|| vec![scores.loss; game_state.snakes.len()]);
+    }
+
+    let free: Vec<usize> = alive
+        .iter()
+        .copied()
+        .filter(|&i| doomed[i].is_none())
+        .collect();
+    let winner = match free[..] {
+        [winner] => {
+            let longest = alive.iter().filter_map(|&i| doomed[i]).max()?;
+            if !outlasts(game_state, winner, longest) {
+                return None;
+            }
+            Some(winner)
+        }
+        [] => {
+            let longest = alive.iter().filter_map(|&i| doomed[i]).max()?;
+            let mut last = alive
+                .iter()
+                .copied()
+                .filter(|&i| doomed[i] == Some(longest));
+            match (last.next(), last.next()) {
+                (Some(winner), None) => Some(winner),
+                _ => None,
+            }
+        }
+        _ => return None,
+    };
+
+    Some(match winner {
+        Some(winner) => (0..game_state.snakes.len())
+            .map(|i| if i == winner { scores.win } else { scores.loss })
+            .collect(),
+        None => vec![scores.draw; game_state.snakes.len()],
+    })
+}
+
+/// Whether the snake at `snake_index`, which isn't doomed, is sure to survive
+/// more than `turns` turns. A snake that isn't isolated has to have the
+/// health to last that long even in hazards; an isolated one has to be
+/// shown to by `longest_survival`, so regions too large to solve don't
+/// count.
+fn outlasts(game_state: &GameState, snake_index: usize, turns: u32) -> bool {
+    let Some(region) = isolated_region(game_state, snake_index) else {
+        let damage = if game_state.hazards.is_empty() {
+            1
+        } else {
+            1 + u32::from(game_state.settings.hazard_damage_per_turn)
+        };
+        return u32::from(game_state.snakes[snake_index].health) > turns * damage;
+    };
+    if region.len() > MAX_REGION_CELLS {
+        return false;
+    }
+    let survival = longest_survival(game_state, snake_index, &region);
+    survival.turns >= survival.horizon || survival.turns > turns
+}
+
+/// Depth-first search over one snake's moves.
+struct Solver<'a> {
+    game_state: &'a GameState,
+    horizon: u32,
+    /// Squares the snake may move to.
+    allowed: Vec<bool>,
+    /// Body segments on each square; a tail stacked after eating counts
+    /// twice.
+    occupied: Vec<u8>,
+    food: Vec<bool>,
+    hazards: Vec<u8>,
+    /// Head first.
+    body: VecDeque<usize>,
+    tails_retract: bool,
+    nodes: u64,
+    aborted: bool,
+    path: Vec<Direction>,
+    best: u32,
+    best_path: Vec<Direction>,
+}
+
+/// What a move changed, to undo it.
+struct Undo {
+    tail: Option<usize>,
+    ate: bool,
+}
+
+impl Solver<'_> {
+    /// Extends the current path, which has left the snake with `health`.
+    /// Returns once a path reaches the horizon.
+    fn search(&mut self, health: u8) {
+        let turns = self.path.len() as u32;
+        if turns > self.best {
+            self.best = turns;
+            self.best_path = self.path.clone();
+        }
+        if self.best >= self.horizon {
+            return;
+        }
+        // The body has moved off the edge of the region, so the snake is out
+        let head = self.body[0];
+        if self.escapes(head) {
+            self.best = self.horizon;
+            self.best_path = self.path.clone();
+            return;
+        }
+        self.nodes += 1;
+        if self.nodes > MAX_NODES {
+            self.aborted = true;
+            return;
+        }
+        // Without food the snake starves, so this line can't beat the best
+        if !self.food.iter().any(|&f| f) && turns + health as u32 <= self.best {
+            return;
+        }
+
+        // Tightest squares first, so dead ends are used up before the open
+        // space that leads back out of them
+        let mut moves: Vec<(Direction, usize, usize)> = Direction::ALL
+            .into_iter()
+            .map(|direction| (direction, self.game_state.neighbour(head, direction)))
+            .filter(|&(_, next)| self.is_open(next))
+            .map(|(direction, next)| (direction, next, self.exits(next)))
+            .collect();
+        moves.sort_by_key(|&(_, _, exits)| exits);
+
+        for (direction, next, _) in moves {
+            let Some(undo) = self.apply(next) else {
+                continue;
+            };
+            let health = self.health_after(next, health, undo.ate);
+            if health > 0 {
+                self.path.push(direction);
+                self.search(health);
+                self.path.pop();
+            }
+            self.undo(next, undo);
+            if self.best >= self.horizon || self.aborted {
+                return;
+            }
+        }
+    }
+
+    /// Whether the snake could move to `index` before its tail moves.
+    fn is_open(&self, index: usize) -> bool {
+        if index == usize::MAX || !self.allowed[index] {
+            return false;
+        }
+        let tail = *self.body.back().expect("snakes have bodies");
+        match self.occupied[index] {
+            0 => true,
+            // The tail moves out of the way unless it is stacked
+            1 => self.tails_retract && index == tail,
+            _ => false,
+        }
+    }
+
+    /// Whether `index` is next to a square outside the region. Only the
+    /// snake's own body separates the region from the rest of the board, so
+    /// reaching its edge means the snake can leave.
+    fn escapes(&self, index: usize) -> bool {
+        Direction::ALL.into_iter().any(|direction| {
+            let next = self.game_state.neighbour(index, direction);
+            next != usize::MAX && !self.allowed[next]
+        })
+    }
+
+    /// Open squares next to `index`.
+    fn exits(&self, index: usize) -> usize {
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| self.is_open(self.game_state.neighbour(index, direction)))
+            .count()
+    }
+
+    /// Moves the head to `next`, or `None` if the body is in the way once the
+    /// tail has moved.
+    fn apply(&mut self, next: usize) -> Option<Undo> {
+        let tail = if self.tails_retract {
+            let tail = self.body.pop_back().expect("snakes have bodies");
+            self.occupied[tail] -= 1;
+            Some(tail)
+        } else {
+            None
+        };
+        if self.occupied[next] > 0 {
+            if let Some(tail) = tail {
+                self.body.push_back(tail);
+                self.occupied[tail] += 1;
+            }
+            return None;
+        }
+
+        self.body.push_front(next);
+        self.occupied[next] += 1;
+        let ate = self.food[next];
+        if ate {
+            self.food[next] = false;
+            let tail = *self.body.back().expect("snakes have bodies");
+            self.body.push_back(tail);
+            self.occupied[tail] += 1;
+        }
+        Some(Undo { tail, ate })
+    }
+
+    fn undo(&mut self, next: usize, undo: Undo) {
+        if undo.ate {
+            self.food[next] = true;
+            let tail = self.body.pop_back().expect("snakes have bodies");
+            self.occupied[tail] -= 1;
+        }
+        self.body.pop_front();
+        self.occupied[next] -= 1;
+        if let Some(tail) = undo.tail {
+            self.body.push_back(tail);
+            self.occupied[tail] += 1;
+        }
+    }
+
+    /// Health after the head reaches `next`: one point a turn, hazard damage
+    /// unless the square has food, and full health after eating.
+    fn health_after(&self, next: usize, health: u8, ate: bool) -> u8 {
+        if ate {
+            return SNAKE_MAX_HEALTH;
+        }
+        let damage = self.game_state.settings.hazard_damage_per_turn;
+        let mut health = health.saturating_sub(1);
+        for _ in 0..self.hazards[next] {
+            health = health.saturating_sub(damage);
+        }
+        health
+    }
+}
@@ -0,0 +1,235 @@
+//! Position evaluation for the leaves of a search.
+//!
+//! An `Evaluator` scores every snake in a position that isn't over. `MCTS`
+//! asks its evaluator through `MCTS::default_policy`, which still settles
+//! finished and `endgame`-decided positions itself, so evaluators only see
+//! games that are still being played.
+//!
+//! `evaluator_for` builds the evaluator named by the config's `heuristic`:
+//! the board share from `calculate_control_percentages`, or a
+//! `CompositeEvaluator` weighing territory against length, health, food and
+//! hazards with the weights in `SearchConfig::composite`. Both work out board
+//! control with the config's `control` options. `Heuristic::Network` uses a
+//! `NetworkEvaluator`. `try_evaluator_for` fails if its weights can't be
+//! loaded, and `evaluator_for` falls back to the board share, logging why
+//! each time.
+
+use crate::config::{CompositeWeights, Heuristic, SearchConfig};
+use crate::game_state::{Direction, GameState};
+use crate::heuristic::{
+    calculate_control_percentages_with, calculate_snake_control_with, ControlOptions,
+};
+use crate::network::{load_cached, NetworkError, NetworkEvaluator};
+use crate::rules::SNAKE_MAX_HEALTH;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Scores positions for every snake.
+pub trait Evaluator: Send + Sync {
+    /// Each snake's score for `state`, in `0.0..=1.0` and in the order of
+    /// `state.snakes`. Higher is better for that snake.
+    fn evaluate(&self, state: &GameState) -> Vec<f32>;
+}
+
+/// The evaluator configured by `config.heuristic`, or why its network
+/// couldn't be loaded.
+pub fn try_evaluator_for(config: &SearchConfig) -> Result<Arc<dyn Evaluator>, NetworkError> {
+    Ok(match config.heuristic {
+        Heuristic::Control => Arc::new(ControlEvaluator {
+            options: config.control,
+        }),
+        Heuristic::Composite => {
+            Arc::new(CompositeEvaluator::new(config.composite).with_control_options(config.control))
+        }
+        Heuristic::Network => {
+            let path = config
+                .network
+                .weights
+                .as_deref()
+                .ok_or(NetworkError::NoWeights)?;
+            Arc::new(NetworkEvaluator::new(load_cached(path)?))
+        }
+    })
+}
+
+/// The evaluator configured by `config.heuristic`, or the board share if its
+/// network can't be loaded.
+pub fn evaluator_for(config: &SearchConfig) -> Arc<dyn Evaluator> {
+    try_evaluator_for(config).unwrap_or_else(|e| {
+        println!("{}, using the control heuristic", e);
+        Arc::new(ControlEvaluator {
+            options: config.control,
+        })
+    })
+}
+
+/// Each snake's share of the board from `calculate_control_percentages`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ControlEvaluator {
+    pub options: ControlOptions,
+}
+
+impl Evaluator for ControlEvaluator {
+    fn evaluate(&self, state: &GameState) -> Vec<f32> {
+        calculate_control_percentages_with(state, &self.options)
+    }
+}
+
+/// The parts a `CompositeEvaluator` score is made of, each in `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EvaluationTerms {
+    /// The share of the board the snake controls.
+    pub territory: f32,
+    /// The snake's length against the longest other snake: 0.5 when they
+    /// are as long, more when the snake is longer.
+    pub length: f32,
+    /// Remaining health, or 0 if the snake controls no food it can reach
+    /// before starving.
+    pub health: f32,
+    /// How close the nearest food the snake controls is, 0 if it controls
+    /// none.
+    pub food: f32,
+    /// The share of the snake's territory that is free of hazards.
+    pub hazard: f32,
+}
+
+/// A weighted mean of `EvaluationTerms`. Eliminated snakes score 0.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CompositeEvaluator {
+    weights: CompositeWeights,
+    options: ControlOptions,
+}
+
+impl CompositeEvaluator {
+    pub fn new(weights: CompositeWeights) -> Self {
+        CompositeEvaluator {
+            weights,
+            options: ControlOptions::default(),
+        }
+    }
+
+    /// Works out territory with `options` instead of the defaults.
+    pub fn with_control_options(mut self, options: ControlOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn weights(&self) -> &CompositeWeights {
+        &self.weights
+    }
+
+    /// Every snake's terms, in the order of `state.snakes`. Eliminated
+    /// snakes have all terms at 0.
+    pub fn terms(&self, state: &GameState) -> Vec<EvaluationTerms> {
+        let control = calculate_snake_control_with(state, &self.options);
+        let board_size = (state.width * state.height) as f32;
+        let mut hazardous = vec![false; control.len()];
+        for hazard in &state.hazards {
+            hazardous[hazard.index] = true;
+        }
+
+        (0..state.snakes.len())
+            .map(|i| {
+                let snake = &state.snakes[i];
+                if snake.health == 0 || snake.head().index == usize::MAX {
+                    return EvaluationTerms::default();
+                }
+
+                let owned: Vec<usize> = (0..control.len())
+                    .filter(|&index| control[index] == i as i8)
+                    .collect();
+                let exposed = owned.iter().filter(|&&index| hazardous[index]).count();
+
+                let longest_other = state
+                    .snakes
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, other)| j != i && other.health > 0)
+                    .map(|(_, other)| other.length())
+                    .max()
+                    .unwrap_or(0);
+
+                let food_distance = nearest_controlled_food(state, &control, i);
+                let starving = food_distance.is_none_or(|d| d >= snake.health as usize);
+
+                EvaluationTerms {
+                    territory: owned.len() as f32 / board_size,
+                    length: snake.length() as f32 / (snake.length() + longest_other) as f32,
+                    health: if starving {
+                        0.0
+                    } else {
+                        snake.health as f32 / SNAKE_MAX_HEALTH as f32
+                    },
+                    food: food_distance.map_or(0.0, |d| {
+                        1.0 - d as f32 / (state.width + state.height) as f32
+                    }),
+                    hazard: if owned.is_empty() {
+                        1.0
+                    } else {
+                        1.0 - exposed as f32 / owned.len() as f32
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// The weighted mean of `terms`.
+    pub fn combine(&self, terms: &EvaluationTerms) -> f32 {
+        let weights = &self.weights;
+        let total = weights.total();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        let sum = weights.territory * terms.territory
+            + weights.length * terms.length
+            + weights.health * terms.health
+            + weights.food * terms.food
+            + weights.hazard * terms.hazard;
+        (sum / total).clamp(0.0, 1.0)
+    }
+}
+
+impl Evaluator for CompositeEvaluator {
+    fn evaluate(&self, state: &GameState) -> Vec<f32> {
+        self.terms(state)
+            .iter()
+            .map(|terms| self.combine(terms))
+            .collect()
+    }
+}
+
+/// Moves from the head of the snake at `snake_index` to the nearest food on a
+/// square it controls, going only through squares it controls.
+fn nearest_controlled_food(state: &GameState, control: &[i8], snake_index: usize) -> Option<usize> {
+    let owner = snake_index as i8;
+    let mut food = vec![false; control.len()];
+    let mut any = false;
+    for f in &state.food {
+        if control[f.index] == owner {
+            food[f.index] = true;
+            any = true;
+        }
+    }
+    if !any {
+        return None;
+    }
+
+    let head = state.snakes[snake_index].head().index;
+    let mut distance = vec![usize::MAX; control.len()];
+    let mut queue = VecDeque::from([head]);
+    distance[head] = 0;
+    while let Some(index) = queue.pop_front() {
+        if food[index] {
+            return Some(distance[index]);
+        }
+        for direction in Direction::ALL {
+            let next = state.neighbour(index, direction);
+            if next == usize::MAX || control[next] != owner || distance[next] != usize::MAX {
+                continue;
+            }
+            distance[next] = distance[index] + 1;
+            queue.push_back(next);
+        }
+    }
+    None
+}
@@ -1,16 +1,48 @@
-use serde::Serialize;
-use std::collections::{HashMap, HashSet, VecDeque};
+use crate::rules::{self, GameMode, Ruleset, Settings};
+use crate::zobrist;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 pub struct Position {
     pub index: usize,
 }
 
+/// Why a snake left the game, using the reference engine's cause strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EliminationCause {
+    #[serde(rename = "snake-collision")]
+    Collision,
+    #[serde(rename = "snake-self-collision")]
+    SelfCollision,
+    #[serde(rename = "out-of-health")]
+    OutOfHealth,
+    #[serde(rename = "head-collision")]
+    HeadToHeadCollision,
+    #[serde(rename = "wall-collision")]
+    OutOfBounds,
+    #[serde(rename = "hazard")]
+    Hazard,
+    #[serde(rename = "squad-eliminated")]
+    Squad,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Elimination {
+    pub cause: EliminationCause,
+    /// Id of the snake responsible, if any.
+    pub by: Option<String>,
+    /// The turn on which the snake was eliminated.
+    pub turn: u32,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Snake {
     pub id: String,
     pub body: VecDeque<Position>,
     pub health: u8,
+    pub eliminated: Option<Elimination>,
+    pub squad: Option<String>,
 }
 
 impl Snake {
@@ -24,15 +56,66 @@ impl Snake {
     pub fn length(&self) -> usize {
         self.body.len()
     }
+
+    pub fn is_eliminated(&self) -> bool {
+        self.eliminated.is_some()
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct GameState {
     pub width: usize,
     pub height: usize,
+    pub turn: u32,
+    pub mode: GameMode,
+    pub settings: Settings,
     pub snakes: Vec<Snake>,
     pub food: Vec<Position>,
     pub hazards: Vec<Position>,
+    /// Zobrist hash of the position, kept up to date by the methods here and
+    /// by `rules`. Call `rehash` after editing the fields directly.
+    #[serde(skip)]
+    pub(crate) hash: u64,
+}
+
+/// The order snakes were listed in before being reordered for the search,
+/// such as `board.snakes` in an API request. Snakes are matched by id, so the
+/// mapping still holds in trees reused from earlier turns, where snakes that
+/// have since left the board are still present.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SnakeOrder {
+    ids: Vec<String>,
+}
+
+impl SnakeOrder {
+    pub fn new(ids: Vec<String>) -> Self {
+        SnakeOrder { ids }
+    }
+
+    /// The order of `game_state`'s own snakes.
+    pub fn of(game_state: &GameState) -> Self {
+        SnakeOrder::new(game_state.snakes.iter().map(|s| s.id.clone()).collect())
+    }
+
+    pub fn ids(&self) -> &[String] {
+        &self.ids
+    }
+
+    /// Where the snake with `id` was originally listed.
+    pub fn original_index(&self, id: &str) -> Option<usize> {
+        self.ids.iter().position(|other| other == id)
+    }
+
+    /// `game_state`'s snake indices sorted into the original order. Snakes
+    /// the order doesn't know come last, in the order they are in.
+    pub fn arrange(&self, game_state: &GameState) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..game_state.snakes.len()).collect();
+        indices.sort_by_key(|&i| {
+            self.original_index(&game_state.snakes[i].id)
+                .unwrap_or(usize::MAX)
+        });
+        indices
+    }
 }
 
 impl GameState {
@@ -40,26 +123,46 @@ impl GameState {
         GameState {
             width,
             height,
+            turn: 0,
+            mode: GameMode::Standard,
+            settings: Settings::default(),
             snakes: Vec::new(),
             food: Vec::new(),
             hazards: Vec::new(),
+            hash: 0,
         }
+        .rehashed()
+    }
+
+    /// The Zobrist hash of this position. See `zobrist`.
+    pub fn hash(&self) -> u64 {
+        self.hash
     }
 
+    /// Recomputes the hash from scratch.
+    pub fn rehash(&mut self) {
+        self.hash = zobrist::hash_game_state(self);
+    }
+
+    fn rehashed(mut self) -> Self {
+        self.rehash();
+        self
+    }
+
+    /// Applies the movement stage of the rules to a single snake: the head
+    /// advances and the tail is dropped. Health and collisions are handled by
+    /// `resolve_collisions` once every snake has moved.
     pub fn move_snake(&mut self, snake_index: usize, direction: Direction) {
         if snake_index >= self.snakes.len() {
             return;
         }
 
-        let snake = &mut self.snakes[snake_index];
-        if snake.health == 0 {
+        let snake = &self.snakes[snake_index];
+        if snake.is_eliminated() || snake.health == 0 {
             // Dead snakes do not move
             return;
         }
 
-        let width = self.width;
-        let board_size = width * self.height;
-
         let head_index = snake.head().index;
 
         // If the snake is already out of bounds, no need to move it
@@ -67,168 +170,136 @@ impl GameState {
             return;
         }
 
-        let new_index = match direction {
-            Direction::Up => {
-                if head_index >= width {
-                    head_index - width
-                } else {
-                    usize::MAX // Moved out of bounds
-                }
-            }
-            Direction::Down => {
-                if head_index + width < board_size {
-                    head_index + width
-                } else {
-                    usize::MAX // Moved out of bounds
-                }
-            }
-            Direction::Left => {
-                if head_index % width != 0 {
-                    head_index - 1
-                } else {
-                    usize::MAX // Moved out of bounds
-                }
-            }
-            Direction::Right => {
-                if head_index % width != width - 1 {
-                    head_index + 1
-                } else {
-                    usize::MAX // Moved out of bounds
-                }
-            }
+        let new_index = self.neighbour(head_index, direction);
+
+        // The tail's segment goes, the segment before it becomes the tail,
+        // and the new head links to the old one.
+        let body = &self.snakes[snake_index].body;
+        let len = body.len();
+        let tail = body[len - 1].index;
+        let tail_key = zobrist::segment_key(snake_index, tail, zobrist::tail_link());
+        let (removed, added) = if len >= 2 {
+            let before_tail = body[len - 2].index;
+            let link = zobrist::link(self, before_tail, tail);
+            let new_link = zobrist::link(self, new_index, head_index);
+            (
+                tail_key.wrapping_add(zobrist::segment_key(snake_index, before_tail, link)),
+                zobrist::segment_key(snake_index, before_tail, zobrist::tail_link())
+                    .wrapping_add(zobrist::segment_key(snake_index, new_index, new_link)),
+            )
+        } else {
+            let new_key = zobrist::segment_key(snake_index, new_index, zobrist::tail_link());
+            (tail_key, new_key)
         };
+        self.hash = self.hash.wrapping_sub(removed).wrapping_add(added);
 
-        // Update snake's head position and health
+        let snake = &mut self.snakes[snake_index];
         snake.body.push_front(Position { index: new_index });
         snake.body.pop_back();
-        snake.health = snake.health.saturating_sub(1);
     }
 
-    pub fn resolve_collisions(&mut self) {
-        let mut eaten_food = HashSet::new();
-        let mut snakes_to_kill = HashSet::new();
-
-        // Check for out-of-bounds, dead snakes, and health depletion
-        for (i, snake) in self.snakes.iter().enumerate() {
-            if snake.health == 0 {
-                continue; // Already dead
-            }
-
-            let head = snake.head();
-            if head.index == usize::MAX {
-                // Snake moved out of bounds
-                snakes_to_kill.insert(i);
-            }
-        }
+    /// Grows a snake by one segment, a copy of its tail, as when it eats.
+    pub fn grow_snake(&mut self, snake_index: usize) {
+        let Some(tail) = self.snakes[snake_index].body.back().copied() else {
+            return;
+        };
+        let link = zobrist::link(self, tail.index, tail.index);
+        self.hash = self
+            .hash
+            .wrapping_add(zobrist::segment_key(snake_index, tail.index, link));
+        self.snakes[snake_index].body.push_back(tail);
+    }
 
-        // Food consumption and hazard damage
-        for (i, snake) in self.snakes.iter_mut().enumerate() {
-            if snake.health == 0 {
-                continue; // Skip dead snakes
-            }
+    /// Runs the rest of the turn after every snake has moved: health is
+    /// reduced, hazards deal damage, snakes eat and eliminations are applied.
+    pub fn resolve_collisions(&mut self) {
+        rules::resolve_turn(self);
+    }
 
-            let head = snake.head();
+    /// Plays a full turn. `moves` holds one direction per snake index;
+    /// eliminated snakes ignore theirs.
+    pub fn step(&mut self, moves: &[Direction]) {
+        rules::step(self, moves);
+    }
 
-            // Food consumption
-            if self.food.contains(&head) {
-                eaten_food.insert(head);
-                snake.health = 100; // Reset health when food is eaten
-                                    // Grow the snake
-                if let Some(&tail) = snake.body.back() {
-                    snake.body.push_back(tail);
-                }
-            }
+    /// The rules this game is played under.
+    pub fn ruleset(&self) -> &'static dyn Ruleset {
+        self.mode.ruleset()
+    }
 
-            // Hazard damage
-            if self.hazards.contains(&head) {
-                snake.health = snake.health.saturating_sub(15);
-                if snake.health == 0 {
-                    snakes_to_kill.insert(i);
+    /// The move the reference engine falls back to for a snake: keep going
+    /// the way its neck-to-head segment points, or up if that can't be told.
+    pub fn default_move(&self, snake_index: usize) -> Direction {
+        let snake = &self.snakes[snake_index];
+        let head = snake.head().index;
+        if let Some(neck) = snake.body.get(1).map(|p| p.index) {
+            if neck != usize::MAX && neck != head {
+                for direction in [
+                    Direction::Up,
+                    Direction::Down,
+                    Direction::Left,
+                    Direction::Right,
+                ] {
+                    if self.neighbour(neck, direction) == head {
+                        return direction;
+                    }
                 }
             }
         }
+        Direction::Up
+    }
 
-        // Remove eaten food
-        self.food.retain(|food_pos| !eaten_food.contains(food_pos));
-
-        // Build a map of head positions to snake indices
-        let mut head_positions: HashMap<usize, Vec<usize>> = HashMap::new();
-        for (i, snake) in self.snakes.iter().enumerate() {
-            if snake.health == 0 {
-                continue;
-            }
-            let head_index = snake.head().index;
-            head_positions.entry(head_index).or_default().push(i);
+    /// Whether `observed` is the same position as this state: same turn, the
+    /// same snakes alive with the same bodies and health, and the same food
+    /// and hazards, apart from any that `observed` has on top. Those are
+    /// chance spawns and a shrinking board, which searches don't simulate.
+    /// Snakes that have been eliminated here may be missing from `observed`,
+    /// as the API stops sending them.
+    pub fn matches_position(&self, observed: &GameState) -> bool {
+        if self.turn != observed.turn
+            || self.width != observed.width
+            || self.height != observed.height
+        {
+            return false;
         }
 
-        // Handle head-on collisions
-        for snakes_at_position in head_positions.values() {
-            if snakes_at_position.len() > 1 {
-                self.handle_head_collision(snakes_at_position, &mut snakes_to_kill);
-            }
+        let alive = self.snakes.iter().filter(|s| s.health > 0);
+        let observed_alive = observed.snakes.iter().filter(|s| s.health > 0);
+        if alive.clone().count() != observed_alive.clone().count() {
+            return false;
         }
-
-        // Handle collisions with bodies and self-collisions
-        let mut body_positions: HashMap<usize, usize> = HashMap::new();
-        for (i, snake) in self.snakes.iter().enumerate() {
-            if snake.health == 0 {
-                continue;
-            }
-            for pos in snake.body.iter().skip(1) {
-                body_positions.insert(pos.index, i);
+        for snake in alive {
+            let Some(other) = observed_alive.clone().find(|s| s.id == snake.id) else {
+                return false;
+            };
+            if snake.health != other.health || snake.body != other.body {
+                return false;
             }
         }
 
-        for (i, snake) in self.snakes.iter().enumerate() {
-            if snake.health == 0 {
-                continue; // Skip dead snakes
-            }
-            let head = snake.head();
+        contained_in(&self.food, &observed.food) && contained_in(&self.hazards, &observed.hazards)
+    }
 
-            // Self-collision
-            if snake.body.iter().skip(1).any(|&p| p == head) {
-                // Snake collides with its own body
-                snakes_to_kill.insert(i);
-                continue;
-            }
+    /// Whether `observed` has exactly this state's food and hazards.
+    pub fn same_food_and_hazards(&self, observed: &GameState) -> bool {
+        sorted_indices(&self.food) == sorted_indices(&observed.food)
+            && sorted_indices(&self.hazards) == sorted_indices(&observed.hazards)
+    }
 
-            // Collision with other snakes' bodies
-            if let Some(&other_snake_index) = body_positions.get(&head.index) {
-                if other_snake_index != i {
-                    // Snake collides with another snake's body
-                    snakes_to_kill.insert(i);
-                }
-            }
-        }
+    /// Whether the game has finished under its ruleset.
+    pub fn is_game_over(&self) -> bool {
+        self.ruleset().is_game_over(self)
+    }
 
-        // Update snakes' health after all computations
-        for &i in &snakes_to_kill {
-            self.snakes[i].health = 0;
-        }
+    /// The index reached by moving from `index` in `direction`, or
+    /// `usize::MAX` if that leaves the board.
+    pub fn neighbour(&self, index: usize, direction: Direction) -> usize {
+        self.ruleset().neighbour(self, index, direction)
     }
 
-    fn handle_head_collision(
-        &self,
-        snakes_at_position: &[usize],
-        snakes_to_kill: &mut HashSet<usize>,
-    ) {
-        // Determine the maximum length among these snakes
-        let lengths: Vec<usize> = snakes_at_position
-            .iter()
-            .map(|&i| self.snakes[i].length())
-            .collect();
-        let max_length = *lengths.iter().max().unwrap();
-        let all_same_length = lengths.iter().all(|&l| l == max_length);
-
-        for &i in snakes_at_position {
-            if all_same_length {
-                // All snakes have the same length, all die
-                snakes_to_kill.insert(i);
-            } else if self.snakes[i].length() < max_length {
-                // Snake is shorter than the longest snake, dies
-                snakes_to_kill.insert(i);
-            }
-        }
+    /// The index of the snake with `id`.
+    pub fn snake_index(&self, id: &str) -> Option<usize> {
+        self.snakes.iter().position(|s| s.id == id)
     }
 
     pub fn add_snake(&mut self, id: String, body: Vec<usize>, health: u8) {
@@ -238,16 +309,21 @@ impl GameState {
             id,
             body: snake_body,
             health,
+            eliminated: None,
+            squad: None,
         };
         self.snakes.push(snake);
+        self.rehash();
     }
 
     pub fn add_food(&mut self, index: usize) {
         self.food.push(Position { index });
+        self.hash = self.hash.wrapping_add(zobrist::food_key(index));
     }
 
     pub fn add_hazard(&mut self, index: usize) {
         self.hazards.push(Position { index });
+        self.hash = self.hash.wrapping_add(zobrist::hazard_key(index));
     }
 
     pub fn get_safe_moves(&self, snake_index: usize) -> Vec<Direction> {
@@ -261,8 +337,6 @@ impl GameState {
         }
 
         let head_index = snake.head().index;
-        let width = self.width;
-        let board_size = width * self.height;
         let mut safe_moves = Vec::new();
 
         // If the snake is already out of bounds, it has no safe moves
@@ -279,36 +353,7 @@ impl GameState {
             Direction::Left,
             Direction::Right,
         ] {
-            let new_index = match direction {
-                Direction::Up => {
-                    if head_index >= width {
-                        head_index - width
-                    } else {
-                        usize::MAX // Out of bounds
-                    }
-                }
-                Direction::Down => {
-                    if head_index + width < board_size {
-                        head_index + width
-                    } else {
-                        usize::MAX // Out of bounds
-                    }
-                }
-                Direction::Left => {
-                    if head_index % width != 0 {
-                        head_index - 1
-                    } else {
-                        usize::MAX // Out of bounds
-                    }
-                }
-                Direction::Right => {
-                    if head_index % width != width - 1 {
-                        head_index + 1
-                    } else {
-                        usize::MAX // Out of bounds
-                    }
-                }
-            };
+            let new_index = self.neighbour(head_index, direction);
 
             // If the move is out of bounds, skip it
             if new_index == usize::MAX {
@@ -328,6 +373,21 @@ impl GameState {
     }
 }
 
+fn sorted_indices(positions: &[Position]) -> Vec<usize> {
+    let mut indices: Vec<usize> = positions.iter().map(|p| p.index).collect();
+    indices.sort_unstable();
+    indices
+}
+
+/// Whether every position in `positions`, counting repeats, is also in
+/// `others`.
+fn contained_in(positions: &[Position], others: &[Position]) -> bool {
+    let mut others = sorted_indices(others).into_iter();
+    sorted_indices(positions)
+        .into_iter()
+        .all(|index| others.any(|other| other == index))
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Direction {
     Up,
@@ -335,3 +395,17 @@ pub enum Direction {
     Left,
     Right,
 }
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    /// Position of this direction in `Direction::ALL`.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
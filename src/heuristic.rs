@@ -1,6 +1,9 @@
-use std::collections::VecDeque;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
 
 use crate::game_state::{Direction, GameState};
+use crate::rules::{Ruleset, SNAKE_MAX_HEALTH};
+use serde::{Deserialize, Serialize};
 
 /// Calculates which snake controls each position on the board.
 ///
@@ -10,6 +13,9 @@ use crate::game_state::{Direction, GameState};
 /// If two snakes can reach a position at the same time, the snake with the lower index
 /// controls it.
 ///
+/// Movement and tail timing follow the game's ruleset, so wrapped boards are
+/// explored across their edges and constrictor bodies never free up.
+///
 /// # Parameters
 /// - `game_state`: The current state of the game.
 ///
@@ -17,9 +23,104 @@ use crate::game_state::{Direction, GameState};
 /// A vector of length `board_size`, where each element is the index of the snake that
 /// controls that position. If a position is unclaimed, the value is -1.
 pub fn calculate_snake_control(game_state: &GameState) -> Vec<i8> {
-    let width = game_state.width;
-    let height = game_state.height;
-    let board_size = width * height;
+    calculate_snake_control_with(game_state, &ControlOptions::default())
+}
+
+/// Options for `calculate_snake_control_with`. The defaults give
+/// `calculate_snake_control`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlOptions {
+    /// Follow food and health along each snake's expansion: a snake that
+    /// reaches food grows, so its own tail stays put one move longer for each
+    /// food eaten on the way, and a snake doesn't expand to squares it would
+    /// run out of health before reaching (counting hazard damage, with food
+    /// restoring it).
+    pub food_aware: bool,
+    /// How squares reached at the same time are shared out.
+    pub rule: ControlRule,
+}
+
+/// How `calculate_snake_control_with` shares out the squares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ControlRule {
+    /// The first snake to reach a square gets it, and the one with the lower
+    /// index if several get there at once.
+    #[default]
+    FirstToArrive,
+    /// The first snake to reach a square gets it, and the longest if several
+    /// get there at once, as in `calculate_head_to_head_control`.
+    HeadToHead,
+    /// The snake that spends the least health getting to a square gets it,
+    /// counting hazard damage, as in `calculate_hazard_control`. Food is
+    /// already weighed by the health it saves, so `food_aware` is ignored.
+    LeastHealth,
+}
+
+/// Calculates which snake controls each position on the board, as
+/// `calculate_snake_control` does, with `options` applied.
+///
+/// # Parameters
+/// - `game_state`: The current state of the game.
+/// - `options`: What else the expansion takes into account.
+///
+/// # Returns
+/// A vector of length `board_size`, where each element is the index of the snake that
+/// controls that position. If a position is unclaimed, the value is -1.
+pub fn calculate_snake_control_with(game_state: &GameState, options: &ControlOptions) -> Vec<i8> {
+    snake_control(&Expansion::new(game_state), options)
+}
+
+/// What every control expansion starts from: the snakes on the board, the
+/// squares around each square under the game's ruleset, and when each square
+/// frees up.
+struct Expansion<'a> {
+    game_state: &'a GameState,
+    ruleset: &'static dyn Ruleset,
+    /// The earliest time when each position becomes unoccupied
+    unoccupied_at: Vec<u32>,
+}
+
+impl<'a> Expansion<'a> {
+    fn new(game_state: &'a GameState) -> Self {
+        Expansion {
+            game_state,
+            ruleset: game_state.ruleset(),
+            unoccupied_at: position_unoccupied_at(game_state),
+        }
+    }
+
+    fn board_size(&self) -> usize {
+        self.game_state.width * self.game_state.height
+    }
+
+    /// The index and head of every snake still on the board.
+    fn heads(&self) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.game_state
+            .snakes
+            .iter()
+            .enumerate()
+            .filter(|(_, snake)| snake.health > 0 && snake.head().index != usize::MAX)
+            .map(|(i, snake)| (i, snake.head().index))
+    }
+
+    /// The positions one move from `pos`, in the order of `Direction::ALL`.
+    fn neighbours(&self, pos: usize) -> impl Iterator<Item = usize> + '_ {
+        Direction::ALL
+            .into_iter()
+            .map(move |direction| self.ruleset.neighbour(self.game_state, pos, direction))
+            .filter(|&new_pos| new_pos != usize::MAX)
+    }
+}
+
+fn snake_control(expansion: &Expansion, options: &ControlOptions) -> Vec<i8> {
+    if options.rule == ControlRule::LeastHealth {
+        return hazard_control(expansion).control;
+    }
+
+    let game_state = expansion.game_state;
+    let board_size = expansion.board_size();
 
     // Initialize the control vector with -1 (no snake controls the position yet)
     let mut control = vec![-1; board_size];
@@ -27,96 +128,111 @@ pub fn calculate_snake_control(game_state: &GameState) -> Vec<i8> {
     // Initialize the minimum depth (time) at which each position is reached
     let mut min_depth = vec![u32::MAX; board_size];
 
+    // With head-to-head ties, the length of the longest snake reaching each
+    // position at `min_depth`
+    let mut claim_length = vec![0usize; board_size];
+
     // Queue for BFS (Breadth-First Search)
     let mut queue = VecDeque::new();
 
-    // This vector will store the earliest time when each position becomes unoccupied
-    let mut position_unoccupied_at = vec![0u32; board_size];
-
-    // Precompute when each position becomes unoccupied for all snakes
-    for snake in &game_state.snakes {
-        // Length of the snake's body
-        let body_len = snake.body.len() as u32;
-
-        // Skip the snake if it's dead or has an invalid position
-        if snake.health == 0 || snake.body.is_empty() || snake.body[0].index == usize::MAX {
-            continue;
-        }
-
-        // Iterate over each segment of the snake's body
-        for (i, body_part) in snake.body.iter().enumerate() {
-            let pos = body_part.index;
-
-            // Determine the time when this position becomes unoccupied
-            let t = if i == snake.body.len() - 1 {
-                // For the tail segment, it becomes unoccupied at time 0 (immediately)
-                0
-            } else {
-                // For other segments, they become unoccupied after the snake moves
-                i as u32 + 1 // Time steps start from 1
-            };
-
-            // Update the earliest time when this position becomes unoccupied
-            if t > position_unoccupied_at[pos] {
-                position_unoccupied_at[pos] = t;
+    // With food aware expansion, the snake whose body is on each position,
+    // and where food and hazards are
+    let mut body_owner = vec![-1i8; board_size];
+    let mut food = vec![false; board_size];
+    let mut hazards = vec![0u8; board_size];
+    if options.food_aware {
+        for (i, snake) in game_state.snakes.iter().enumerate() {
+            if snake.health == 0 {
+                continue;
             }
+            for part in &snake.body {
+                if part.index != usize::MAX {
+                    body_owner[part.index] = i as i8;
+                }
+            }
+        }
+        for f in &game_state.food {
+            food[f.index] = true;
+        }
+        for h in &game_state.hazards {
+            hazards[h.index] = hazards[h.index].saturating_add(1);
         }
     }
+    let hazard_damage = game_state.settings.hazard_damage_per_turn;
 
     // Initialize the BFS queue with the heads of all snakes
-    for (i, snake) in game_state.snakes.iter().enumerate() {
-        let head = snake.body[0].index;
-
-        // Skip the snake if it's dead or has an invalid position
-        if head == usize::MAX || snake.health == 0 {
-            continue;
-        }
+    for (i, head) in expansion.heads() {
+        let snake = &game_state.snakes[i];
 
-        // Add the head position to the queue with depth 0
-        queue.push_back((head, i as i8, 0u32)); // (position, snake_id, depth)
+        // Add the head position to the queue with depth 0, its health and
+        // no food eaten yet
+        queue.push_back((head, i as i8, 0u32, snake.health, 0u32));
         min_depth[head] = 0; // The head position is reached at time 0
         control[head] = i as i8; // The snake controls its head position
+        claim_length[head] = snake.length();
     }
 
-    // Directions to move on the board: Up, Down, Left, Right
-    let directions = [-(width as isize), width as isize, -1, 1];
-
-    // Perform BFS to expand each snake's control territory
-    while let Some((pos, snake_id, depth)) = queue.pop_front() {
+    // Perform BFS to expand each snake's control territory. Every claim on a
+    // position is made before the position is taken off the queue, so its
+    // owner is settled by then
+    while let Some((pos, snake_id, depth, health, eaten)) = queue.pop_front() {
+        // Under head-to-head ties, a longer snake or a tie may have taken the
+        // position since this snake reached it
+        if options.rule == ControlRule::HeadToHead && control[pos] != snake_id {
+            continue;
+        }
+        let length = game_state.snakes[snake_id as usize].length();
         let next_depth = depth + 1; // Time increases by 1 with each move
 
         // Try moving in all four directions
-        for &offset in &directions {
-            let new_pos = pos as isize + offset;
-
-            // Check if the new position is within the board boundaries
-            if new_pos < 0 || new_pos >= board_size as isize {
-                continue; // Skip positions outside the board
+        for new_pos in expansion.neighbours(pos) {
+            // Check if the position is occupied at the time we reach it. The
+            // snake's own body stays a move longer for each food it has eaten
+            let mut unoccupied_at = expansion.unoccupied_at[new_pos];
+            if body_owner[new_pos] == snake_id {
+                unoccupied_at = unoccupied_at.saturating_add(eaten);
             }
-
-            // Check for horizontal wrapping (moving left or right across edges)
-            if (offset == -1 && pos % width == 0) || (offset == 1 && (pos + 1) % width == 0) {
-                continue; // Skip wrapping around the edges
+            if unoccupied_at > next_depth {
+                continue; // Position is still occupied by a snake segment
             }
 
-            let new_pos = new_pos as usize;
-
-            // Check if the position is occupied at the time we reach it
-            if position_unoccupied_at[new_pos] > next_depth {
-                continue; // Position is still occupied by a snake segment
+            // The snake's health on arrival, and whether it survives getting there
+            let (new_health, new_eaten) = if !options.food_aware {
+                (health, eaten)
+            } else if food[new_pos] {
+                (SNAKE_MAX_HEALTH, eaten + 1)
+            } else {
+                let mut new_health = health.saturating_sub(1);
+                for _ in 0..hazards[new_pos] {
+                    new_health = new_health.saturating_sub(hazard_damage);
+                }
+                (new_health, eaten)
+            };
+            if new_health == 0 {
+                continue; // The snake starves on the way
             }
 
             // Update control if we found a shorter path to this position
             if next_depth < min_depth[new_pos] {
                 min_depth[new_pos] = next_depth;
                 control[new_pos] = snake_id;
+                claim_length[new_pos] = length;
                 // Add the new position to the queue to continue expanding
-                queue.push_back((new_pos, snake_id, next_depth));
-            }
-            // If two snakes reach the position at the same time
-            else if next_depth == min_depth[new_pos] && snake_id < control[new_pos] {
-                control[new_pos] = snake_id;
-                // No need to enqueue again since depth is the same
+                queue.push_back((new_pos, snake_id, next_depth, new_health, new_eaten));
+            } else if next_depth == min_depth[new_pos] && control[new_pos] != snake_id {
+                // If two snakes reach the position at the same time
+                if options.rule == ControlRule::HeadToHead {
+                    if length > claim_length[new_pos] {
+                        control[new_pos] = snake_id;
+                        claim_length[new_pos] = length;
+                        queue.push_back((new_pos, snake_id, next_depth, new_health, new_eaten));
+                    } else if length == claim_length[new_pos] {
+                        control[new_pos] = -1;
+                    }
+                } else if snake_id < control[new_pos] {
+                    control[new_pos] = snake_id;
+                    // No need to enqueue again since depth is the same
+                }
             }
         }
     }
@@ -133,7 +249,16 @@ pub fn calculate_snake_control(game_state: &GameState) -> Vec<i8> {
 /// # Returns
 /// A vector where each element is the percentage of the board controlled by the corresponding snake.
 pub fn calculate_control_percentages(game_state: &GameState) -> Vec<f32> {
-    let control = calculate_snake_control(game_state);
+    calculate_control_percentages_with(game_state, &ControlOptions::default())
+}
+
+/// Calculates the percentage of the board controlled by each snake, with
+/// control from `calculate_snake_control_with`.
+pub fn calculate_control_percentages_with(
+    game_state: &GameState,
+    options: &ControlOptions,
+) -> Vec<f32> {
+    let control = calculate_snake_control_with(game_state, options);
     let board_size = game_state.width * game_state.height;
 
     // Initialize counts for each snake
@@ -149,7 +274,7 @@ pub fn calculate_control_percentages(game_state: &GameState) -> Vec<f32> {
     // Calculate the percentage for each snake
     counts
         .iter()
-        .map(|&count| (count as f32 / board_size as f32))
+        .map(|&count| count as f32 / board_size as f32)
         .collect()
 }
 
@@ -203,3 +328,258 @@ pub fn calculate_move_control(
         0.0
     }
 }
+
+/// The earliest time at which each position is free of snake bodies.
+///
+/// A body segment `i` squares behind the head of a snake of length `n` is
+/// left behind after `n - i` moves, so the tail is free to move into on the
+/// first move and the neck only once the whole snake has passed. A tail
+/// stacked after eating stays for an extra move. Under rules where tails
+/// never retract, bodies stay put for good.
+fn position_unoccupied_at(game_state: &GameState) -> Vec<u32> {
+    let mut unoccupied_at = vec![0u32; game_state.width * game_state.height];
+    let tails_retract = game_state.ruleset().tails_retract();
+
+    for snake in &game_state.snakes {
+        // Skip the snake if it's dead or has an invalid position
+        if snake.health == 0 || snake.body.is_empty() || snake.body[0].index == usize::MAX {
+            continue;
+        }
+
+        let length = snake.body.len();
+        for (i, body_part) in snake.body.iter().enumerate() {
+            let t = if tails_retract {
+                (length - i) as u32
+            } else {
+                u32::MAX
+            };
+            let pos = body_part.index;
+            unoccupied_at[pos] = unoccupied_at[pos].max(t);
+        }
+    }
+
+    unoccupied_at
+}
+
+/// The room one snake has to move in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReachableArea {
+    /// Squares the snake could reach if no other snake moved, each no sooner
+    /// than the bodies on it have moved off.
+    pub area: usize,
+    /// Whether the area is smaller than the snake, so it can't fit and will
+    /// run out of room.
+    pub trapped: bool,
+}
+
+/// Calculates the area each snake can reach on its own.
+///
+/// Unlike `calculate_snake_control`, the snakes don't compete for squares:
+/// each snake's flood fill ignores the others' heads, and only waits for
+/// bodies (its own included) to move off squares as in
+/// `calculate_snake_control`. The result is how much room the snake has, and
+/// a snake whose room is smaller than its own length is trapped.
+///
+/// # Parameters
+/// - `game_state`: The current state of the game.
+///
+/// # Returns
+/// A `ReachableArea` for each snake, in the order of `game_state.snakes`.
+/// Eliminated snakes have an area of 0 and are trapped.
+pub fn calculate_reachable_areas(game_state: &GameState) -> Vec<ReachableArea> {
+    let position_unoccupied_at = position_unoccupied_at(game_state);
+    let board_size = game_state.width * game_state.height;
+    let ruleset = game_state.ruleset();
+
+    let mut depth = vec![u32::MAX; board_size];
+    let mut queue = VecDeque::new();
+
+    game_state
+        .snakes
+        .iter()
+        .map(|snake| {
+            let head = snake.head().index;
+            if snake.health == 0 || head == usize::MAX {
+                return ReachableArea {
+                    area: 0,
+                    trapped: true,
+                };
+            }
+
+            depth.fill(u32::MAX);
+            depth[head] = 0;
+            queue.push_back(head);
+            let mut area = 0;
+
+            while let Some(pos) = queue.pop_front() {
+                let next_depth = depth[pos] + 1;
+                for direction in Direction::ALL {
+                    let new_pos = ruleset.neighbour(game_state, pos, direction);
+                    if new_pos == usize::MAX
+                        || depth[new_pos] != u32::MAX
+                        || position_unoccupied_at[new_pos] > next_depth
+                    {
+                        continue;
+                    }
+                    depth[new_pos] = next_depth;
+                    area += 1;
+                    queue.push_back(new_pos);
+                }
+            }
+
+            ReachableArea {
+                area,
+                trapped: area < snake.length(),
+            }
+        })
+        .collect()
+}
+
+/// Board control with head-to-head collisions taken into account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadToHeadControl {
+    /// The snake that controls each position, or -1 if nobody does.
+    pub control: Vec<i8>,
+    /// Positions a snake could move to next turn where a longer snake's head
+    /// could move too, so going there risks losing a head-to-head collision.
+    pub dangerous: Vec<bool>,
+}
+
+/// Calculates which snake controls each position like
+/// `calculate_snake_control`, but settles squares reached at the same time
+/// the way a head-to-head collision would: the longest snake to reach a
+/// square gets it, and if several are equally long nobody does. Squares
+/// nobody gets aren't expanded from. This is the control from
+/// `calculate_snake_control_with` under `ControlRule::HeadToHead`.
+///
+/// Squares next to a snake's head that a longer snake's head is also next
+/// to are marked dangerous.
+///
+/// # Parameters
+/// - `game_state`: The current state of the game.
+///
+/// # Returns
+/// A `HeadToHeadControl` with a control vector of length `board_size`, as
+/// from `calculate_snake_control`, and a matching vector of dangerous
+/// positions.
+pub fn calculate_head_to_head_control(game_state: &GameState) -> HeadToHeadControl {
+    let expansion = Expansion::new(game_state);
+    let heads: Vec<(usize, usize)> = expansion.heads().collect();
+    let mut dangerous = vec![false; expansion.board_size()];
+
+    // Squares next to a head that a longer snake's head can also reach
+    for &(i, head) in &heads {
+        let length = game_state.snakes[i].length();
+        for pos in expansion.neighbours(head) {
+            if expansion.unoccupied_at[pos] > 1 {
+                continue;
+            }
+            dangerous[pos] |= heads.iter().any(|&(j, enemy_head)| {
+                j != i
+                    && game_state.snakes[j].length() > length
+                    && expansion.neighbours(enemy_head).any(|p| p == pos)
+            });
+        }
+    }
+
+    let options = ControlOptions {
+        rule: ControlRule::HeadToHead,
+        ..ControlOptions::default()
+    };
+    HeadToHeadControl {
+        control: snake_control(&expansion, &options),
+        dangerous,
+    }
+}
+
+/// Board control measured in health rather than moves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HazardControl {
+    /// The snake that controls each position, or -1 if nobody does.
+    pub control: Vec<i8>,
+    /// The health the controlling snake is expected to have on reaching each
+    /// position, or 0 where nobody does.
+    pub health: Vec<u8>,
+}
+
+/// Calculates which snake controls each position when moving through hazards
+/// costs health.
+///
+/// Each snake's expansion is a Dijkstra search over the health it spends:
+/// one point a move, plus the ruleset's hazard damage for every hazard on a
+/// square entered, unless the square has food. The snake that gets to a
+/// position for the least health controls it, and the one with the lower
+/// index if two spend the same. Bodies are waited for as in
+/// `calculate_snake_control`, counting the moves taken. A snake can't claim
+/// a square it would arrive at with no health left.
+///
+/// Eating isn't followed, so the health reported for squares past food is
+/// a lower bound. The control is that of `calculate_snake_control_with`
+/// under `ControlRule::LeastHealth`.
+///
+/// # Parameters
+/// - `game_state`: The current state of the game.
+///
+/// # Returns
+/// A `HazardControl` with a control vector of length `board_size`, as from
+/// `calculate_snake_control`, and the owner's health at each position.
+pub fn calculate_hazard_control(game_state: &GameState) -> HazardControl {
+    hazard_control(&Expansion::new(game_state))
+}
+
+fn hazard_control(expansion: &Expansion) -> HazardControl {
+    let game_state = expansion.game_state;
+    let board_size = expansion.board_size();
+    let damage = game_state.settings.hazard_damage_per_turn as u32;
+
+    // The health it costs to step onto each position
+    let mut step_cost = vec![1u32; board_size];
+    for hazard in &game_state.hazards {
+        step_cost[hazard.index] += damage;
+    }
+    for food in &game_state.food {
+        step_cost[food.index] = 1;
+    }
+
+    let mut control = vec![-1i8; board_size];
+    let mut health = vec![0u8; board_size];
+    let mut min_cost = vec![u32::MAX; board_size];
+    // (cost, snake, position, moves), cheapest first
+    let mut queue = BinaryHeap::new();
+
+    for (i, head) in expansion.heads() {
+        queue.push(Reverse((0u32, i as i8, head, 0u32)));
+        min_cost[head] = 0;
+        control[head] = i as i8;
+        health[head] = game_state.snakes[i].health;
+    }
+
+    while let Some(Reverse((cost, snake_id, pos, moves))) = queue.pop() {
+        if cost > min_cost[pos] || control[pos] != snake_id {
+            continue; // A cheaper path, or an equal one by a lower index, got here
+        }
+        let starting_health = game_state.snakes[snake_id as usize].health as u32;
+
+        for new_pos in expansion.neighbours(pos) {
+            if expansion.unoccupied_at[new_pos] > moves + 1 {
+                continue;
+            }
+
+            let new_cost = cost + step_cost[new_pos];
+            if new_cost >= starting_health {
+                continue; // The snake would arrive with no health left
+            }
+
+            if new_cost < min_cost[new_pos]
+                || (new_cost == min_cost[new_pos] && snake_id < control[new_pos])
+            {
+                min_cost[new_pos] = new_cost;
+                control[new_pos] = snake_id;
+                health[new_pos] = (starting_health - new_cost) as u8;
+                queue.push(Reverse((new_cost, snake_id, new_pos, moves + 1)));
+            }
+        }
+    }
+
+    HazardControl { control, health }
+}
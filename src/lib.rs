@@ -1,6 +1,18 @@
+pub mod alphabeta;
+pub mod analysis;
 pub mod battlesnake_api;
+pub mod bitboard;
+pub mod config;
+pub mod endgame;
+pub mod evaluation;
 pub mod game_state;
 pub mod heuristic;
+pub mod matrix_game;
+pub mod network;
+pub mod rules;
 pub mod search;
+pub mod time_manager;
+pub mod transposition;
 pub mod tree;
 pub mod visualizer;
+pub mod zobrist;
@@ -3,19 +3,17 @@ use rand::seq::SliceRandom;
 use serde_json::json;
 use std::env;
 use std::sync::atomic::Ordering;
-use std::sync::Arc;
-use std::time::Duration;
-
-mod battlesnake_api;
-mod game_state;
-mod heuristic;
-mod search;
-mod visualizer;
-
-use crate::battlesnake_api::{BattlesnakeRequest, MoveResponse};
-use crate::game_state::Direction;
-use crate::search::MCTS;
-use crate::visualizer::visualize_game_state;
+use std::time::Instant;
+
+use battlesnake::alphabeta::AlphaBeta;
+use battlesnake::battlesnake_api::{direction_to_move, BattlesnakeRequest, MoveResponse};
+use battlesnake::config::{Engine, SearchConfig};
+use battlesnake::evaluation::try_evaluator_for;
+use battlesnake::game_state::{Direction, GameState};
+use battlesnake::matrix_game::MatrixGameSearch;
+use battlesnake::search::TreeStore;
+use battlesnake::time_manager::{SearchBudget, TimeManager};
+use battlesnake::visualizer::visualize_game_state;
 
 async fn index() -> impl Responder {
     HttpResponse::Ok().json(json!({
@@ -33,18 +31,99 @@ async fn start(info: web::Json<BattlesnakeRequest>) -> impl Responder {
     HttpResponse::Ok()
 }
 
-async fn r#move(info: web::Json<BattlesnakeRequest>) -> impl Responder {
+async fn r#move(
+    info: web::Json<BattlesnakeRequest>,
+    trees: web::Data<TreeStore>,
+    time_manager: web::Data<TimeManager>,
+    config: web::Data<SearchConfig>,
+) -> impl Responder {
+    let received_at = Instant::now();
+    let budget = match config.time.move_time() {
+        Some(move_time) => SearchBudget::fixed(move_time),
+        None => time_manager.budget(&info.game.id, info.game.timeout, &info.you.latency),
+    };
     let game_state = info.to_game_state();
 
     println!("Turn: {}", info.turn);
     println!("Game state:\n{}", visualize_game_state(&game_state));
 
-    let mcts = MCTS::new(game_state.clone());
+    let our_snake_id = &info.you.id;
+    let engine = config.engine_for(&game_state);
+    println!(
+        "Running {:?} for {}-{} milliseconds",
+        engine,
+        budget.soft.as_millis(),
+        budget.hard.as_millis()
+    );
+
+    let budget = budget.after(received_at.elapsed());
+    let best_move = match engine {
+        Engine::AlphaBeta => {
+            let result =
+                AlphaBeta::new(&config).search(&game_state, our_snake_id, budget.soft, budget.hard);
+            println!("Alpha-beta: {}", result);
+            result.best_move
+        }
+        Engine::MatrixGame => {
+            let result = MatrixGameSearch::new(&config).search(
+                &game_state,
+                our_snake_id,
+                budget.soft,
+                budget.hard,
+            );
+            println!("Matrix game: {}", result);
+            result.sample_move(&mut rand::thread_rng())
+        }
+        _ => search_with_mcts(&info, &game_state, &trees, &config, &budget),
+    };
+    time_manager.record_response_time(&info.game.id, received_at.elapsed());
+
+    if let Some(our_move) = best_move {
+        HttpResponse::Ok().json(MoveResponse::new(
+            our_move,
+            format!("Moving {} using {:?}", direction_to_move(our_move), engine),
+        ))
+    } else {
+        let chosen_move = Direction::ALL.choose(&mut rand::thread_rng()).unwrap();
+
+        HttpResponse::Ok().json(MoveResponse::new(
+            *chosen_move,
+            "No valid moves! Moving randomly!".to_string(),
+        ))
+    }
+}
 
-    let duration = Duration::from_millis(400);
-    println!("Running MCTS for {} milliseconds", duration.as_millis());
+/// Searches with the tree kept for this game, leaving the grown tree behind
+/// for the next turn.
+fn search_with_mcts(
+    info: &BattlesnakeRequest,
+    game_state: &GameState,
+    trees: &TreeStore,
+    config: &SearchConfig,
+    budget: &SearchBudget,
+) -> Option<Direction> {
+    let mut mcts = trees.take(&info.game.id, game_state, config);
+    mcts.set_snake_order(info.snake_order());
+    let reused_visits = mcts.root.visits.load(Ordering::Relaxed);
+    if reused_visits > 0 {
+        println!("Reusing tree with {} visits", reused_visits);
+    }
 
-    mcts.run(duration, 12);
+    let our_snake_id = &info.you.id;
+    let num_threads = config.num_threads();
+    let budget = budget.reusing(reused_visits);
+    let search_start = Instant::now();
+    mcts.run_until(num_threads, |progress, elapsed| {
+        budget.should_stop(elapsed, &progress.root_moves(our_snake_id))
+    });
+    let iterations = mcts.iterations.load(Ordering::Relaxed);
+    println!(
+        "Searched {} iterations on {} threads, {:?} ({:.0}/s)",
+        iterations,
+        num_threads,
+        config.parallelism,
+        iterations as f64 / search_start.elapsed().as_secs_f64()
+    );
 
     println!(
         "Root node game state:\n{}",
@@ -55,53 +134,44 @@ async fn r#move(info: web::Json<BattlesnakeRequest>) -> impl Responder {
         mcts.root.visits.load(Ordering::Relaxed)
     );
 
-    if !mcts.root.children.is_empty() {
-        let best_child_entry = mcts
-            .root
-            .children
-            .iter()
-            .max_by_key(|entry| entry.value().visits.load(Ordering::Relaxed));
-
-        if let Some(entry) = best_child_entry {
-            let best_child_node = entry.value();
-            println!(
-                "Best child game state:\n{}",
-                visualize_game_state(&best_child_node.game_state)
-            );
-            println!(
-                "Best child visits: {}",
-                best_child_node.visits.load(Ordering::Relaxed)
-            );
-        }
+    let best_child = mcts
+        .root
+        .child_nodes()
+        .into_iter()
+        .max_by_key(|child| child.visits.load(Ordering::Relaxed));
+
+    if let Some(best_child_node) = best_child {
+        println!(
+            "Best child game state:\n{}",
+            visualize_game_state(&best_child_node.game_state)
+        );
+        println!(
+            "Best child visits: {}",
+            best_child_node.visits.load(Ordering::Relaxed)
+        );
     }
 
-    let our_snake_id = &info.you.id;
-    if let Some(our_move) = mcts.get_best_move_for_snake(our_snake_id) {
-        // down and up need to be opposite since our board is flipped
-        let chosen_move = match our_move {
-            Direction::Up => "down",
-            Direction::Down => "up",
-            Direction::Left => "left",
-            Direction::Right => "right",
-        };
-
-        HttpResponse::Ok().json(MoveResponse {
-            r#move: chosen_move.to_string(),
-            shout: Some(format!("Moving {} using MCTS", chosen_move)),
-        })
-    } else {
-        let moves = vec!["up", "down", "left", "right"];
-        let chosen_move = moves.choose(&mut rand::thread_rng()).unwrap();
-
-        HttpResponse::Ok().json(MoveResponse {
-            r#move: chosen_move.to_string(),
-            shout: Some("No valid moves! Moving randomly!".to_string()),
-        })
+    for score in mcts.root_scores() {
+        println!(
+            "Snake {} (board index {:?}, search index {}): mean score {:.3}",
+            score.id, score.original_index, score.search_index, score.mean_score
+        );
     }
+
+    let selection = mcts.select_move(our_snake_id);
+    println!("Move selection, {}", selection);
+    trees.insert(&info.game.id, mcts);
+    selection.chosen
 }
 
-async fn end(info: web::Json<BattlesnakeRequest>) -> impl Responder {
+async fn end(
+    info: web::Json<BattlesnakeRequest>,
+    trees: web::Data<TreeStore>,
+    time_manager: web::Data<TimeManager>,
+) -> impl Responder {
     println!("Game ended: {}", info.game.id);
+    trees.remove(&info.game.id);
+    time_manager.end_game(&info.game.id);
     HttpResponse::Ok()
 }
 
@@ -111,8 +181,26 @@ async fn main() -> std::io::Result<()> {
 
     println!("Starting server on port: {}", port);
 
-    HttpServer::new(|| {
+    let trees = web::Data::new(TreeStore::new());
+
+    let mut config = SearchConfig::load().unwrap_or_else(|e| {
+        println!("{}, using the default search config", e);
+        SearchConfig::default()
+    });
+    if let Err(e) = try_evaluator_for(&config) {
+        println!("{}, using the default search config", e);
+        config = SearchConfig::default();
+    }
+    println!("Search config:\n{}", config);
+
+    let time_manager = web::Data::new(TimeManager::new(config.time.settings()));
+    let config = web::Data::new(config);
+
+    HttpServer::new(move || {
         App::new()
+            .app_data(trees.clone())
+            .app_data(time_manager.clone())
+            .app_data(config.clone())
             .route("/", web::get().to(index))
             .route("/start", web::post().to(start))
             .route("/move", web::post().to(r#move))
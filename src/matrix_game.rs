@@ -0,0 +1,403 @@
+//! Simultaneous-move minimax for duels.
+//!
+//! Both snakes move at once, so neither gets to see the other's move, as
+//! `AlphaBeta` assumes. Here every position is a matrix game instead: our
+//! moves against theirs, each joint move scored by searching the position it
+//! leads to. The value of a position is the value of its matrix as a
+//! zero-sum game, and at the root the equilibrium itself is returned, the
+//! probability with which each snake should play each move.
+//!
+//! Payoffs are our score less theirs, from the same evaluation as `MCTS`.
+
+use crate::alphabeta::deterministic;
+use crate::config::SearchConfig;
+use crate::evaluation::{evaluator_for, Evaluator};
+use crate::game_state::{Direction, GameState};
+use crate::search::MCTS;
+use rand::Rng;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Mixed strategies for both players of a zero-sum matrix game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equilibrium {
+    /// The probability of each row.
+    pub row: Vec<f32>,
+    /// The probability of each column.
+    pub column: Vec<f32>,
+    /// The row player's expected payoff when both play these strategies.
+    pub value: f32,
+}
+
+/// Solves the zero-sum game where the row player receives `payoffs[r][c]`
+/// and the column player loses it.
+///
+/// A game with a pure saddle point is solved exactly. Anything else is
+/// approximated with `iterations` rounds of regret matching+, whose
+/// averaged strategies converge on an equilibrium.
+pub fn solve_zero_sum(payoffs: &[Vec<f32>], iterations: u32) -> Equilibrium {
+    let rows = payoffs.len();
+    let columns = payoffs.first().map_or(0, |row| row.len());
+    if rows == 0 || columns == 0 {
+        return Equilibrium {
+            row: vec![1.0; rows.min(1)],
+            column: vec![1.0; columns.min(1)],
+            value: 0.0,
+        };
+    }
+
+    if let Some(equilibrium) = saddle_point(payoffs) {
+        return equilibrium;
+    }
+
+    let mut row_regret = vec![0.0f64; rows];
+    let mut column_regret = vec![0.0f64; columns];
+    let mut row_total = vec![0.0f64; rows];
+    let mut column_total = vec![0.0f64; columns];
+
+    for t in 1..=iterations.max(1) {
+        // The players update in turn, each against the other's latest
+        // strategy
+        let column = regret_matching(&column_regret);
+        let utilities: Vec<f64> = payoffs.iter().map(|row| dot(row, &column)).collect();
+        let row = regret_matching(&row_regret);
+        let expected: f64 = row.iter().zip(&utilities).map(|(p, u)| p * u).sum();
+        for (regret, utility) in row_regret.iter_mut().zip(&utilities) {
+            *regret = (*regret + utility - expected).max(0.0);
+        }
+
+        let row = regret_matching(&row_regret);
+        let utilities: Vec<f64> = (0..columns)
+            .map(|c| {
+                -payoffs
+                    .iter()
+                    .zip(&row)
+                    .map(|(r, p)| r[c] as f64 * p)
+                    .sum::<f64>()
+            })
+            .collect();
+        let expected: f64 = column.iter().zip(&utilities).map(|(p, u)| p * u).sum();
+        for (regret, utility) in column_regret.iter_mut().zip(&utilities) {
+            *regret = (*regret + utility - expected).max(0.0);
+        }
+
+        // Later rounds count for more, as in regret matching+
+        let weight = t as f64;
+        for (total, p) in row_total.iter_mut().zip(&row) {
+            *total += weight * p;
+        }
+        for (total, p) in column_total.iter_mut().zip(&column) {
+            *total += weight * p;
+        }
+    }
+
+    // The weighted totals, scaled to probabilities
+    let row = regret_matching(&row_total);
+    let column = regret_matching(&column_total);
+    let value = payoffs
+        .iter()
+        .zip(&row)
+        .map(|(payoffs, p)| p * dot(payoffs, &column))
+        .sum::<f64>();
+    Equilibrium {
+        row: row.iter().map(|&p| p as f32).collect(),
+        column: column.iter().map(|&p| p as f32).collect(),
+        value: value as f32,
+    }
+}
+
+/// The pure equilibrium, if the best of the row minimums is also the
+/// smallest of the column maximums.
+fn saddle_point(payoffs: &[Vec<f32>]) -> Option<Equilibrium> {
+    let columns = payoffs[0].len();
+    let (best_row, maximin) = payoffs
+        .iter()
+        .map(|row| row.iter().copied().fold(f32::INFINITY, f32::min))
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |best, (r, min)| {
+            if min > best.1 {
+                (r, min)
+            } else {
+                best
+            }
+        });
+    let (best_column, minimax) = (0..columns)
+        .map(|c| {
+            payoffs
+                .iter()
+                .map(|row| row[c])
+                .fold(f32::NEG_INFINITY, f32::max)
+        })
+        .enumerate()
+        .fold((0, f32::INFINITY), |best, (c, max)| {
+            if max < best.1 {
+                (c, max)
+            } else {
+                best
+            }
+        });
+
+    if maximin < minimax {
+        return None;
+    }
+    let mut row = vec![0.0; payoffs.len()];
+    let mut column = vec![0.0; columns];
+    row[best_row] = 1.0;
+    column[best_column] = 1.0;
+    Some(Equilibrium {
+        row,
+        column,
+        value: payoffs[best_row][best_column],
+    })
+}
+
+/// Plays each action in proportion to its positive regret, or uniformly if
+/// none has any.
+fn regret_matching(regrets: &[f64]) -> Vec<f64> {
+    let total: f64 = regrets.iter().sum();
+    if total > 0.0 {
+        regrets.iter().map(|r| r / total).collect()
+    } else {
+        vec![1.0 / regrets.len() as f64; regrets.len()]
+    }
+}
+
+fn dot(payoffs: &[f32], strategy: &[f64]) -> f64 {
+    payoffs
+        .iter()
+        .zip(strategy)
+        .map(|(&payoff, p)| payoff as f64 * p)
+        .sum()
+}
+
+/// How often a snake should play a move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveProbability {
+    pub direction: Direction,
+    pub probability: f32,
+}
+
+/// The outcome of a `MatrixGameSearch`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixGameResult {
+    /// Our most probable move.
+    pub best_move: Option<Direction>,
+    /// Our equilibrium strategy at the root.
+    pub strategy: Vec<MoveProbability>,
+    /// The opponent's equilibrium strategy at the root.
+    pub opponent_strategy: Vec<MoveProbability>,
+    /// Our expected score less theirs when both play the equilibrium.
+    pub value: f32,
+    /// The deepest search, in turns, that completed.
+    pub depth: u32,
+    /// Positions visited across all iterations.
+    pub nodes: u64,
+}
+
+impl MatrixGameResult {
+    /// A move drawn from our equilibrium strategy.
+    pub fn sample_move(&self, rng: &mut impl Rng) -> Option<Direction> {
+        let mut remaining: f32 = rng.gen_range(0.0..1.0);
+        for choice in &self.strategy {
+            if remaining < choice.probability {
+                return Some(choice.direction);
+            }
+            remaining -= choice.probability;
+        }
+        // Rounding left a sliver past the last move
+        self.best_move
+    }
+}
+
+/// Prints the depth and value, then both strategies.
+impl fmt::Display for MatrixGameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:?} at depth {} ({} nodes): {:.3}",
+            self.best_move, self.depth, self.nodes, self.value
+        )?;
+        for (name, strategy) in [("us", &self.strategy), ("them", &self.opponent_strategy)] {
+            write!(f, "  {}:", name)?;
+            for choice in strategy.iter() {
+                write!(f, " {:?} {:.2}", choice.direction, choice.probability)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// An iterative-deepening simultaneous-move search for one snake of a duel.
+pub struct MatrixGameSearch {
+    config: SearchConfig,
+    evaluator: Arc<dyn Evaluator>,
+}
+
+/// State of one iteration.
+struct Iteration {
+    us: usize,
+    them: usize,
+    deadline: Instant,
+    nodes: u64,
+}
+
+impl MatrixGameSearch {
+    pub fn new(config: &SearchConfig) -> Self {
+        MatrixGameSearch {
+            config: config.clone(),
+            evaluator: evaluator_for(config),
+        }
+    }
+
+    /// Scores leaves with `evaluator` instead of the config's heuristic.
+    pub fn set_evaluator(&mut self, evaluator: Arc<dyn Evaluator>) {
+        self.evaluator = evaluator;
+    }
+
+    /// Searches `state` for the snake with `snake_id`, one turn deeper per
+    /// iteration up to `matrix_game.max_depth`. No iteration starts after
+    /// `soft` and the search stops at `hard`, keeping the deepest completed
+    /// iteration.
+    ///
+    /// Only duels are searched; with any other number of snakes alive the
+    /// result has no strategy and `best_move` is our first safe move.
+    pub fn search(
+        &self,
+        state: &GameState,
+        snake_id: &str,
+        soft: Duration,
+        hard: Duration,
+    ) -> MatrixGameResult {
+        let start = Instant::now();
+        let state = &deterministic(state);
+        let mut result = MatrixGameResult {
+            best_move: None,
+            strategy: Vec::new(),
+            opponent_strategy: Vec::new(),
+            value: 0.0,
+            depth: 0,
+            nodes: 0,
+        };
+
+        let us = match state.snake_index(snake_id) {
+            Some(us) if state.snakes[us].health > 0 => us,
+            _ => return result,
+        };
+        result.best_move = moves(state, us).first().copied();
+        let alive: Vec<usize> = (0..state.snakes.len())
+            .filter(|&i| state.snakes[i].health > 0)
+            .collect();
+        let them = match alive[..] {
+            [a, b] => {
+                if a == us {
+                    b
+                } else {
+                    a
+                }
+            }
+            _ => return result,
+        };
+
+        for depth in 1..=self.config.matrix_game.max_depth {
+            if start.elapsed() >= soft {
+                break;
+            }
+            let mut iteration = Iteration {
+                us,
+                them,
+                deadline: start + hard,
+                nodes: 0,
+            };
+            let root = self.solve(state, depth, &mut iteration);
+            result.nodes += iteration.nodes;
+            let Some((our_moves, their_moves, equilibrium)) = root else {
+                break;
+            };
+
+            let probabilities = |moves: &[Direction], strategy: &[f32]| {
+                moves
+                    .iter()
+                    .zip(strategy)
+                    .map(|(&direction, &probability)| MoveProbability {
+                        direction,
+                        probability,
+                    })
+                    .collect::<Vec<_>>()
+            };
+            result.strategy = probabilities(&our_moves, &equilibrium.row);
+            result.opponent_strategy = probabilities(&their_moves, &equilibrium.column);
+            result.best_move = result
+                .strategy
+                .iter()
+                .fold(None::<MoveProbability>, |best, &choice| match best {
+                    Some(best) if best.probability >= choice.probability => Some(best),
+                    _ => Some(choice),
+                })
+                .map(|choice| choice.direction);
+            result.value = equilibrium.value;
+            result.depth = depth;
+        }
+
+        result
+    }
+
+    /// The matrix game at `state` with `depth` turns left to search: each
+    /// snake's moves and the equilibrium, or `None` if the deadline passed.
+    fn solve(
+        &self,
+        state: &GameState,
+        depth: u32,
+        iteration: &mut Iteration,
+    ) -> Option<(Vec<Direction>, Vec<Direction>, Equilibrium)> {
+        let (us, them) = (iteration.us, iteration.them);
+        let our_moves = moves(state, us);
+        let their_moves = moves(state, them);
+
+        let mut payoffs = Vec::with_capacity(our_moves.len());
+        for &ours in &our_moves {
+            let mut row = Vec::with_capacity(their_moves.len());
+            for &theirs in &their_moves {
+                let mut joint: Vec<Direction> = (0..state.snakes.len())
+                    .map(|i| state.default_move(i))
+                    .collect();
+                joint[us] = ours;
+                joint[them] = theirs;
+                let mut child = state.clone();
+                child.step(&joint);
+                row.push(self.value(&child, depth - 1, iteration)?);
+            }
+            payoffs.push(row);
+        }
+
+        let equilibrium = solve_zero_sum(&payoffs, self.config.matrix_game.solver_iterations);
+        Some((our_moves, their_moves, equilibrium))
+    }
+
+    /// Our payoff at `state` with `depth` turns left to search.
+    fn value(&self, state: &GameState, depth: u32, iteration: &mut Iteration) -> Option<f32> {
+        iteration.nodes += 1;
+        if Instant::now() >= iteration.deadline {
+            return None;
+        }
+        if depth == 0 || state.is_game_over() {
+            let scores = MCTS::default_policy(state, &self.config, self.evaluator.as_ref(), None);
+            return Some(scores[iteration.us] - scores[iteration.them]);
+        }
+        self.solve(state, depth, iteration)
+            .map(|(_, _, equilibrium)| equilibrium.value)
+    }
+}
+
+/// `snake`'s safe moves, or the move it is forced into if it has none.
+/// Moving into a wall or its own neck is never better than a safe move, so
+/// those are left out of the matrix.
+fn moves(state: &GameState, snake: usize) -> Vec<Direction> {
+    let moves = state.get_safe_moves(snake);
+    if moves.is_empty() {
+        vec![state.default_move(snake)]
+    } else {
+        moves
+    }
+}
@@ -0,0 +1,411 @@
+//! A small convolutional value and policy network, run on the CPU.
+//!
+//! The board is encoded once per snake, from that snake's point of view, as
+//! `INPUT_PLANES` planes of `width * height` values in board order. The
+//! planes go through 3x3 convolutions with ReLU, are averaged over the board
+//! so any board size gives the same number of features, and two dense heads
+//! read the features: a value in `0.0..=1.0` through a sigmoid, and priors
+//! over `Direction::ALL` through a softmax.
+//!
+//! Weights are loaded from a JSON file holding `NetworkWeights`. Training
+//! happens elsewhere; this module only runs the network.
+//! `NetworkEvaluator` plugs its values into the search as an `Evaluator`;
+//! the search doesn't use the priors.
+
+use crate::evaluation::Evaluator;
+use crate::game_state::{Direction, GameState};
+use crate::rules::SNAKE_MAX_HEALTH;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Planes in an encoded position, in this order:
+///
+/// 0. our head
+/// 1. our body, head and tail included
+/// 2. our tail
+/// 3. the other snakes' heads
+/// 4. the other snakes' bodies, heads and tails included
+/// 5. the other snakes' tails
+/// 6. food
+/// 7. hazards, counting stacked hazards
+/// 8. our health, over every square
+/// 9. the healthiest other snake's health, over every square
+pub const INPUT_PLANES: usize = 10;
+
+/// One 3x3 convolution, zero padded so the board keeps its size.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConvLayer {
+    pub in_channels: usize,
+    pub out_channels: usize,
+    /// `out_channels * in_channels * 9` weights, each output channel's
+    /// kernels together, each kernel row by row from the top.
+    pub weights: Vec<f32>,
+    /// One per output channel.
+    pub bias: Vec<f32>,
+}
+
+/// A fully connected layer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DenseLayer {
+    pub inputs: usize,
+    pub outputs: usize,
+    /// `outputs * inputs` weights, each output's together.
+    pub weights: Vec<f32>,
+    /// One per output.
+    pub bias: Vec<f32>,
+}
+
+/// Everything a `Network` is made of, as stored in a weights file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkWeights {
+    /// Applied in order; the first takes `INPUT_PLANES` channels.
+    pub conv: Vec<ConvLayer>,
+    /// One output, the value before the sigmoid.
+    pub value: DenseLayer,
+    /// One output per direction of `Direction::ALL`, before the softmax.
+    pub policy: DenseLayer,
+}
+
+/// Why a `Network` couldn't be built.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkError {
+    /// The network heuristic is configured without a weights file.
+    NoWeights,
+    /// The weights file couldn't be read.
+    Io(String),
+    /// The weights file isn't valid JSON weights.
+    Parse(String),
+    /// The layers don't fit together.
+    Shape(String),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::NoWeights => write!(f, "no network weights file configured"),
+            NetworkError::Io(e) => write!(f, "couldn't read network weights: {}", e),
+            NetworkError::Parse(e) => write!(f, "invalid network weights: {}", e),
+            NetworkError::Shape(e) => write!(f, "network layers don't fit: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+/// What the network makes of a position for one snake.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prediction {
+    /// How good the position is for the snake, in `0.0..=1.0`.
+    pub value: f32,
+    /// The probability of each move of `Direction::ALL`. Moves into walls
+    /// and bodies aren't ruled out.
+    pub priors: [f32; 4],
+}
+
+/// A network with weights that fit together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    weights: NetworkWeights,
+}
+
+impl Network {
+    pub fn new(weights: NetworkWeights) -> Result<Self, NetworkError> {
+        let mut channels = INPUT_PLANES;
+        for (i, layer) in weights.conv.iter().enumerate() {
+            if layer.in_channels != channels {
+                return Err(NetworkError::Shape(format!(
+                    "conv layer {} takes {} channels, but gets {}",
+                    i, layer.in_channels, channels
+                )));
+            }
+            if layer.weights.len() != layer.out_channels * layer.in_channels * 9
+                || layer.bias.len() != layer.out_channels
+            {
+                return Err(NetworkError::Shape(format!(
+                    "conv layer {} needs {} weights and {} biases",
+                    i,
+                    layer.out_channels * layer.in_channels * 9,
+                    layer.out_channels
+                )));
+            }
+            channels = layer.out_channels;
+        }
+        for (name, layer, outputs) in [
+            ("value", &weights.value, 1),
+            ("policy", &weights.policy, Direction::ALL.len()),
+        ] {
+            if layer.inputs != channels || layer.outputs != outputs {
+                return Err(NetworkError::Shape(format!(
+                    "the {} head must map {} features to {} outputs",
+                    name, channels, outputs
+                )));
+            }
+            if layer.weights.len() != layer.inputs * layer.outputs
+                || layer.bias.len() != layer.outputs
+            {
+                return Err(NetworkError::Shape(format!(
+                    "the {} head needs {} weights and {} biases",
+                    name,
+                    layer.inputs * layer.outputs,
+                    layer.outputs
+                )));
+            }
+        }
+        Ok(Network { weights })
+    }
+
+    /// Reads weights from a JSON file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, NetworkError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| NetworkError::Io(format!("{}: {}", path.display(), e)))?;
+        Self::from_json(&contents)
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self, NetworkError> {
+        let weights: NetworkWeights =
+            serde_json::from_str(contents).map_err(|e| NetworkError::Parse(e.to_string()))?;
+        Self::new(weights)
+    }
+
+    /// The weights as a weights file would hold them.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.weights).expect("network weights always serialise")
+    }
+
+    pub fn weights(&self) -> &NetworkWeights {
+        &self.weights
+    }
+
+    /// A network with a conv layer of each of `channels` widths and small
+    /// random weights, for benchmarks and tests.
+    pub fn random<R: Rng>(channels: &[usize], rng: &mut R) -> Self {
+        let mut random = |n: usize, fan_in: usize| -> Vec<f32> {
+            let scale = (1.0 / fan_in as f32).sqrt();
+            (0..n).map(|_| rng.gen_range(-scale..scale)).collect()
+        };
+
+        let mut conv = Vec::with_capacity(channels.len());
+        let mut in_channels = INPUT_PLANES;
+        for &out_channels in channels {
+            conv.push(ConvLayer {
+                in_channels,
+                out_channels,
+                weights: random(out_channels * in_channels * 9, in_channels * 9),
+                bias: vec![0.0; out_channels],
+            });
+            in_channels = out_channels;
+        }
+        let value = DenseLayer {
+            inputs: in_channels,
+            outputs: 1,
+            weights: random(in_channels, in_channels),
+            bias: vec![0.0],
+        };
+        let policy = DenseLayer {
+            inputs: in_channels,
+            outputs: Direction::ALL.len(),
+            weights: random(in_channels * Direction::ALL.len(), in_channels),
+            bias: vec![0.0; Direction::ALL.len()],
+        };
+
+        Network::new(NetworkWeights {
+            conv,
+            value,
+            policy,
+        })
+        .expect("random layers are built to fit")
+    }
+
+    /// The input planes for the snake at `snake_index`.
+    pub fn encode(state: &GameState, snake_index: usize) -> Vec<f32> {
+        let board_size = state.width * state.height;
+        let mut planes = vec![0.0; INPUT_PLANES * board_size];
+        let mut set = |plane: usize, index: usize, value: f32| {
+            if index != usize::MAX {
+                planes[plane * board_size + index] += value;
+            }
+        };
+
+        let mut their_health = 0;
+        for (i, snake) in state.snakes.iter().enumerate() {
+            if snake.health == 0 {
+                continue;
+            }
+            let ours = i == snake_index;
+            if !ours {
+                their_health = their_health.max(snake.health);
+            }
+            let offset = if ours { 0 } else { 3 };
+            set(offset, snake.head().index, 1.0);
+            for part in &snake.body {
+                set(offset + 1, part.index, 1.0);
+            }
+            if let Some(tail) = snake.body.back() {
+                set(offset + 2, tail.index, 1.0);
+            }
+        }
+        for food in &state.food {
+            set(6, food.index, 1.0);
+        }
+        for hazard in &state.hazards {
+            set(7, hazard.index, 1.0);
+        }
+
+        let our_health = state.snakes[snake_index].health as f32 / SNAKE_MAX_HEALTH as f32;
+        let their_health = their_health as f32 / SNAKE_MAX_HEALTH as f32;
+        planes[8 * board_size..9 * board_size].fill(our_health);
+        planes[9 * board_size..].fill(their_health);
+        planes
+    }
+
+    /// Runs the network on planes from `encode` for a `width` by `height`
+    /// board.
+    pub fn forward(&self, planes: &[f32], width: usize, height: usize) -> Prediction {
+        let board_size = width * height;
+        let mut activations = planes.to_vec();
+        for layer in &self.weights.conv {
+            activations = convolve(layer, &activations, width, height);
+        }
+
+        let channels = activations.len() / board_size.max(1);
+        let features: Vec<f32> = (0..channels)
+            .map(|c| {
+                activations[c * board_size..(c + 1) * board_size]
+                    .iter()
+                    .sum::<f32>()
+            })
+            .map(|sum| sum / board_size.max(1) as f32)
+            .collect();
+
+        let value = dense(&self.weights.value, &features)[0];
+        let logits = dense(&self.weights.policy, &features);
+        let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let exps: Vec<f32> = logits.iter().map(|l| (l - max).exp()).collect();
+        let total: f32 = exps.iter().sum();
+
+        let mut priors = [0.0; 4];
+        for (prior, exp) in priors.iter_mut().zip(&exps) {
+            *prior = exp / total;
+        }
+        Prediction {
+            value: 1.0 / (1.0 + (-value).exp()),
+            priors,
+        }
+    }
+
+    /// A prediction for every snake, in the order of `state.snakes`.
+    /// Eliminated snakes get a value of 0 and no priors.
+    pub fn predict(&self, state: &GameState) -> Vec<Prediction> {
+        (0..state.snakes.len())
+            .map(|i| {
+                let snake = &state.snakes[i];
+                if snake.health == 0 || snake.head().index == usize::MAX {
+                    return Prediction {
+                        value: 0.0,
+                        priors: [0.0; 4],
+                    };
+                }
+                let planes = Self::encode(state, i);
+                self.forward(&planes, state.width, state.height)
+            })
+            .collect()
+    }
+}
+
+/// Networks read by `load_cached`, by path.
+type LoadedNetworks = HashMap<PathBuf, Arc<Network>>;
+
+/// A network loaded from `path`, read only the first time it loads. Failures
+/// aren't remembered, so a missing or broken file is read again on the next
+/// call.
+pub fn load_cached(path: impl AsRef<Path>) -> Result<Arc<Network>, NetworkError> {
+    static NETWORKS: OnceLock<Mutex<LoadedNetworks>> = OnceLock::new();
+
+    let path = path.as_ref();
+    let mut networks = NETWORKS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(network) = networks.get(path) {
+        return Ok(Arc::clone(network));
+    }
+    let network = Arc::new(Network::from_file(path)?);
+    networks.insert(path.to_path_buf(), Arc::clone(&network));
+    Ok(network)
+}
+
+/// Scores positions with a `Network`'s values.
+#[derive(Debug, Clone)]
+pub struct NetworkEvaluator {
+    network: Arc<Network>,
+}
+
+impl NetworkEvaluator {
+    pub fn new(network: Arc<Network>) -> Self {
+        NetworkEvaluator { network }
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+}
+
+impl Evaluator for NetworkEvaluator {
+    fn evaluate(&self, state: &GameState) -> Vec<f32> {
+        self.network
+            .predict(state)
+            .into_iter()
+            .map(|prediction| prediction.value)
+            .collect()
+    }
+}
+
+fn convolve(layer: &ConvLayer, input: &[f32], width: usize, height: usize) -> Vec<f32> {
+    let board_size = width * height;
+    let mut output = vec![0.0; layer.out_channels * board_size];
+
+    for out in 0..layer.out_channels {
+        let plane = &mut output[out * board_size..(out + 1) * board_size];
+        plane.fill(layer.bias[out]);
+        for channel in 0..layer.in_channels {
+            let source = &input[channel * board_size..(channel + 1) * board_size];
+            let kernel = &layer.weights[(out * layer.in_channels + channel) * 9..][..9];
+            for y in 0..height {
+                for x in 0..width {
+                    let mut sum = 0.0;
+                    for ky in 0..3 {
+                        let Some(sy) = (y + ky).checked_sub(1).filter(|&sy| sy < height) else {
+                            continue;
+                        };
+                        for kx in 0..3 {
+                            let Some(sx) = (x + kx).checked_sub(1).filter(|&sx| sx < width) else {
+                                continue;
+                            };
+                            sum += kernel[ky * 3 + kx] * source[sy * width + sx];
+                        }
+                    }
+                    plane[y * width + x] += sum;
+                }
+            }
+        }
+        for value in plane.iter_mut() {
+            *value = value.max(0.0);
+        }
+    }
+
+    output
+}
+
+fn dense(layer: &DenseLayer, input: &[f32]) -> Vec<f32> {
+    (0..layer.outputs)
+        .map(|out| {
+            let weights = &layer.weights[out * layer.inputs..(out + 1) * layer.inputs];
+            layer.bias[out] + weights.iter().zip(input).map(|(w, x)| w * x).sum::<f32>()
+        })
+        .collect()
+}
@@ -0,0 +1,835 @@
+//! Battlesnake rulesets, expressed as the same ordered stages the reference
+//! engine runs every turn. The "standard" pipeline is:
+//!
+//! 1. move snakes
+//! 2. reduce health
+//! 3. damage hazards
+//! 4. feed snakes
+//! 5. eliminate snakes
+//! 6. spawn food
+//!
+//! Other game modes implement `Ruleset` by swapping out or appending stages.
+//! Rulesets run the stages through `GameBoard`, so the same rules play out on
+//! a `GameState` and on the bitboard states.
+//! Snakes that are eliminated keep their body for inspection but have their
+//! health set to 0 and an `Elimination` recorded, and are skipped by every
+//! later stage.
+
+use crate::game_state::{Direction, Elimination, EliminationCause, GameState, Position, Snake};
+use crate::zobrist;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Health a snake is restored to when it eats.
+pub const SNAKE_MAX_HEALTH: u8 = 100;
+
+/// Damage dealt for each hazard stacked on a snake's head, per turn.
+pub const DEFAULT_HAZARD_DAMAGE_PER_TURN: u8 = 14;
+
+/// How often royale games grow the hazard border.
+pub const DEFAULT_SHRINK_EVERY_N_TURNS: u32 = 25;
+
+/// Percentage chance of a food spawning on a turn with enough food.
+pub const DEFAULT_FOOD_SPAWN_CHANCE: u8 = 15;
+
+/// Food is topped up to this amount every turn.
+pub const DEFAULT_MINIMUM_FOOD: usize = 1;
+
+/// Game parameters that the rules stages read instead of hardcoding. The
+/// defaults match the reference engine's defaults. Keys are named as in the
+/// API's `game.ruleset.settings`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    /// Percentage chance (0-100) of spawning a food each turn.
+    pub food_spawn_chance: u8,
+    pub minimum_food: usize,
+    pub hazard_damage_per_turn: u8,
+    pub royale: RoyaleSettings,
+    pub squad: SquadSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            food_spawn_chance: DEFAULT_FOOD_SPAWN_CHANCE,
+            minimum_food: DEFAULT_MINIMUM_FOOD,
+            hazard_damage_per_turn: DEFAULT_HAZARD_DAMAGE_PER_TURN,
+            royale: RoyaleSettings::default(),
+            squad: SquadSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RoyaleSettings {
+    pub shrink_every_n_turns: u32,
+}
+
+impl Default for RoyaleSettings {
+    fn default() -> Self {
+        RoyaleSettings {
+            shrink_every_n_turns: DEFAULT_SHRINK_EVERY_N_TURNS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SquadSettings {
+    pub allow_body_collisions: bool,
+    pub shared_elimination: bool,
+    pub shared_health: bool,
+    pub shared_length: bool,
+}
+
+impl Default for SquadSettings {
+    fn default() -> Self {
+        SquadSettings {
+            allow_body_collisions: true,
+            shared_elimination: true,
+            shared_health: true,
+            shared_length: true,
+        }
+    }
+}
+
+/// The game modes we know how to simulate, as named by the API's
+/// `game.ruleset.name`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    #[default]
+    Standard,
+    Wrapped,
+    Constrictor,
+    Royale,
+    Solo,
+    Squad,
+}
+
+impl GameMode {
+    /// Maps a ruleset name from the API onto a game mode, falling back to
+    /// standard rules for anything we don't recognise.
+    pub fn from_name(name: &str) -> GameMode {
+        match name {
+            "wrapped" => GameMode::Wrapped,
+            "constrictor" => GameMode::Constrictor,
+            "royale" => GameMode::Royale,
+            "solo" => GameMode::Solo,
+            "squad" => GameMode::Squad,
+            _ => GameMode::Standard,
+        }
+    }
+
+    pub fn ruleset(self) -> &'static dyn Ruleset {
+        match self {
+            GameMode::Standard => &StandardRuleset,
+            GameMode::Wrapped => &WrappedRuleset,
+            GameMode::Constrictor => &ConstrictorRuleset,
+            GameMode::Royale => &RoyaleRuleset,
+            GameMode::Solo => &SoloRuleset,
+            GameMode::Squad => &SquadRuleset,
+        }
+    }
+}
+
+/// A position the rules can be played on, one method per stage. `GameState`
+/// implements it below and the bitboard states in `bitboard`, so every
+/// `Ruleset` is written once for both.
+pub trait GameBoard {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn settings(&self) -> &Settings;
+
+    /// Snakes with health left.
+    fn alive_snakes(&self) -> usize;
+
+    /// Squads with a snake that has health left. Snakes without a squad are
+    /// a squad of their own.
+    fn alive_squads(&self) -> usize;
+
+    /// Stage 1: every snake still in the game moves. `moves` holds one
+    /// direction per snake index; a snake without one uses its default move.
+    fn move_snakes(&mut self, moves: &[Direction]);
+
+    /// Stage 2.
+    fn reduce_snake_health(&mut self);
+
+    /// Stage 3.
+    fn damage_hazards(&mut self);
+
+    /// Stage 4.
+    fn feed_snakes(&mut self);
+
+    /// Stage 5. Unless `squadmates_collide`, snakes pass through their
+    /// squadmates' bodies.
+    fn eliminate_snakes(&mut self, squadmates_collide: bool);
+
+    /// Stage 6.
+    fn spawn_food(&mut self);
+
+    /// Removes every food from the board.
+    fn clear_food(&mut self);
+
+    /// Constrictor stage.
+    fn grow_snakes(&mut self);
+
+    /// Royale stage.
+    fn shrink_safe_area(&mut self);
+
+    /// Squad stage.
+    fn share_squad_attributes(&mut self);
+
+    fn advance_turn(&mut self);
+}
+
+/// A set of game rules. The provided methods are the standard ruleset; each
+/// game mode overrides the parts that differ.
+pub trait Ruleset: Sync {
+    fn name(&self) -> &'static str;
+
+    /// The index reached by moving from `index` in `direction`, or
+    /// `usize::MAX` if that leaves the board.
+    fn neighbour(&self, board: &dyn GameBoard, index: usize, direction: Direction) -> usize {
+        bounded_neighbour(board.width(), board.height(), index, direction)
+    }
+
+    /// Whether tails move up behind the snake every turn. When they don't,
+    /// every body segment stays occupied for the rest of the game.
+    fn tails_retract(&self) -> bool {
+        true
+    }
+
+    /// Whether the game has finished.
+    fn is_game_over(&self, board: &dyn GameBoard) -> bool {
+        board.alive_snakes() <= 1
+    }
+
+    /// Plays a full turn: every snake moves, then the turn is resolved.
+    fn step(&self, board: &mut dyn GameBoard, moves: &[Direction]) {
+        board.move_snakes(moves);
+        self.resolve_turn(board);
+    }
+
+    /// Runs every stage after movement and advances the turn counter.
+    fn resolve_turn(&self, board: &mut dyn GameBoard) {
+        board.reduce_snake_health();
+        board.damage_hazards();
+        board.feed_snakes();
+        board.eliminate_snakes(true);
+        board.spawn_food();
+        board.advance_turn();
+    }
+}
+
+pub struct StandardRuleset;
+
+impl Ruleset for StandardRuleset {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+}
+
+/// Standard rules on a board whose edges wrap around to the opposite side.
+pub struct WrappedRuleset;
+
+impl Ruleset for WrappedRuleset {
+    fn name(&self) -> &'static str {
+        "wrapped"
+    }
+
+    fn neighbour(&self, board: &dyn GameBoard, index: usize, direction: Direction) -> usize {
+        wrapped_neighbour(board.width(), board.height(), index, direction)
+    }
+}
+
+/// Standard rules, except there is no food and every snake grows and is
+/// restored to full health every turn, so tails never move.
+pub struct ConstrictorRuleset;
+
+impl Ruleset for ConstrictorRuleset {
+    fn name(&self) -> &'static str {
+        "constrictor"
+    }
+
+    fn tails_retract(&self) -> bool {
+        false
+    }
+
+    fn resolve_turn(&self, board: &mut dyn GameBoard) {
+        board.reduce_snake_health();
+        board.damage_hazards();
+        board.feed_snakes();
+        board.eliminate_snakes(true);
+        board.clear_food();
+        board.grow_snakes();
+        board.advance_turn();
+    }
+}
+
+/// Standard rules, with the hazard border growing by one row or column on a
+/// random side every `shrink_every_n_turns` turns.
+pub struct RoyaleRuleset;
+
+impl Ruleset for RoyaleRuleset {
+    fn name(&self) -> &'static str {
+        "royale"
+    }
+
+    fn resolve_turn(&self, board: &mut dyn GameBoard) {
+        board.reduce_snake_health();
+        board.damage_hazards();
+        board.feed_snakes();
+        board.eliminate_snakes(true);
+        board.spawn_food();
+        board.advance_turn();
+        board.shrink_safe_area();
+    }
+}
+
+/// Standard rules for a single snake: the game only ends once it dies.
+pub struct SoloRuleset;
+
+impl Ruleset for SoloRuleset {
+    fn name(&self) -> &'static str {
+        "solo"
+    }
+
+    fn is_game_over(&self, board: &dyn GameBoard) -> bool {
+        board.alive_snakes() == 0
+    }
+}
+
+/// Standard rules played in teams. Depending on the squad settings,
+/// squadmates can move through each other's bodies and share eliminations,
+/// health and length. The game ends when only one squad is left.
+pub struct SquadRuleset;
+
+impl Ruleset for SquadRuleset {
+    fn name(&self) -> &'static str {
+        "squad"
+    }
+
+    fn is_game_over(&self, board: &dyn GameBoard) -> bool {
+        board.alive_squads() <= 1
+    }
+
+    fn resolve_turn(&self, board: &mut dyn GameBoard) {
+        board.reduce_snake_health();
+        board.damage_hazards();
+        board.feed_snakes();
+        let squadmates_collide = !board.settings().squad.allow_body_collisions;
+        board.eliminate_snakes(squadmates_collide);
+        board.share_squad_attributes();
+        board.spawn_food();
+        board.advance_turn();
+    }
+}
+
+/// The stages of `rules` on a `GameState`.
+impl GameBoard for GameState {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    fn alive_snakes(&self) -> usize {
+        alive_snakes(self)
+    }
+
+    fn alive_squads(&self) -> usize {
+        let alive: Vec<&Snake> = self.snakes.iter().filter(|s| s.health > 0).collect();
+        (0..alive.len())
+            .filter(|&i| !alive[..i].iter().any(|other| same_squad(alive[i], other)))
+            .count()
+    }
+
+    fn move_snakes(&mut self, moves: &[Direction]) {
+        move_snakes(self, moves);
+    }
+
+    fn reduce_snake_health(&mut self) {
+        reduce_snake_health(self);
+    }
+
+    fn damage_hazards(&mut self) {
+        damage_hazards(self);
+    }
+
+    fn feed_snakes(&mut self) {
+        feed_snakes(self);
+    }
+
+    fn eliminate_snakes(&mut self, squadmates_collide: bool) {
+        if squadmates_collide {
+            eliminate_snakes(self);
+        } else {
+            eliminate_snakes_where(self, |snake, other| !same_squad(snake, other));
+        }
+    }
+
+    fn spawn_food(&mut self) {
+        spawn_food(self);
+    }
+
+    fn clear_food(&mut self) {
+        self.food.clear();
+    }
+
+    fn grow_snakes(&mut self) {
+        grow_snakes(self);
+    }
+
+    fn shrink_safe_area(&mut self) {
+        shrink_safe_area(self);
+    }
+
+    fn share_squad_attributes(&mut self) {
+        share_squad_attributes(self);
+    }
+
+    fn advance_turn(&mut self) {
+        self.turn += 1;
+    }
+}
+
+/// Plays a full turn under the game's ruleset. `moves` holds one direction
+/// per snake index. A snake without an entry uses its default move, as the
+/// reference engine does for missing moves.
+pub fn step(game_state: &mut GameState, moves: &[Direction]) {
+    let before = zobrist::turn_hash(game_state);
+    game_state.mode.ruleset().step(game_state, moves);
+    update_turn_hash(game_state, before);
+}
+
+/// Runs every stage after movement under the game's ruleset.
+pub fn resolve_turn(game_state: &mut GameState) {
+    let before = zobrist::turn_hash(game_state);
+    game_state.mode.ruleset().resolve_turn(game_state);
+    update_turn_hash(game_state, before);
+}
+
+/// Bodies are hashed as they change; the rest of the hash is swapped out once
+/// the turn is over.
+fn update_turn_hash(game_state: &mut GameState, before: u64) {
+    game_state.hash = game_state
+        .hash
+        .wrapping_sub(before)
+        .wrapping_add(zobrist::turn_hash(game_state));
+}
+
+/// Stage 1: every snake still in the game moves its head one square and drops
+/// its tail. Heads that leave the board are stored as `usize::MAX`.
+pub fn move_snakes(game_state: &mut GameState, moves: &[Direction]) {
+    for i in 0..game_state.snakes.len() {
+        let direction = moves
+            .get(i)
+            .copied()
+            .unwrap_or_else(|| game_state.default_move(i));
+        game_state.move_snake(i, direction);
+    }
+}
+
+/// Stage 2: every snake still in the game loses one point of health.
+pub fn reduce_snake_health(game_state: &mut GameState) {
+    for snake in game_state.snakes.iter_mut() {
+        if snake.is_eliminated() {
+            continue;
+        }
+        snake.health = snake.health.saturating_sub(1);
+    }
+}
+
+/// Stage 3: snakes whose head is on a hazard take the configured hazard
+/// damage once per hazard stacked on that square, unless the square also
+/// holds food. A snake that runs out of health here is eliminated by the
+/// hazard.
+pub fn damage_hazards(game_state: &mut GameState) {
+    let next_turn = game_state.turn + 1;
+    let damage = game_state.settings.hazard_damage_per_turn;
+
+    for snake in game_state.snakes.iter_mut() {
+        if snake.is_eliminated() {
+            continue;
+        }
+
+        let head = snake.head();
+        if game_state.food.contains(&head) {
+            continue;
+        }
+
+        for hazard in &game_state.hazards {
+            if *hazard != head {
+                continue;
+            }
+            snake.health = snake.health.saturating_sub(damage);
+            if snake.health == 0 {
+                snake.eliminated = Some(Elimination {
+                    cause: EliminationCause::Hazard,
+                    by: None,
+                    turn: next_turn,
+                });
+                break;
+            }
+        }
+    }
+}
+
+/// Stage 4: every snake whose head is on food grows by one segment (a copy of
+/// its tail) and is restored to full health. Food is removed once eaten; if
+/// several snakes reach the same food they all eat it.
+pub fn feed_snakes(game_state: &mut GameState) {
+    let mut remaining_food = Vec::with_capacity(game_state.food.len());
+
+    for f in 0..game_state.food.len() {
+        let food = game_state.food[f];
+        let mut eaten = false;
+
+        for i in 0..game_state.snakes.len() {
+            let snake = &game_state.snakes[i];
+            if snake.is_eliminated() || snake.body.is_empty() {
+                continue;
+            }
+            if snake.head() == food {
+                game_state.grow_snake(i);
+                game_state.snakes[i].health = SNAKE_MAX_HEALTH;
+                eaten = true;
+            }
+        }
+
+        if !eaten {
+            remaining_food.push(food);
+        }
+    }
+
+    game_state.food = remaining_food;
+}
+
+/// Stage 5: snakes that are out of health or off the board are eliminated
+/// first. The survivors are then checked for self collisions, body collisions
+/// and lost head-to-heads, in that order, against the board as it stood before
+/// any of those collisions were applied. Credit for a collision goes to the
+/// longest snake involved.
+pub fn eliminate_snakes(game_state: &mut GameState) {
+    eliminate_snakes_where(game_state, |_, _| true);
+}
+
+/// `eliminate_snakes`, where a snake only collides with another snake's body
+/// if `can_collide(snake, other)` holds.
+pub fn eliminate_snakes_where(
+    game_state: &mut GameState,
+    can_collide: impl Fn(&Snake, &Snake) -> bool,
+) {
+    let next_turn = game_state.turn + 1;
+
+    // Longest first, so that multi-snake collisions are attributed to the
+    // longest snake.
+    let mut by_length: Vec<usize> = (0..game_state.snakes.len()).collect();
+    by_length.sort_by_key(|&i| std::cmp::Reverse(game_state.snakes[i].length()));
+
+    for snake in game_state.snakes.iter_mut() {
+        if snake.is_eliminated() {
+            continue;
+        }
+
+        let cause = if snake.health == 0 {
+            EliminationCause::OutOfHealth
+        } else if snake.head().index == usize::MAX {
+            EliminationCause::OutOfBounds
+        } else {
+            continue;
+        };
+
+        snake.eliminated = Some(Elimination {
+            cause,
+            by: None,
+            turn: next_turn,
+        });
+    }
+
+    let mut collisions: Vec<(usize, EliminationCause, usize)> = Vec::new();
+
+    for (i, snake) in game_state.snakes.iter().enumerate() {
+        if snake.is_eliminated() {
+            continue;
+        }
+
+        let head = snake.head();
+
+        if has_body_collided(head, &snake.body) {
+            collisions.push((i, EliminationCause::SelfCollision, i));
+            continue;
+        }
+
+        let body_hit = by_length.iter().copied().find(|&other| {
+            let other_snake = &game_state.snakes[other];
+            other != i
+                && !other_snake.is_eliminated()
+                && can_collide(snake, other_snake)
+                && has_body_collided(head, &other_snake.body)
+        });
+        if let Some(other) = body_hit {
+            collisions.push((i, EliminationCause::Collision, other));
+            continue;
+        }
+
+        let head_hit = by_length.iter().copied().find(|&other| {
+            let other_snake = &game_state.snakes[other];
+            other != i
+                && !other_snake.is_eliminated()
+                && other_snake.head() == head
+                && snake.length() <= other_snake.length()
+        });
+        if let Some(other) = head_hit {
+            collisions.push((i, EliminationCause::HeadToHeadCollision, other));
+        }
+    }
+
+    for (i, cause, by) in collisions {
+        let by = game_state.snakes[by].id.clone();
+        game_state.snakes[i].eliminated = Some(Elimination {
+            cause,
+            by: Some(by),
+            turn: next_turn,
+        });
+    }
+
+    for snake in game_state.snakes.iter_mut() {
+        if snake.is_eliminated() {
+            snake.health = 0;
+        }
+    }
+}
+
+/// Whether `head` lands on any segment of `body` other than its head.
+fn has_body_collided(head: Position, body: &VecDeque<Position>) -> bool {
+    body.iter().skip(1).any(|&segment| segment == head)
+}
+
+/// Constrictor stage: every snake still in the game grows by one segment and
+/// is restored to full health.
+pub fn grow_snakes(game_state: &mut GameState) {
+    for i in 0..game_state.snakes.len() {
+        if game_state.snakes[i].is_eliminated() {
+            continue;
+        }
+        game_state.grow_snake(i);
+        game_state.snakes[i].health = SNAKE_MAX_HEALTH;
+    }
+}
+
+/// Stage 6: food is topped up to the minimum, and otherwise a single food
+/// spawns with the configured chance. New food only lands on empty squares.
+pub fn spawn_food(game_state: &mut GameState) {
+    let settings = &game_state.settings;
+    let current_food = game_state.food.len();
+    let mut rng = rand::thread_rng();
+
+    let to_spawn = if current_food < settings.minimum_food {
+        settings.minimum_food - current_food
+    } else if settings.food_spawn_chance > 0 && rng.gen_range(0..100) < settings.food_spawn_chance {
+        1
+    } else {
+        0
+    };
+    if to_spawn == 0 {
+        return;
+    }
+
+    let mut occupied = vec![false; game_state.width * game_state.height];
+    let snake_cells = game_state
+        .snakes
+        .iter()
+        .filter(|s| !s.is_eliminated())
+        .flat_map(|s| s.body.iter());
+    for position in snake_cells.chain(game_state.food.iter()) {
+        if position.index < occupied.len() {
+            occupied[position.index] = true;
+        }
+    }
+
+    let mut free: Vec<usize> = (0..occupied.len()).filter(|&i| !occupied[i]).collect();
+    for _ in 0..to_spawn {
+        if free.is_empty() {
+            break;
+        }
+        let index = free.swap_remove(rng.gen_range(0..free.len()));
+        game_state.food.push(Position { index });
+    }
+}
+
+/// Royale stage: on every turn that is a multiple of the configured
+/// `shrink_every_n_turns`, hazards spread one row or column in from a random
+/// side of the remaining safe area.
+pub fn shrink_safe_area(game_state: &mut GameState) {
+    let shrink_every_n_turns = game_state.settings.royale.shrink_every_n_turns;
+    if shrink_every_n_turns == 0
+        || game_state.turn == 0
+        || !game_state.turn.is_multiple_of(shrink_every_n_turns)
+    {
+        return;
+    }
+
+    let width = game_state.width;
+    let height = game_state.height;
+    let mut is_hazard = vec![false; width * height];
+    for hazard in &game_state.hazards {
+        if hazard.index < is_hazard.len() {
+            is_hazard[hazard.index] = true;
+        }
+    }
+
+    // Bounding box of the cells that are still safe
+    let (mut min_x, mut max_x, mut min_y, mut max_y) = (isize::MAX, -1, isize::MAX, -1);
+    for i in (0..width * height).filter(|&i| !is_hazard[i]) {
+        let (x, y) = ((i % width) as isize, (i / width) as isize);
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+    }
+    if max_x < 0 {
+        return; // Already all hazard
+    }
+
+    match rand::thread_rng().gen_range(0..4) {
+        0 => min_x += 1,
+        1 => max_x -= 1,
+        2 => min_y += 1,
+        _ => max_y -= 1,
+    }
+
+    for (i, hazard) in is_hazard.iter().enumerate() {
+        let (x, y) = ((i % width) as isize, (i / width) as isize);
+        let outside = x < min_x || x > max_x || y < min_y || y > max_y;
+        if outside && !hazard {
+            game_state.hazards.push(Position { index: i });
+        }
+    }
+}
+
+/// Squad stage: depending on the squad settings, if any snake in a squad was
+/// eliminated so is the rest of its squad, and survivors share the best
+/// health and length in their squad.
+pub fn share_squad_attributes(game_state: &mut GameState) {
+    let next_turn = game_state.turn + 1;
+    let squad_settings = game_state.settings.squad.clone();
+    let snakes = &game_state.snakes;
+
+    let squad_eliminated: Vec<bool> = snakes
+        .iter()
+        .map(|snake| {
+            snakes
+                .iter()
+                .any(|other| same_squad(snake, other) && other.is_eliminated())
+        })
+        .collect();
+
+    let squad_best: Vec<(u8, usize)> = snakes
+        .iter()
+        .map(|snake| {
+            snakes
+                .iter()
+                .filter(|other| same_squad(snake, other) && !other.is_eliminated())
+                .fold((0, 0), |(health, length), other| {
+                    (health.max(other.health), length.max(other.length()))
+                })
+        })
+        .collect();
+
+    for i in 0..game_state.snakes.len() {
+        let snake = &mut game_state.snakes[i];
+        if snake.is_eliminated() {
+            continue;
+        }
+        if squad_settings.shared_elimination && squad_eliminated[i] {
+            snake.eliminated = Some(Elimination {
+                cause: EliminationCause::Squad,
+                by: None,
+                turn: next_turn,
+            });
+            snake.health = 0;
+            continue;
+        }
+
+        let (health, length) = squad_best[i];
+        if squad_settings.shared_health {
+            snake.health = health;
+        }
+        while squad_settings.shared_length && game_state.snakes[i].length() < length {
+            game_state.grow_snake(i);
+        }
+    }
+}
+
+/// The index reached by moving from `index` in `direction` on a board whose
+/// edges are walls, or `usize::MAX` if that leaves the board.
+pub fn bounded_neighbour(width: usize, height: usize, index: usize, direction: Direction) -> usize {
+    let board_size = width * height;
+
+    match direction {
+        Direction::Up => {
+            if index >= width {
+                index - width
+            } else {
+                usize::MAX // Moved out of bounds
+            }
+        }
+        Direction::Down => {
+            if index + width < board_size {
+                index + width
+            } else {
+                usize::MAX // Moved out of bounds
+            }
+        }
+        Direction::Left => {
+            if !index.is_multiple_of(width) {
+                index - 1
+            } else {
+                usize::MAX // Moved out of bounds
+            }
+        }
+        Direction::Right => {
+            if index % width != width - 1 {
+                index + 1
+            } else {
+                usize::MAX // Moved out of bounds
+            }
+        }
+    }
+}
+
+/// The index reached by moving from `index` in `direction` on a board whose
+/// edges wrap around to the opposite side.
+pub fn wrapped_neighbour(width: usize, height: usize, index: usize, direction: Direction) -> usize {
+    let (x, y) = (index % width, index / width);
+
+    match direction {
+        Direction::Up => ((y + height - 1) % height) * width + x,
+        Direction::Down => ((y + 1) % height) * width + x,
+        Direction::Left => y * width + (x + width - 1) % width,
+        Direction::Right => y * width + (x + 1) % width,
+    }
+}
+
+fn alive_snakes(game_state: &GameState) -> usize {
+    game_state.snakes.iter().filter(|s| s.health > 0).count()
+}
+
+/// Snakes without a squad are only in a squad with themselves.
+fn same_squad(snake: &Snake, other: &Snake) -> bool {
+    match (&snake.squad, &other.squad) {
+        (Some(a), Some(b)) => a == b,
+        _ => snake.id == other.id,
+    }
+}
@@ -1,104 +1,1040 @@
-use crate::game_state::{Direction, GameState};
-use crate::heuristic::calculate_control_percentages;
+use crate::alphabeta::deterministic;
+use crate::config::{FinalMovePolicy, SearchConfig, TerminalScores};
+use crate::endgame;
+use crate::evaluation::{evaluator_for, Evaluator};
+use crate::game_state::{Direction, GameState, SnakeOrder};
+use crate::transposition::{TranspositionEntry, TranspositionTable};
+use crate::zobrist;
 use dashmap::DashMap;
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::{Arc, Weak};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Barrier, OnceLock, RwLock, Weak};
 use std::thread;
 use std::time::{Duration, Instant};
 
+/// Scores are kept in atomic integers as fixed point with this many units
+/// per 1.0.
+pub const SCORE_SCALE: f32 = 1000.0;
+
+/// How often `MCTS::run_until` checks whether to stop.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+/// Under `Parallelism::RootParallel`, helper trees scale the exploration
+/// constant by up to one plus this, spread evenly across the threads.
+const ROOT_PARALLEL_EXPLORATION_SPREAD: f32 = 0.5;
+
+/// Losses added to every node on a thread's selection path until its result
+/// is backed up, so that concurrent threads spread over different paths.
+const VIRTUAL_LOSS: u32 = 1;
+
+// `Node::expansion` states
+const UNEXPANDED: u8 = 0;
+const EXPANDING: u8 = 1;
+const EXPANDED: u8 = 2;
+
+/// How the tree models a turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SearchMode {
+    /// Snakes take turns within a round: each node belongs to one
+    /// `current_player` and collisions are resolved once everyone has moved.
+    #[default]
+    Sequential,
+    /// Decoupled UCT: each node is a full turn. Every snake picks its own move
+    /// from its own statistics without seeing the others' choices, and
+    /// children are keyed by the resulting joint action.
+    Simultaneous,
+}
+
+/// How `MCTS::run_until` spreads a search over its threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Parallelism {
+    /// Every thread runs full iterations on the one shared tree, kept apart
+    /// by virtual loss.
+    #[default]
+    SharedTree,
+    /// Every thread grows an independent tree from the root position. The
+    /// trees' root statistics are summed into this tree when the search
+    /// stops.
+    RootParallel,
+    /// The calling thread selects a batch of leaves, one per thread, and the
+    /// threads evaluate the batch together before it is backed up.
+    LeafParallel,
+}
+
+/// One snake's statistics for one of its moves at a simultaneous node.
+pub struct MoveStats {
+    pub direction: Direction,
+    pub visits: AtomicU32,
+    pub total_score: AtomicU32,
+    /// Threads currently searching below this move.
+    pub virtual_loss: AtomicU32,
+}
+
+/// One of a snake's root moves, as weighed by the final move selection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveReport {
+    pub direction: Direction,
+    pub visits: u32,
+    pub mean_score: f32,
+    /// The mean score less the UCB exploration term, used by
+    /// `FinalMovePolicy::SecureChild`. Minus infinity for unvisited moves.
+    pub lower_bound: f32,
+}
+
+/// The final move chosen for one snake and the statistics behind it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveSelection {
+    pub policy: FinalMovePolicy,
+    /// `None` if the snake has no root moves.
+    pub chosen: Option<Direction>,
+    pub moves: Vec<MoveReport>,
+}
+
+/// One line per root move, with the chosen one marked.
+impl fmt::Display for MoveSelection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {:?}", self.policy, self.chosen)?;
+        for m in &self.moves {
+            let marker = if Some(m.direction) == self.chosen {
+                " <"
+            } else {
+                ""
+            };
+            write!(
+                f,
+                "\n  {:<5} visits {:>7}  mean {:.3}  lcb {:.3}{}",
+                format!("{:?}", m.direction),
+                m.visits,
+                m.mean_score,
+                m.lower_bound,
+                marker
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A snake's mean result at the root, with its place in both orders.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnakeScore {
+    pub id: String,
+    /// Where the snake was listed before reordering, if it still is.
+    pub original_index: Option<usize>,
+    /// The snake's index in the searched `GameState`.
+    pub search_index: usize,
+    pub mean_score: f32,
+}
+
+/// Search statistics for one of a snake's moves at the root.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RootMove {
+    pub direction: Direction,
+    pub visits: u32,
+    pub mean_score: f32,
+}
+
 pub struct Node {
     pub game_state: GameState,
     pub total_score: Vec<AtomicU32>,
     pub visits: AtomicU32,
+    /// Virtual losses from threads whose selection path runs through this
+    /// node and which haven't backed up their result yet.
+    pub virtual_loss: AtomicU32,
+    /// Whether a thread has claimed, or finished, creating this node's
+    /// sequential children.
+    expansion: AtomicU8,
     pub children: DashMap<Direction, Arc<Node>>,
+    /// Children of a simultaneous node, keyed by one direction per snake.
+    pub joint_children: DashMap<Vec<Direction>, Arc<Node>>,
+    /// Per-snake move statistics of a simultaneous node. Snakes that are out
+    /// of the game have no moves.
+    pub move_stats: Vec<Vec<MoveStats>>,
     pub move_made: Option<Direction>,
+    /// The joint action that led to this node in simultaneous mode.
+    pub joint_move: Option<Vec<Direction>>,
     pub parent: Option<Weak<Node>>,
     pub current_player: usize,
     pub num_snakes: usize,
-    pub is_terminal: bool,
-    pub heuristic: Option<Vec<f32>>, // Added back the heuristic field
+    /// How `endgame` settles the position, worked out the first time a
+    /// search expands or evaluates the node.
+    endgame: OnceLock<Endgame>,
+    /// The node's evaluation, computed by the first search thread to reach
+    /// it and never changed after that.
+    heuristic: OnceLock<Vec<f32>>,
+    /// Transposition key: the state's hash, plus the player to move in
+    /// sequential mode.
+    pub key: u64,
+    /// Statistics shared with transposed nodes, if the search has a
+    /// transposition table with room for this position.
+    pub entry: Option<Arc<TranspositionEntry>>,
+}
+
+impl Node {
+    fn new(
+        game_state: GameState,
+        parent: Option<Weak<Node>>,
+        current_player: usize,
+        mode: SearchMode,
+        transpositions: Option<&TranspositionTable>,
+    ) -> Self {
+        let num_snakes = game_state.snakes.len();
+        let key = match mode {
+            SearchMode::Sequential => game_state
+                .hash()
+                .wrapping_add(zobrist::player_key(current_player)),
+            SearchMode::Simultaneous => game_state.hash(),
+        };
+        let entry = transpositions.and_then(|table| table.entry(key, num_snakes));
+        let move_stats = match mode {
+            SearchMode::Simultaneous if !game_state.is_game_over() => (0..num_snakes)
+                .map(|i| MCTS::candidate_moves(&game_state, i))
+                .map(|moves| {
+                    moves
+                        .into_iter()
+                        .map(|direction| MoveStats {
+                            direction,
+                            visits: AtomicU32::new(0),
+                            total_score: AtomicU32::new(0),
+                            virtual_loss: AtomicU32::new(0),
+                        })
+                        .collect()
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Node {
+            game_state,
+            total_score: (0..num_snakes).map(|_| AtomicU32::new(0)).collect(),
+            visits: AtomicU32::new(0),
+            virtual_loss: AtomicU32::new(0),
+            expansion: AtomicU8::new(UNEXPANDED),
+            children: DashMap::new(),
+            joint_children: DashMap::new(),
+            move_stats,
+            move_made: None,
+            joint_move: None,
+            parent,
+            current_player,
+            num_snakes,
+            endgame: OnceLock::new(),
+            heuristic: OnceLock::new(),
+            key,
+            entry,
+        }
+    }
+
+    /// A root for `state`, a position that only differs from `node`'s by
+    /// added food and hazards, carrying over `node`'s statistics and
+    /// children.
+    fn grafted(
+        node: &Node,
+        state: GameState,
+        mode: SearchMode,
+        transpositions: Option<&TranspositionTable>,
+    ) -> Self {
+        let mut root = Node::new(state, None, node.current_player, mode, transpositions);
+        add_node_stats(&root, node);
+        add_move_stats(&root, node);
+        root.expansion = AtomicU8::new(node.expansion.load(Ordering::Acquire));
+        root.children = node.children.clone();
+        root.joint_children = node.joint_children.clone();
+        root
+    }
+
+    /// The node's evaluation, if a search has reached it.
+    pub fn heuristic(&self) -> Option<&[f32]> {
+        self.heuristic.get().map(Vec::as_slice)
+    }
+
+    /// For each snake, the turns it has left if `endgame` finds it isolated
+    /// and doomed.
+    pub fn doomed(&self) -> &[Option<u32>] {
+        &self.endgame().doomed
+    }
+
+    /// Whether the game is over, or as good as over because `endgame` shows
+    /// how it ends.
+    pub fn is_terminal(&self) -> bool {
+        self.endgame().is_terminal
+    }
+
+    fn endgame(&self) -> &Endgame {
+        self.endgame.get_or_init(|| {
+            let state = &self.game_state;
+            if state.is_game_over() {
+                return Endgame {
+                    doomed: vec![None; self.num_snakes],
+                    is_terminal: true,
+                };
+            }
+            let doomed = endgame::doomed_snakes(state);
+            let is_terminal =
+                endgame::decided_scores(state, &doomed, &TerminalScores::default()).is_some();
+            Endgame {
+                doomed,
+                is_terminal,
+            }
+        })
+    }
+
+    /// Whether this node's sequential children have all been created.
+    pub fn is_expanded(&self) -> bool {
+        self.expansion.load(Ordering::Acquire) == EXPANDED
+    }
+
+    /// Visits to this position, shared with its transpositions if any, plus
+    /// this node's pending virtual losses.
+    fn shared_visits(&self) -> u32 {
+        let visits = match &self.entry {
+            Some(entry) => entry.visits.load(Ordering::Relaxed),
+            None => self.visits.load(Ordering::Relaxed),
+        };
+        visits + self.virtual_loss.load(Ordering::Relaxed)
+    }
+
+    /// Total scaled score for `snake_index`, shared with transpositions.
+    fn shared_score(&self, snake_index: usize) -> u32 {
+        match &self.entry {
+            Some(entry) => entry.total_score[snake_index].load(Ordering::Relaxed),
+            None => self.total_score[snake_index].load(Ordering::Relaxed),
+        }
+    }
+
+    /// Visits and total scaled score for a snake's move at a simultaneous
+    /// node, shared with transpositions. Visits include pending virtual
+    /// losses.
+    fn shared_move_stats(&self, snake_index: usize, stat: &MoveStats) -> (u32, u32) {
+        let virtual_loss = stat.virtual_loss.load(Ordering::Relaxed);
+        let (visits, total_score) = match &self.entry {
+            Some(entry) => {
+                let shared = entry.move_stats(snake_index, stat.direction);
+                (
+                    shared.visits.load(Ordering::Relaxed),
+                    shared.total_score.load(Ordering::Relaxed),
+                )
+            }
+            None => (
+                stat.visits.load(Ordering::Relaxed),
+                stat.total_score.load(Ordering::Relaxed),
+            ),
+        };
+        (visits + virtual_loss, total_score)
+    }
+
+    /// All children of this node, whichever search mode created them.
+    pub fn child_nodes(&self) -> Vec<Arc<Node>> {
+        self.children
+            .iter()
+            .map(|entry| Arc::clone(entry.value()))
+            .chain(
+                self.joint_children
+                    .iter()
+                    .map(|entry| Arc::clone(entry.value())),
+            )
+            .collect()
+    }
+}
+
+/// What `MCTS::run_until`'s stop check sees of a search in progress.
+pub trait SearchProgress {
+    fn root_visits(&self) -> u32;
+    /// Selection-evaluation-backup passes completed.
+    fn iterations(&self) -> u64;
+    /// See `MCTS::root_moves`.
+    fn root_moves(&self, snake_id: &str) -> Vec<RootMove>;
+}
+
+impl SearchProgress for MCTS {
+    fn root_visits(&self) -> u32 {
+        self.root.visits.load(Ordering::Relaxed)
+    }
+
+    fn iterations(&self) -> u64 {
+        self.iterations.load(Ordering::Relaxed)
+    }
+
+    fn root_moves(&self, snake_id: &str) -> Vec<RootMove> {
+        MCTS::root_moves(self, snake_id)
+    }
+}
+
+/// The root statistics of root-parallel trees summed, copied as they stood
+/// when it was made.
+struct MergedRoot {
+    visits: u32,
+    iterations: u64,
+    /// Each snake's id and root moves.
+    moves: Vec<(String, Vec<RootMove>)>,
+}
+
+impl MergedRoot {
+    fn of(tree: &MCTS, others: &[MCTS]) -> Self {
+        let trees: Vec<&MCTS> = std::iter::once(tree).chain(others).collect();
+        let moves = tree
+            .root
+            .game_state
+            .snakes
+            .iter()
+            .map(|snake| {
+                // Visits and total score per `Direction::index`
+                let mut totals: [Option<(u32, f32)>; 4] = [None; 4];
+                for tree in &trees {
+                    for root_move in MCTS::root_moves(tree, &snake.id) {
+                        let (visits, total_score) =
+                            totals[root_move.direction.index()].get_or_insert((0, 0.0));
+                        *visits += root_move.visits;
+                        *total_score += root_move.mean_score * root_move.visits as f32;
+                    }
+                }
+                let root_moves = Direction::ALL
+                    .iter()
+                    .zip(totals)
+                    .filter_map(|(&direction, totals)| {
+                        totals.map(|(visits, total_score)| RootMove {
+                            direction,
+                            visits,
+                            mean_score: if visits == 0 {
+                                0.0
+                            } else {
+                                total_score / visits as f32
+                            },
+                        })
+                    })
+                    .collect();
+                (snake.id.clone(), root_moves)
+            })
+            .collect();
+
+        MergedRoot {
+            visits: trees
+                .iter()
+                .map(|tree| tree.root.visits.load(Ordering::Relaxed))
+                .sum(),
+            iterations: trees
+                .iter()
+                .map(|tree| tree.iterations.load(Ordering::Relaxed))
+                .sum(),
+            moves,
+        }
+    }
+}
+
+impl SearchProgress for MergedRoot {
+    fn root_visits(&self) -> u32 {
+        self.visits
+    }
+
+    fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    fn root_moves(&self, snake_id: &str) -> Vec<RootMove> {
+        self.moves
+            .iter()
+            .find(|(id, _)| id == snake_id)
+            .map(|(_, moves)| moves.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// What `endgame` makes of a node's position.
+struct Endgame {
+    doomed: Vec<Option<u32>>,
+    is_terminal: bool,
+}
+
+/// A path chosen by one selection pass. Every node on it, and every move
+/// taken at a simultaneous node, holds a virtual loss until the pass is
+/// completed.
+struct Selection {
+    path: Vec<Arc<Node>>,
+    /// The joint move taken from each node on the path, in simultaneous mode.
+    joint_moves: Vec<Vec<Direction>>,
+}
+
+impl Selection {
+    fn leaf(&self) -> &Node {
+        self.path
+            .last()
+            .expect("a selection path starts at the root")
+    }
 }
 
 pub struct MCTS {
     pub root: Arc<Node>,
-    exploration_constant: f32,
+    config: SearchConfig,
+    evaluator: Arc<dyn Evaluator>,
+    snake_order: SnakeOrder,
+    transpositions: Option<Arc<TranspositionTable>>,
+    /// Node evaluations performed since this search was created.
+    pub evaluations: AtomicU64,
+    /// Selection-evaluation-backup passes completed since this search was
+    /// created.
+    pub iterations: AtomicU64,
+    /// Nodes created since this search was created.
+    pub nodes: AtomicU64,
 }
 
 impl MCTS {
-    pub fn new(initial_state: GameState) -> Self {
-        let number_of_snakes = initial_state.snakes.len();
-        let is_terminal = Self::is_terminal(&initial_state);
+    pub fn new(initial_state: GameState, config: SearchConfig) -> Self {
         MCTS {
-            root: Arc::new(Node {
-                game_state: initial_state,
-                total_score: (0..number_of_snakes).map(|_| AtomicU32::new(0)).collect(),
-                visits: AtomicU32::new(0),
-                children: DashMap::new(),
-                move_made: None,
-                parent: None,
-                current_player: 0,
-                num_snakes: number_of_snakes,
-                is_terminal,
-                heuristic: None, // Initialize heuristic as None
-            }),
-            exploration_constant: 1.414,
+            snake_order: SnakeOrder::of(&initial_state),
+            root: Arc::new(Node::new(
+                deterministic(&initial_state),
+                None,
+                0,
+                config.mode,
+                None,
+            )),
+            evaluator: evaluator_for(&config),
+            config,
+            transpositions: None,
+            evaluations: AtomicU64::new(0),
+            iterations: AtomicU64::new(0),
+            nodes: AtomicU64::new(0),
+        }
+    }
+
+    /// A search with the default config in `mode`.
+    pub fn with_mode(initial_state: GameState, mode: SearchMode) -> Self {
+        Self::new(
+            initial_state,
+            SearchConfig {
+                mode,
+                ..SearchConfig::default()
+            },
+        )
+    }
+
+    /// A search whose transposed nodes share statistics and evaluations
+    /// through `table`.
+    pub fn with_transpositions(
+        initial_state: GameState,
+        config: SearchConfig,
+        table: Arc<TranspositionTable>,
+    ) -> Self {
+        MCTS {
+            snake_order: SnakeOrder::of(&initial_state),
+            root: Arc::new(Node::new(
+                deterministic(&initial_state),
+                None,
+                0,
+                config.mode,
+                Some(&table),
+            )),
+            evaluator: evaluator_for(&config),
+            config,
+            transpositions: Some(table),
+            evaluations: AtomicU64::new(0),
+            iterations: AtomicU64::new(0),
+            nodes: AtomicU64::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &SearchConfig {
+        &self.config
+    }
+
+    pub fn mode(&self) -> SearchMode {
+        self.config.mode
+    }
+
+    /// What scores the leaves. Defaults to the one named by the config's
+    /// `heuristic`.
+    pub fn evaluator(&self) -> &Arc<dyn Evaluator> {
+        &self.evaluator
+    }
+
+    /// Scores leaves with `evaluator` from now on. Nodes already evaluated
+    /// keep their scores.
+    pub fn set_evaluator(&mut self, evaluator: Arc<dyn Evaluator>) {
+        self.evaluator = evaluator;
+    }
+
+    /// The order the snakes were given in before the search reordered them,
+    /// used when reporting per-snake results. Defaults to the root's order.
+    pub fn snake_order(&self) -> &SnakeOrder {
+        &self.snake_order
+    }
+
+    pub fn set_snake_order(&mut self, snake_order: SnakeOrder) {
+        self.snake_order = snake_order;
+    }
+
+    /// Every snake's visits-weighted mean score at the root, in the original
+    /// snake order.
+    pub fn root_scores(&self) -> Vec<SnakeScore> {
+        let root = &self.root;
+        let visits = root.visits.load(Ordering::Relaxed);
+
+        self.snake_order
+            .arrange(&root.game_state)
+            .into_iter()
+            .map(|i| {
+                let id = &root.game_state.snakes[i].id;
+                let total_score = root.total_score[i].load(Ordering::Relaxed);
+                SnakeScore {
+                    id: id.clone(),
+                    original_index: self.snake_order.original_index(id),
+                    search_index: i,
+                    mean_score: if visits == 0 {
+                        0.0
+                    } else {
+                        total_score as f32 / SCORE_SCALE / visits as f32 // Adjust for scaling
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Whether the search has created as many nodes as its config allows.
+    pub fn node_limit_reached(&self) -> bool {
+        self.config
+            .max_nodes
+            .is_some_and(|max_nodes| self.nodes.load(Ordering::Relaxed) >= max_nodes)
+    }
+
+    pub fn transpositions(&self) -> Option<&Arc<TranspositionTable>> {
+        self.transpositions.as_ref()
+    }
+
+    /// Re-roots the tree at the descendant whose position matches `observed`,
+    /// keeping everything already searched below it. Returns `None` if the
+    /// tree never reached that position.
+    ///
+    /// The search never spawns food or shrinks the board, so food and
+    /// hazards the game has added since are grafted onto the new root. Its
+    /// subtree was searched without them until it is expanded again.
+    pub fn advance_to(self, observed: &GameState) -> Option<MCTS> {
+        let mut queue = VecDeque::from([Arc::clone(&self.root)]);
+
+        while let Some(node) = queue.pop_front() {
+            let state = &node.game_state;
+            if state.turn == observed.turn
+                && node.current_player == 0
+                && state.matches_position(observed)
+            {
+                let root = if state.same_food_and_hazards(observed) {
+                    node
+                } else {
+                    Arc::new(Node::grafted(
+                        &node,
+                        deterministic(observed),
+                        self.config.mode,
+                        self.transpositions.as_deref(),
+                    ))
+                };
+                return Some(MCTS {
+                    root,
+                    config: self.config,
+                    evaluator: self.evaluator,
+                    snake_order: self.snake_order,
+                    transpositions: self.transpositions,
+                    evaluations: AtomicU64::new(0),
+                    iterations: AtomicU64::new(0),
+                    nodes: AtomicU64::new(0),
+                });
+            }
+            if state.turn < observed.turn {
+                queue.extend(node.child_nodes());
+            }
         }
+
+        None
     }
 
     pub fn run(&self, duration: Duration, num_threads: usize) {
+        self.run_until(num_threads, |_, elapsed| elapsed >= duration);
+    }
+
+    /// Searches on `num_threads` threads, as set out by the config's
+    /// `parallelism`, until `should_stop` returns true. It is polled from the
+    /// calling thread every few milliseconds with the search's progress and
+    /// the time spent so far. Under `RootParallel` the progress sums every
+    /// thread's root statistics so far.
+    pub fn run_until<F>(&self, num_threads: usize, should_stop: F)
+    where
+        F: Fn(&dyn SearchProgress, Duration) -> bool,
+    {
+        match self.config.parallelism {
+            Parallelism::SharedTree => self.run_shared_tree(num_threads, should_stop),
+            Parallelism::RootParallel => self.run_root_parallel(num_threads, should_stop),
+            Parallelism::LeafParallel => self.run_leaf_parallel(num_threads, should_stop),
+        }
+    }
+
+    fn run_shared_tree<F>(&self, num_threads: usize, should_stop: F)
+    where
+        F: Fn(&dyn SearchProgress, Duration) -> bool,
+    {
         let start_time = Instant::now();
+        let stop = AtomicBool::new(false);
 
-        let handles: Vec<_> = (0..num_threads)
-            .map(|_| {
-                let root_clone = Arc::clone(&self.root);
-                let exploration_constant = self.exploration_constant;
-                thread::spawn(move || {
-                    while Instant::now().duration_since(start_time) < duration {
-                        Self::tree_policy(&root_clone, exploration_constant);
+        thread::scope(|scope| {
+            for _ in 0..num_threads {
+                let stop = &stop;
+                scope.spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        self.iterate();
                     }
-                })
+                });
+            }
+
+            while !should_stop(self, start_time.elapsed()) && !self.node_limit_reached() {
+                thread::sleep(STOP_POLL_INTERVAL);
+            }
+            stop.store(true, Ordering::Relaxed);
+        });
+    }
+
+    fn run_root_parallel<F>(&self, num_threads: usize, should_stop: F)
+    where
+        F: Fn(&dyn SearchProgress, Duration) -> bool,
+    {
+        let start_time = Instant::now();
+        let stop = AtomicBool::new(false);
+
+        let helpers: Vec<MCTS> = (1..num_threads)
+            .map(|i| {
+                // Trees that explore alike mostly search the same lines, so
+                // each helper explores a little more widely than the last
+                let spread = ROOT_PARALLEL_EXPLORATION_SPREAD * i as f32 / num_threads as f32;
+                let config = SearchConfig {
+                    exploration_constant: self.config.exploration_constant * (1.0 + spread),
+                    ..self.config.clone()
+                };
+                let mut tree = MCTS::new(self.root.game_state.clone(), config);
+                tree.set_evaluator(Arc::clone(&self.evaluator));
+                tree
             })
             .collect();
 
-        for handle in handles {
-            handle.join().unwrap();
+        thread::scope(|scope| {
+            let stop = &stop;
+            for tree in std::iter::once(self).chain(&helpers) {
+                scope.spawn(move || {
+                    while !stop.load(Ordering::Relaxed) && !tree.node_limit_reached() {
+                        tree.iterate();
+                    }
+                });
+            }
+
+            while !should_stop(&MergedRoot::of(self, &helpers), start_time.elapsed())
+                && !self.node_limit_reached()
+            {
+                thread::sleep(STOP_POLL_INTERVAL);
+            }
+            stop.store(true, Ordering::Relaxed);
+        });
+
+        for tree in &helpers {
+            self.merge_root(tree);
         }
     }
 
-    pub fn get_best_move_for_snake(&self, our_snake_id: &str) -> Option<Direction> {
+    /// Adds the root statistics of `other`, a search from the same position,
+    /// to this tree's root.
+    ///
+    /// Only the root and, in sequential mode, its children gain the merged
+    /// visits; deeper nodes keep this tree's own. A tree re-rooted below them
+    /// for the next turn is therefore consistent, but carries only this
+    /// tree's share of the search.
+    fn merge_root(&self, other: &MCTS) {
         let root = &self.root;
+        let other_root = &other.root;
 
-        if !root.children.is_empty() {
-            let best_child = root
-                .children
-                .iter()
-                .max_by_key(|entry| entry.value().visits.load(Ordering::Relaxed))
-                .map(|entry| *entry.key());
+        add_node_stats(root, other_root);
+        match self.config.mode {
+            SearchMode::Sequential => {
+                self.expand(root);
+                for entry in other_root.children.iter() {
+                    if let Some(child) = root.children.get(entry.key()) {
+                        add_node_stats(child.value(), entry.value());
+                    }
+                }
+            }
+            SearchMode::Simultaneous => add_move_stats(root, other_root),
+        }
+
+        self.iterations
+            .fetch_add(other.iterations.load(Ordering::Relaxed), Ordering::Relaxed);
+        self.evaluations
+            .fetch_add(other.evaluations.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    fn run_leaf_parallel<F>(&self, num_threads: usize, should_stop: F)
+    where
+        F: Fn(&dyn SearchProgress, Duration) -> bool,
+    {
+        let start_time = Instant::now();
+        let num_threads = num_threads.max(1);
+        let stop = AtomicBool::new(false);
+        let batch: RwLock<Vec<Selection>> = RwLock::new(Vec::with_capacity(num_threads));
+        // Batches start and finish with every thread at the barrier
+        let barrier = Barrier::new(num_threads);
+
+        let evaluate_share = |thread_index: usize| {
+            let batch = batch.read().unwrap_or_else(|e| e.into_inner());
+            for selection in batch.iter().skip(thread_index).step_by(num_threads) {
+                self.evaluate(selection.leaf());
+            }
+        };
+
+        thread::scope(|scope| {
+            for thread_index in 1..num_threads {
+                let (stop, barrier, evaluate_share) = (&stop, &barrier, &evaluate_share);
+                scope.spawn(move || loop {
+                    barrier.wait();
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    evaluate_share(thread_index);
+                    barrier.wait();
+                });
+            }
+
+            let mut last_poll = Instant::now();
+            while !self.node_limit_reached() {
+                if last_poll.elapsed() >= STOP_POLL_INTERVAL {
+                    if should_stop(self, start_time.elapsed()) {
+                        break;
+                    }
+                    last_poll = Instant::now();
+                }
+
+                {
+                    let mut batch = batch.write().unwrap_or_else(|e| e.into_inner());
+                    batch.extend((0..num_threads).map(|_| self.select()));
+                }
+                barrier.wait();
+                evaluate_share(0);
+                barrier.wait();
+
+                let mut batch = batch.write().unwrap_or_else(|e| e.into_inner());
+                for selection in batch.drain(..) {
+                    let simulation_result = self.evaluate(selection.leaf());
+                    self.complete(&selection, simulation_result);
+                }
+            }
 
-            return best_child;
+            stop.store(true, Ordering::Relaxed);
+            barrier.wait();
+        });
+    }
+
+    /// Visit counts and mean scores of the moves available to `snake_id` at
+    /// the root. In sequential mode a snake's moves are made below the root
+    /// when it isn't the first to move, so its statistics are summed over
+    /// every node where it makes its first move. Snakes that have already
+    /// moved this round, or are out of the game, have no root moves.
+    pub fn root_moves(&self, snake_id: &str) -> Vec<RootMove> {
+        let root = &self.root;
+        let Some(snake_index) = root.game_state.snake_index(snake_id) else {
+            return Vec::new();
+        };
+        if root.game_state.snakes[snake_index].health == 0 {
+            return Vec::new();
         }
 
-        None
+        let mean = |total: u32, visits: u32| {
+            if visits == 0 {
+                0.0
+            } else {
+                total as f32 / SCORE_SCALE / visits as f32 // Adjust for scaling
+            }
+        };
+
+        match self.config.mode {
+            SearchMode::Simultaneous => root
+                .move_stats
+                .get(snake_index)
+                .map(|stats| {
+                    stats
+                        .iter()
+                        .map(|stat| {
+                            let visits = stat.visits.load(Ordering::Relaxed);
+                            RootMove {
+                                direction: stat.direction,
+                                visits,
+                                mean_score: mean(stat.total_score.load(Ordering::Relaxed), visits),
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            SearchMode::Sequential => {
+                let Some(depth) = snake_index.checked_sub(root.current_player) else {
+                    return Vec::new();
+                };
+
+                let mut nodes = vec![Arc::clone(root)];
+                for _ in 0..depth {
+                    nodes = nodes.iter().flat_map(|node| node.child_nodes()).collect();
+                }
+
+                // Visits and total score per `Direction::index`
+                let mut totals: [Option<(u32, u32)>; 4] = [None; 4];
+                for node in &nodes {
+                    for entry in node.children.iter() {
+                        let child = entry.value();
+                        let (visits, total_score) =
+                            totals[entry.key().index()].get_or_insert((0, 0));
+                        *visits += child.visits.load(Ordering::Relaxed);
+                        *total_score += child.total_score[snake_index].load(Ordering::Relaxed);
+                    }
+                }
+
+                Direction::ALL
+                    .iter()
+                    .zip(totals)
+                    .filter_map(|(&direction, totals)| {
+                        totals.map(|(visits, total_score)| RootMove {
+                            direction,
+                            visits,
+                            mean_score: mean(total_score, visits),
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// The move for `our_snake_id` under the config's final move policy.
+    pub fn get_best_move_for_snake(&self, our_snake_id: &str) -> Option<Direction> {
+        self.select_move(our_snake_id).chosen
+    }
+
+    /// The move for `snake_id` under the config's final move policy, with the
+    /// statistics of every root move.
+    pub fn select_move(&self, snake_id: &str) -> MoveSelection {
+        self.select_move_with_policy(snake_id, self.config.final_move)
+    }
+
+    /// The move for `snake_id` under `policy`, with the statistics of every
+    /// root move.
+    pub fn select_move_with_policy(
+        &self,
+        snake_id: &str,
+        policy: FinalMovePolicy,
+    ) -> MoveSelection {
+        let root_moves = self.root_moves(snake_id);
+        let total_visits: u32 = root_moves.iter().map(|m| m.visits).sum();
+        let exploration_constant = self.config.exploration_constant;
+
+        let moves: Vec<MoveReport> = root_moves
+            .iter()
+            .map(|m| {
+                let lower_bound = if m.visits == 0 {
+                    f32::NEG_INFINITY
+                } else {
+                    let uncertainty = ((total_visits as f32).ln() / m.visits as f32).sqrt();
+                    m.mean_score - exploration_constant * uncertainty
+                };
+                MoveReport {
+                    direction: m.direction,
+                    visits: m.visits,
+                    mean_score: m.mean_score,
+                    lower_bound,
+                }
+            })
+            .collect();
+
+        let visited = || moves.iter().filter(|m| m.visits > 0);
+        let most_visited = moves.iter().max_by_key(|m| m.visits);
+        let best_mean = || visited().max_by(|a, b| a.mean_score.total_cmp(&b.mean_score));
+
+        let chosen = match policy {
+            FinalMovePolicy::MaxVisits => most_visited,
+            FinalMovePolicy::MaxMean => best_mean(),
+            FinalMovePolicy::RobustMax => {
+                let agreed = best_mean()
+                    .filter(|m| most_visited.is_some_and(|most| most.direction == m.direction));
+                agreed.or(most_visited)
+            }
+            FinalMovePolicy::SecureChild => {
+                visited().max_by(|a, b| a.lower_bound.total_cmp(&b.lower_bound))
+            }
+        };
+
+        MoveSelection {
+            policy,
+            chosen: chosen.map(|m| m.direction),
+            moves,
+        }
+    }
+
+    /// One full iteration: selects a leaf, evaluates it and backs up the
+    /// result.
+    fn iterate(&self) {
+        let selection = self.select();
+        let simulation_result = self.evaluate(selection.leaf());
+        self.complete(&selection, simulation_result);
     }
 
-    fn tree_policy(node: &Arc<Node>, exploration_constant: f32) {
+    fn select(&self) -> Selection {
+        match self.config.mode {
+            SearchMode::Sequential => self.tree_policy(),
+            SearchMode::Simultaneous => self.simultaneous_tree_policy(),
+        }
+    }
+
+    /// Backs up `simulation_result` along the selected path and releases the
+    /// selection's virtual losses.
+    fn complete(&self, selection: &Selection, simulation_result: &[f32]) {
+        let path = &selection.path;
+        Self::back_propagate(path, simulation_result);
+        for (node, joint_move) in path.iter().zip(&selection.joint_moves) {
+            for (snake_index, stat) in Self::joint_move_stats(node, joint_move) {
+                let delta = (simulation_result[snake_index] * SCORE_SCALE) as u32; // Scale to integer
+                stat.visits.fetch_add(1, Ordering::Relaxed);
+                stat.total_score.fetch_add(delta, Ordering::Relaxed);
+                stat.virtual_loss.fetch_sub(VIRTUAL_LOSS, Ordering::Relaxed);
+                if let Some(entry) = &node.entry {
+                    let shared = entry.move_stats(snake_index, stat.direction);
+                    shared.visits.fetch_add(1, Ordering::Relaxed);
+                    shared.total_score.fetch_add(delta, Ordering::Relaxed);
+                }
+            }
+        }
+        for node in path {
+            node.virtual_loss.fetch_sub(VIRTUAL_LOSS, Ordering::Relaxed);
+        }
+        self.iterations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn tree_policy(&self) -> Selection {
+        let exploration_constant = self.config.exploration_constant;
         let mut path = Vec::new();
-        let mut current_node = Arc::clone(node);
+        let mut current_node = Arc::clone(&self.root);
 
         loop {
+            current_node
+                .virtual_loss
+                .fetch_add(VIRTUAL_LOSS, Ordering::Relaxed);
             path.push(Arc::clone(&current_node));
 
-            if current_node.is_terminal {
+            if current_node.is_terminal() {
                 break;
             }
 
             // Try to expand the node
-            if Self::expand(&current_node) {
+            if self.expand(&current_node) {
                 // Node was expanded, select one of the new children
                 let selected_child = Self::select_child(&current_node, exploration_constant);
                 current_node = selected_child;
+                current_node
+                    .virtual_loss
+                    .fetch_add(VIRTUAL_LOSS, Ordering::Relaxed);
                 path.push(Arc::clone(&current_node));
                 break;
+            } else if !current_node.is_expanded() {
+                // Another thread is expanding it; back it up as a leaf again
+                break;
             } else {
                 // Select best child
                 let selected_child = Self::select_child(&current_node, exploration_constant);
@@ -106,31 +1042,22 @@ impl MCTS {
             }
         }
 
-        // Simulate a playout from the current node
-        let simulation_result = Self::default_policy(&current_node.game_state);
-
-        // Backpropagate the result
-        Self::back_propagate(&path, &simulation_result);
-
-        // Store heuristic at the leaf node
-        if let Some(leaf_node) = path.last() {
-            // Only store heuristic if it's not already stored
-            if leaf_node.heuristic.is_none() {
-                let heuristic = simulation_result.clone();
-                // Since we're using Arc<Node>, we need to update heuristic in a thread-safe way
-                // You can use a Mutex or RwLock for this field, or design the Node to have interior mutability for heuristic
-                // For simplicity, let's assume we can set it here safely (since only one thread writes to it at a time)
-                // This is acceptable in this context because each simulation works on its own path
-                unsafe {
-                    let node_ptr = Arc::as_ptr(leaf_node) as *mut Node;
-                    (*node_ptr).heuristic = Some(heuristic);
-                }
-            }
+        Selection {
+            path,
+            joint_moves: Vec::new(),
         }
     }
 
-    fn expand(node: &Arc<Node>) -> bool {
-        if node.is_terminal || !node.children.is_empty() {
+    /// Creates the node's children, unless it is terminal or another thread
+    /// has already claimed it. Returns whether this call expanded it.
+    fn expand(&self, node: &Arc<Node>) -> bool {
+        let transpositions = self.transpositions.as_deref();
+        if node.is_terminal()
+            || node
+                .expansion
+                .compare_exchange(UNEXPANDED, EXPANDING, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+        {
             return false;
         }
 
@@ -138,19 +1065,11 @@ impl MCTS {
         let num_snakes = node.num_snakes;
 
         if node.game_state.snakes[current_player].health > 0 {
-            // Get all possible moves (excluding out-of-bounds and moving into own neck)
-            let safe_moves = node.game_state.get_safe_moves(current_player);
-            let moves = if safe_moves.is_empty() {
-                vec![None] // If no safe moves, the snake doesn't move
-            } else {
-                safe_moves.into_iter().map(Some).collect()
-            };
+            let moves = Self::candidate_moves(&node.game_state, current_player);
 
-            for &move_option in &moves {
+            for &direction in &moves {
                 let mut new_state = node.game_state.clone();
-                if let Some(direction) = move_option {
-                    new_state.move_snake(current_player, direction);
-                }
+                new_state.move_snake(current_player, direction);
 
                 let next_player = (current_player + 1) % num_snakes;
                 let should_resolve = next_player == 0;
@@ -159,25 +1078,20 @@ impl MCTS {
                     new_state.resolve_collisions();
                 }
 
-                // Check if the new state is terminal
-                let is_terminal = Self::is_terminal(&new_state);
-
-                let child_node = Arc::new(Node {
-                    game_state: new_state,
-                    total_score: (0..num_snakes).map(|_| AtomicU32::new(0)).collect(),
-                    visits: AtomicU32::new(0),
-                    children: DashMap::new(),
-                    move_made: move_option,
-                    parent: Some(Arc::downgrade(node)),
-                    current_player: next_player,
-                    num_snakes,
-                    is_terminal,
-                    heuristic: None, // Initialize heuristic as None
-                });
+                let mut child_node = Node::new(
+                    new_state,
+                    Some(Arc::downgrade(node)),
+                    next_player,
+                    SearchMode::Sequential,
+                    transpositions,
+                );
+                child_node.move_made = Some(direction);
+                let child_node = Arc::new(child_node);
 
-                let direction_key = move_option.unwrap_or(Direction::Up);
-                node.children.insert(direction_key, child_node);
+                node.children.insert(direction, child_node);
+                self.nodes.fetch_add(1, Ordering::Relaxed);
             }
+            node.expansion.store(EXPANDED, Ordering::Release);
             true
         } else {
             // If the current snake is dead, skip its turn
@@ -189,40 +1103,160 @@ impl MCTS {
                 new_state.resolve_collisions();
             }
 
-            // Check if the new state is terminal
-            let is_terminal = Self::is_terminal(&new_state);
-
-            let child_node = Arc::new(Node {
-                game_state: new_state,
-                total_score: (0..num_snakes).map(|_| AtomicU32::new(0)).collect(),
-                visits: AtomicU32::new(0),
-                children: DashMap::new(),
-                move_made: None,
-                parent: Some(Arc::downgrade(node)),
-                current_player: next_player,
-                num_snakes,
-                is_terminal,
-                heuristic: None, // Initialize heuristic as None
-            });
+            let child_node = Arc::new(Node::new(
+                new_state,
+                Some(Arc::downgrade(node)),
+                next_player,
+                SearchMode::Sequential,
+                transpositions,
+            ));
 
             node.children.insert(Direction::Up, child_node);
+            self.nodes.fetch_add(1, Ordering::Relaxed);
+            node.expansion.store(EXPANDED, Ordering::Release);
             true
         }
     }
 
+    /// One iteration of decoupled UCT: from the root, every snake picks a
+    /// move independently, the joint action selects (or creates) the child,
+    /// and this repeats until a new or terminal node is reached.
+    fn simultaneous_tree_policy(&self) -> Selection {
+        let exploration_constant = self.config.exploration_constant;
+        let transpositions = self.transpositions.as_deref();
+        let mut path = vec![Arc::clone(&self.root)];
+        let mut joint_moves: Vec<Vec<Direction>> = Vec::new();
+        let mut current_node = Arc::clone(&self.root);
+        current_node
+            .virtual_loss
+            .fetch_add(VIRTUAL_LOSS, Ordering::Relaxed);
+
+        while !current_node.is_terminal() {
+            let joint_move = Self::select_joint_move(&current_node, exploration_constant);
+            for (_, stat) in Self::joint_move_stats(&current_node, &joint_move) {
+                stat.virtual_loss.fetch_add(VIRTUAL_LOSS, Ordering::Relaxed);
+            }
+
+            // The child is built before touching the map, so the shard isn't
+            // locked while the turn is played; a thread that loses the race
+            // to insert it drops its copy
+            let existing = current_node
+                .joint_children
+                .get(&joint_move)
+                .map(|child| Arc::clone(child.value()));
+            let mut created = false;
+            let child = existing.unwrap_or_else(|| {
+                let mut new_state = current_node.game_state.clone();
+                new_state.step(&joint_move);
+                let mut child = Node::new(
+                    new_state,
+                    Some(Arc::downgrade(&current_node)),
+                    0,
+                    SearchMode::Simultaneous,
+                    transpositions,
+                );
+                child.joint_move = Some(joint_move.clone());
+                let child = Arc::new(child);
+                current_node
+                    .joint_children
+                    .entry(joint_move.clone())
+                    .or_insert_with(|| {
+                        created = true;
+                        self.nodes.fetch_add(1, Ordering::Relaxed);
+                        child
+                    })
+                    .clone()
+            });
+
+            joint_moves.push(joint_move);
+            child
+                .virtual_loss
+                .fetch_add(VIRTUAL_LOSS, Ordering::Relaxed);
+            path.push(Arc::clone(&child));
+            current_node = child;
+
+            if created {
+                break;
+            }
+        }
+
+        Selection { path, joint_moves }
+    }
+
+    /// Each snake's statistics for its part of `joint_move` at `node`, with
+    /// the snake's index. Snakes without statistics are skipped.
+    fn joint_move_stats<'a>(
+        node: &'a Node,
+        joint_move: &'a [Direction],
+    ) -> impl Iterator<Item = (usize, &'a MoveStats)> + 'a {
+        node.move_stats
+            .iter()
+            .enumerate()
+            .filter_map(move |(snake_index, stats)| {
+                stats
+                    .iter()
+                    .find(|m| m.direction == joint_move[snake_index])
+                    .map(|stat| (snake_index, stat))
+            })
+    }
+
+    /// Every snake independently picks the move with the best UCB over its own
+    /// statistics at this node. Snakes that are out of the game get a
+    /// placeholder move, which the rules ignore.
+    fn select_joint_move(node: &Node, exploration_constant: f32) -> Vec<Direction> {
+        let parent_visits = node.shared_visits() as f32;
+
+        node.move_stats
+            .iter()
+            .enumerate()
+            .map(|(snake_index, stats)| {
+                stats
+                    .iter()
+                    .map(|stat| {
+                        let (visits, total_score) = node.shared_move_stats(snake_index, stat);
+                        let visits = visits as f32;
+                        if visits == 0.0 {
+                            return (stat.direction, f32::INFINITY);
+                        }
+                        let total_score = total_score as f32 / SCORE_SCALE; // Adjust for scaling
+                        let exploitation = total_score / visits;
+                        let exploration =
+                            exploration_constant * (parent_visits.ln() / visits).sqrt();
+                        (stat.direction, exploitation + exploration)
+                    })
+                    .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+                    .map(|(direction, _)| direction)
+                    .unwrap_or(Direction::Up)
+            })
+            .collect()
+    }
+
+    /// The moves searched for a snake: its safe moves, or if it has none, the
+    /// move it is forced into, since snakes always have to move.
+    fn candidate_moves(game_state: &GameState, snake_index: usize) -> Vec<Direction> {
+        if game_state.snakes[snake_index].health == 0 {
+            return Vec::new();
+        }
+        let safe_moves = game_state.get_safe_moves(snake_index);
+        if safe_moves.is_empty() {
+            vec![game_state.default_move(snake_index)]
+        } else {
+            safe_moves
+        }
+    }
+
     fn select_child(node: &Arc<Node>, exploration_constant: f32) -> Arc<Node> {
-        let parent_visits = node.visits.load(Ordering::Relaxed) as f32;
+        let parent_visits = node.shared_visits() as f32;
 
         node.children
             .iter()
             .map(|entry| {
                 let child = entry.value();
-                let child_visits = child.visits.load(Ordering::Relaxed) as f32;
+                let child_visits = child.shared_visits() as f32;
                 if child_visits == 0.0 {
                     return (Arc::clone(child), f32::INFINITY);
                 }
-                let total_score =
-                    child.total_score[child.current_player].load(Ordering::Relaxed) as f32 / 1000.0; // Adjust for scaling
+                let total_score = child.shared_score(node.current_player) as f32 / SCORE_SCALE; // Adjust for scaling
                 let exploitation = total_score / child_visits;
                 let exploration =
                     exploration_constant * ((parent_visits.ln()) / child_visits).sqrt();
@@ -234,41 +1268,200 @@ impl MCTS {
             .unwrap()
     }
 
-    fn default_policy(state: &GameState) -> Vec<f32> {
-        // Implement a simulation policy (e.g., random playout)
-        // For now, we'll use the heuristic directly
-        if Self::is_terminal(state) {
-            let mut scores = vec![0.0; state.snakes.len()];
-            let alive_snakes: Vec<_> = state
-                .snakes
-                .iter()
-                .enumerate()
-                .filter(|(_, s)| s.health > 0)
-                .collect();
+    /// The node's evaluation, computed the first time any thread asks for it.
+    /// Threads that arrive while it is being computed wait for that result
+    /// rather than computing their own.
+    fn evaluate<'a>(&self, node: &'a Node) -> &'a [f32] {
+        node.heuristic.get_or_init(|| {
+            self.evaluations.fetch_add(1, Ordering::Relaxed);
+            Self::score_position(
+                &node.game_state,
+                node.doomed(),
+                &self.config,
+                self.evaluator.as_ref(),
+                self.transpositions.as_deref(),
+            )
+        })
+    }
 
-            if alive_snakes.len() == 1 {
-                let (winner_index, _) = alive_snakes[0];
-                scores[winner_index] = 1.0;
-            }
-            scores
+    /// Every snake's score for `state`: the terminal result if the game is
+    /// over or settled by `endgame`, otherwise `evaluator`'s scores with
+    /// doomed snakes scored as lost.
+    pub(crate) fn default_policy(
+        state: &GameState,
+        config: &SearchConfig,
+        evaluator: &dyn Evaluator,
+        transpositions: Option<&TranspositionTable>,
+    ) -> Vec<f32> {
+        let doomed = if state.is_game_over() {
+            Vec::new()
+        } else {
+            endgame::doomed_snakes(state)
+        };
+        Self::score_position(state, &doomed, config, evaluator, transpositions)
+    }
+
+    /// `default_policy` for a position whose `endgame::doomed_snakes` result
+    /// is already known.
+    fn score_position(
+        state: &GameState,
+        doomed: &[Option<u32>],
+        config: &SearchConfig,
+        evaluator: &dyn Evaluator,
+        transpositions: Option<&TranspositionTable>,
+    ) -> Vec<f32> {
+        if state.is_game_over() {
+            Self::terminal_scores(state, &config.terminal)
         } else {
             // Use heuristic function for non-terminal states
-            calculate_control_percentages(state)
+            let heuristic = || {
+                if let Some(scores) = endgame::decided_scores(state, doomed, &config.terminal) {
+                    return scores;
+                }
+                let mut scores = evaluator.evaluate(state);
+                for (score, turns) in scores.iter_mut().zip(doomed) {
+                    if turns.is_some() {
+                        *score = config.terminal.loss;
+                    }
+                }
+                scores
+            };
+            match transpositions {
+                Some(table) => table.evaluation(zobrist::exact_hash(state), heuristic),
+                None => heuristic(),
+            }
+        }
+    }
+
+    /// The result of a finished game for each snake.
+    fn terminal_scores(state: &GameState, scores: &TerminalScores) -> Vec<f32> {
+        let alive_snakes: Vec<usize> = (0..state.snakes.len())
+            .filter(|&i| state.snakes[i].health > 0)
+            .collect();
+
+        match alive_snakes[..] {
+            [winner_index] => (0..state.snakes.len())
+                .map(|i| {
+                    if i == winner_index {
+                        scores.win
+                    } else {
+                        scores.loss
+                    }
+                })
+                .collect(),
+            _ => vec![scores.draw; state.snakes.len()],
         }
     }
 
     fn back_propagate(path: &[Arc<Node>], simulation_result: &[f32]) {
         for node in path.iter().rev() {
             node.visits.fetch_add(1, Ordering::Relaxed);
-            for i in 0..node.num_snakes {
-                let delta = (simulation_result[i] * 1000.0) as u32; // Scale to integer
-                node.total_score[i].fetch_add(delta, Ordering::Relaxed);
+            for (score, result) in node.total_score.iter().zip(simulation_result) {
+                let delta = (result * SCORE_SCALE) as u32; // Scale to integer
+                score.fetch_add(delta, Ordering::Relaxed);
+            }
+            if let Some(entry) = &node.entry {
+                entry.record(simulation_result);
             }
         }
     }
+}
+
+/// Adds `source`'s visits and score totals to `target`, and to `target`'s
+/// transposition entry if it has one.
+fn add_node_stats(target: &Node, source: &Node) {
+    let visits = source.visits.load(Ordering::Relaxed);
+    target.visits.fetch_add(visits, Ordering::Relaxed);
+    if let Some(entry) = &target.entry {
+        entry.visits.fetch_add(visits, Ordering::Relaxed);
+    }
+    for (i, score) in source.total_score.iter().enumerate() {
+        let total_score = score.load(Ordering::Relaxed);
+        target.total_score[i].fetch_add(total_score, Ordering::Relaxed);
+        if let Some(entry) = &target.entry {
+            entry.total_score[i].fetch_add(total_score, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Adds the simultaneous move statistics of `source`, a node for the same
+/// snakes and moves, to `target`'s, and to `target`'s transposition entry if
+/// it has one.
+fn add_move_stats(target: &Node, source: &Node) {
+    for (snake_index, (stats, source_stats)) in
+        target.move_stats.iter().zip(&source.move_stats).enumerate()
+    {
+        for (stat, source_stat) in stats.iter().zip(source_stats) {
+            let visits = source_stat.visits.load(Ordering::Relaxed);
+            let total_score = source_stat.total_score.load(Ordering::Relaxed);
+            stat.visits.fetch_add(visits, Ordering::Relaxed);
+            stat.total_score.fetch_add(total_score, Ordering::Relaxed);
+            if let Some(entry) = &target.entry {
+                let shared = entry.move_stats(snake_index, stat.direction);
+                shared.visits.fetch_add(visits, Ordering::Relaxed);
+                shared.total_score.fetch_add(total_score, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Search trees kept between turns, keyed by `game.id`, so each `/move`
+/// carries on from where the previous one left off. Each game also keeps one
+/// transposition table that every tree searched for it shares.
+#[derive(Default)]
+pub struct TreeStore {
+    trees: DashMap<String, MCTS>,
+    tables: DashMap<String, Arc<TranspositionTable>>,
+}
+
+impl TreeStore {
+    pub fn new() -> Self {
+        TreeStore::default()
+    }
+
+    /// Takes the stored tree for `game_id`, re-rooted at the node matching
+    /// `game_state` and set to search with `config`. Falls back to a fresh
+    /// tree on the game's transposition table if there is no stored tree, it
+    /// uses a different mode, or it never reached this position.
+    pub fn take(&self, game_id: &str, game_state: &GameState, config: &SearchConfig) -> MCTS {
+        let table = Arc::clone(self.tables.entry(game_id.to_string()).or_default().value());
+        let stored = self.trees.remove(game_id).map(|(_, mcts)| mcts);
+        if stored
+            .as_ref()
+            .is_some_and(|mcts| mcts.config.mode != config.mode)
+        {
+            // Sequential and simultaneous nodes key their statistics
+            // differently
+            table.clear();
+        }
+
+        stored
+            .filter(|mcts| mcts.config.mode == config.mode)
+            .and_then(|mcts| mcts.advance_to(game_state))
+            .map(|mut mcts| {
+                mcts.config = config.clone();
+                mcts.evaluator = evaluator_for(config);
+                mcts
+            })
+            .unwrap_or_else(|| MCTS::with_transpositions(game_state.clone(), config.clone(), table))
+    }
+
+    /// Keeps `mcts` for the next request in `game_id`.
+    pub fn insert(&self, game_id: &str, mcts: MCTS) {
+        self.trees.insert(game_id.to_string(), mcts);
+    }
+
+    /// Drops the tree and table for a finished game.
+    pub fn remove(&self, game_id: &str) {
+        self.trees.remove(game_id);
+        self.tables.remove(game_id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.trees.len()
+    }
 
-    fn is_terminal(game_state: &GameState) -> bool {
-        let alive_snakes = game_state.snakes.iter().filter(|s| s.health > 0).count();
-        alive_snakes == 0 || alive_snakes == 1 // All snakes dead or only one snake left
+    pub fn is_empty(&self) -> bool {
+        self.trees.is_empty()
     }
 }
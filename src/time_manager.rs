@@ -0,0 +1,215 @@
+//! Decides how long each `/move` request may search.
+//!
+//! The engine gives every request `game.timeout` milliseconds, measured from
+//! its side, so the network round trip eats into the search time. The
+//! latency the engine reports for our previous move includes the time we
+//! spent searching; subtracting that leaves an estimate of the network cost,
+//! which is smoothed across turns per game.
+
+use crate::search::RootMove;
+use dashmap::DashMap;
+use std::cmp::Reverse;
+use std::time::Duration;
+
+/// Tunables for `TimeManager`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeSettings {
+    /// Time held back from every turn for request parsing, serialisation and
+    /// scheduling jitter.
+    pub safety_margin: Duration,
+    /// Search time is never cut below this, however bad the latency looks.
+    pub min_budget: Duration,
+    /// Share of the hard budget after which the search stops unless the top
+    /// root moves are too close to call.
+    pub soft_fraction: f32,
+    /// Weight of the newest sample in the network latency moving average.
+    pub latency_smoothing: f32,
+    /// Mean scores closer than this count as too close to call.
+    pub close_value_margin: f32,
+    /// No early stop happens before this much time has been searched, so
+    /// the visit rate used for projections is meaningful.
+    pub min_search_time: Duration,
+}
+
+impl Default for TimeSettings {
+    fn default() -> Self {
+        TimeSettings {
+            safety_margin: Duration::from_millis(50),
+            min_budget: Duration::from_millis(20),
+            soft_fraction: 0.75,
+            latency_smoothing: 0.3,
+            close_value_margin: 0.02,
+            min_search_time: Duration::from_millis(10),
+        }
+    }
+}
+
+/// Timing history for a single game.
+#[derive(Debug, Clone, Copy, Default)]
+struct GameTiming {
+    last_processing: Option<Duration>,
+    network_latency: Option<Duration>,
+}
+
+/// Hands out per-turn search budgets and learns each game's network latency.
+#[derive(Debug, Default)]
+pub struct TimeManager {
+    pub settings: TimeSettings,
+    games: DashMap<String, GameTiming>,
+}
+
+impl TimeManager {
+    pub fn new(settings: TimeSettings) -> Self {
+        TimeManager {
+            settings,
+            games: DashMap::new(),
+        }
+    }
+
+    /// Budget for a move in `game_id`. `reported_latency` is `you.latency`
+    /// from the request: the engine's measurement of our previous response.
+    pub fn budget(&self, game_id: &str, timeout_ms: u32, reported_latency: &str) -> SearchBudget {
+        let mut timing = self.games.entry(game_id.to_string()).or_default();
+
+        if let (Some(round_trip), Some(processing)) =
+            (parse_latency(reported_latency), timing.last_processing)
+        {
+            let sample = round_trip.saturating_sub(processing);
+            timing.network_latency = Some(match timing.network_latency {
+                Some(previous) => {
+                    let smoothing = self.settings.latency_smoothing as f64;
+                    let micros = previous.as_micros() as f64 * (1.0 - smoothing)
+                        + sample.as_micros() as f64 * smoothing;
+                    Duration::from_micros(micros.round() as u64)
+                }
+                None => sample,
+            });
+        }
+
+        let hard = Duration::from_millis(timeout_ms as u64)
+            .saturating_sub(self.settings.safety_margin)
+            .saturating_sub(timing.network_latency.unwrap_or_default())
+            .max(self.settings.min_budget);
+
+        SearchBudget {
+            soft: hard.mul_f64(self.settings.soft_fraction as f64),
+            hard,
+            close_value_margin: self.settings.close_value_margin,
+            min_search_time: self.settings.min_search_time.min(hard),
+            reused_visits: 0,
+        }
+    }
+
+    /// Records how long we took to answer, from receiving the request to
+    /// returning the response, so the next reported latency can be split.
+    pub fn record_response_time(&self, game_id: &str, processing: Duration) {
+        self.games
+            .entry(game_id.to_string())
+            .or_default()
+            .last_processing = Some(processing);
+    }
+
+    /// Current network latency estimate for `game_id`, if any.
+    pub fn network_latency(&self, game_id: &str) -> Option<Duration> {
+        self.games.get(game_id).and_then(|t| t.network_latency)
+    }
+
+    pub fn end_game(&self, game_id: &str) {
+        self.games.remove(game_id);
+    }
+}
+
+/// The engine reports latency as a string of whole milliseconds. "0" means
+/// the previous move timed out, which tells us nothing about the network.
+fn parse_latency(latency: &str) -> Option<Duration> {
+    match latency.trim().parse::<u64>() {
+        Ok(ms) if ms > 0 => Some(Duration::from_millis(ms)),
+        _ => None,
+    }
+}
+
+/// Time limits for one search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchBudget {
+    /// Searching normally ends here.
+    pub soft: Duration,
+    /// Searching always ends here.
+    pub hard: Duration,
+    pub close_value_margin: f32,
+    pub min_search_time: Duration,
+    /// Root visits carried over from an earlier search of a reused tree,
+    /// which say nothing about how fast this search is going.
+    pub reused_visits: u32,
+}
+
+impl SearchBudget {
+    /// A budget that always searches for exactly `duration`.
+    pub fn fixed(duration: Duration) -> SearchBudget {
+        SearchBudget {
+            soft: duration,
+            hard: duration,
+            close_value_margin: 0.0,
+            min_search_time: duration,
+            reused_visits: 0,
+        }
+    }
+
+    /// The budget left once `spent` has already gone on other work, such as
+    /// parsing the request and re-rooting the tree.
+    pub fn after(&self, spent: Duration) -> SearchBudget {
+        SearchBudget {
+            soft: self.soft.saturating_sub(spent),
+            hard: self.hard.saturating_sub(spent),
+            ..*self
+        }
+    }
+
+    /// The budget for a search that starts from a tree whose root already
+    /// has `visits` visits.
+    pub fn reusing(&self, visits: u32) -> SearchBudget {
+        SearchBudget {
+            reused_visits: visits,
+            ..*self
+        }
+    }
+
+    /// Whether a search that has run for `elapsed` and produced `root_moves`
+    /// for our snake should stop.
+    ///
+    /// Before the soft limit the search stops only once the most visited
+    /// move can no longer be overtaken at the current visit rate, measured
+    /// without the `reused_visits` the search started with. Between the
+    /// soft and hard limits it carries on only while the two best moves have
+    /// mean scores within `close_value_margin` of each other.
+    pub fn should_stop(&self, elapsed: Duration, root_moves: &[RootMove]) -> bool {
+        if elapsed >= self.hard {
+            return true;
+        }
+        if elapsed < self.min_search_time {
+            return false;
+        }
+        if root_moves.len() <= 1 {
+            return true;
+        }
+
+        let mut by_visits: Vec<&RootMove> = root_moves.iter().collect();
+        by_visits.sort_by_key(|m| Reverse(m.visits));
+        let lead = by_visits[0].visits - by_visits[1].visits;
+
+        let total_visits: u32 = root_moves.iter().map(|m| m.visits).sum();
+        let new_visits = total_visits.saturating_sub(self.reused_visits);
+        let visits_per_second = new_visits as f64 / elapsed.as_secs_f64();
+        let remaining_visits = visits_per_second * (self.hard - elapsed).as_secs_f64();
+        if lead as f64 > remaining_visits {
+            return true;
+        }
+
+        if elapsed < self.soft {
+            return false;
+        }
+
+        let mut by_value: Vec<f32> = root_moves.iter().map(|m| m.mean_score).collect();
+        by_value.sort_by(|a, b| b.total_cmp(a));
+        by_value[0] - by_value[1] > self.close_value_margin
+    }
+}
@@ -0,0 +1,151 @@
+//! A concurrent transposition table for the search tree.
+//!
+//! Different move orders often reach the same position. Nodes for those
+//! positions stay separate in the tree, but with a table attached to `MCTS`
+//! they share one `TranspositionEntry`: statistics are backed up into the
+//! entry as well as the node, selection reads them from the entry, and each
+//! position's evaluation is computed only once. `TreeStore` keeps one table
+//! per game, so positions searched on earlier turns are shared too.
+
+use crate::game_state::Direction;
+use crate::search::SCORE_SCALE;
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// Default cap on the number of positions kept.
+pub const DEFAULT_MAX_ENTRIES: usize = 1 << 20;
+
+/// Visits and scaled score totals, as kept on a `Node`.
+pub struct SharedStats {
+    pub visits: AtomicU32,
+    pub total_score: AtomicU32,
+}
+
+impl SharedStats {
+    fn new() -> Self {
+        SharedStats {
+            visits: AtomicU32::new(0),
+            total_score: AtomicU32::new(0),
+        }
+    }
+}
+
+/// Statistics shared by every node with the same key.
+pub struct TranspositionEntry {
+    pub visits: AtomicU32,
+    /// Scaled by 1000, one per snake.
+    pub total_score: Vec<AtomicU32>,
+    /// Per-snake statistics for each move, indexed by `Direction::index`,
+    /// used by simultaneous nodes.
+    pub move_stats: Vec<[SharedStats; 4]>,
+}
+
+impl TranspositionEntry {
+    fn new(num_snakes: usize) -> Self {
+        TranspositionEntry {
+            visits: AtomicU32::new(0),
+            total_score: (0..num_snakes).map(|_| AtomicU32::new(0)).collect(),
+            move_stats: (0..num_snakes)
+                .map(|_| std::array::from_fn(|_| SharedStats::new()))
+                .collect(),
+        }
+    }
+
+    /// Statistics for `snake_index` playing `direction` from this position.
+    pub fn move_stats(&self, snake_index: usize, direction: Direction) -> &SharedStats {
+        &self.move_stats[snake_index][direction.index()]
+    }
+
+    /// Adds one visit with the given per-snake results.
+    pub fn record(&self, results: &[f32]) {
+        self.visits.fetch_add(1, Ordering::Relaxed);
+        for (score, result) in self.total_score.iter().zip(results) {
+            let delta = (result * SCORE_SCALE) as u32; // Scale to integer
+            score.fetch_add(delta, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Position keys to shared statistics and cached evaluations. Statistics are
+/// keyed by `GameState::hash` values, combined with `zobrist::player_key` for
+/// sequential nodes. Evaluations depend on exact health, which the hash only
+/// keeps in buckets, so they are keyed by `zobrist::exact_hash` values.
+pub struct TranspositionTable {
+    entries: DashMap<u64, Arc<TranspositionEntry>>,
+    evaluations: DashMap<u64, Vec<f32>>,
+    max_entries: usize,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(DEFAULT_MAX_ENTRIES)
+    }
+}
+
+impl TranspositionTable {
+    /// A table that stops taking new positions once it holds `max_entries`
+    /// of them. Positions that don't fit are still searched, just unshared.
+    pub fn new(max_entries: usize) -> Self {
+        TranspositionTable {
+            entries: DashMap::new(),
+            evaluations: DashMap::new(),
+            max_entries,
+        }
+    }
+
+    /// The entry for `key`, created if there is room.
+    pub fn entry(&self, key: u64, num_snakes: usize) -> Option<Arc<TranspositionEntry>> {
+        if let Some(entry) = self.entries.get(&key) {
+            return Some(Arc::clone(entry.value()));
+        }
+        if self.entries.len() >= self.max_entries {
+            return None;
+        }
+        Some(Arc::clone(
+            self.entries
+                .entry(key)
+                .or_insert_with(|| Arc::new(TranspositionEntry::new(num_snakes)))
+                .value(),
+        ))
+    }
+
+    /// The entry for `key`, if the position has been seen.
+    pub fn get(&self, key: u64) -> Option<Arc<TranspositionEntry>> {
+        self.entries
+            .get(&key)
+            .map(|entry| Arc::clone(entry.value()))
+    }
+
+    /// The cached evaluation of the position with `zobrist::exact_hash`
+    /// value `hash`, computing and caching it with `evaluate` the first time.
+    pub fn evaluation(&self, hash: u64, evaluate: impl FnOnce() -> Vec<f32>) -> Vec<f32> {
+        if let Some(cached) = self.evaluations.get(&hash) {
+            return cached.clone();
+        }
+        let evaluation = evaluate();
+        if self.evaluations.len() < self.max_entries {
+            self.evaluations.insert(hash, evaluation.clone());
+        }
+        evaluation
+    }
+
+    /// The number of positions with shared statistics.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The number of cached evaluations.
+    pub fn evaluations_len(&self) -> usize {
+        self.evaluations.len()
+    }
+
+    pub fn clear(&self) {
+        self.entries.clear();
+        self.evaluations.clear();
+    }
+}
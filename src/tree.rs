@@ -1,10 +1,9 @@
-use crate::game_state::{GameState, Position, Snake as GameStateSnake};
+use crate::game_state::GameState;
 use crate::heuristic::calculate_snake_control;
-use crate::search::Node;
+use crate::search::{Node, MCTS, SCORE_SCALE};
 use crate::visualizer::{visualize_control, visualize_game_state};
 use chrono::Utc;
 use serde::Serialize;
-use std::collections::VecDeque;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
@@ -34,14 +33,23 @@ pub struct TreeNode {
     pub id: String,
     pub visits: u32,
     pub ucb: f32,
-    pub isMostVisited: bool,
+    #[serde(rename = "isMostVisited")]
+    pub is_most_visited: bool,
     pub children: Vec<TreeNode>,
     pub body: String,
     pub board: Board,
 }
 
+/// What every node of an exported tree shares.
+struct ExportSettings {
+    exploration_constant: f32,
+    /// Snake indices in the order they were given to the search, so exports
+    /// list and label snakes the way the engine does.
+    snake_order: Vec<usize>,
+}
+
 impl TreeNode {
-    fn from_node(node: &Arc<Node>, exploration_constant: f32, is_root: bool) -> Self {
+    fn from_node(node: &Arc<Node>, settings: &ExportSettings, is_root: bool) -> Self {
         // Since we're using atomics, we need to load the values
         let visits = node.visits.load(Ordering::Relaxed);
 
@@ -51,44 +59,54 @@ impl TreeNode {
             .map(|score| score.load(Ordering::Relaxed))
             .collect();
 
-        let heuristics_clone = node.heuristic.clone();
+        let heuristics_clone = node.heuristic();
         let parent_weak = node.parent.clone();
         let id = format!("Node_{:p}", Arc::as_ptr(node));
         let body = visualize_game_state(&node.game_state);
         let game_state = node.game_state.clone();
-        let terminal = node.is_terminal;
+        let terminal = node.is_terminal();
 
-        let ucb = calculate_ucb_value(&node, parent_weak.as_ref(), exploration_constant);
+        let ucb = calculate_ucb_value(node, parent_weak.as_ref(), settings.exploration_constant);
 
-        let board = game_state_to_board(&game_state);
+        let board = game_state_to_board(&game_state, &settings.snake_order);
 
         let snake_control = calculate_snake_control(&game_state);
         let control_visualization =
             visualize_control(&snake_control, game_state.width, game_state.height);
 
-        let total_scores = game_state
-            .snakes
+        let total_scores = settings
+            .snake_order
             .iter()
             .enumerate()
-            .map(|(i, _)| {
-                let score = total_score_clone.get(i).cloned().unwrap_or(0) as f32 / 1000.0;
-                format!("Player {}: total Score: {:.2}", i + 1, score)
+            .map(|(player, &i)| {
+                let score = total_score_clone.get(i).cloned().unwrap_or(0) as f32 / SCORE_SCALE;
+                format!(
+                    "Player {} ({}): total Score: {:.2}",
+                    player + 1,
+                    game_state.snakes[i].id,
+                    score
+                )
             })
             .collect::<Vec<String>>()
             .join("\n");
 
-        let heuristics = game_state
-            .snakes
+        let heuristics = settings
+            .snake_order
             .iter()
             .enumerate()
-            .map(|(i, _)| {
+            .map(|(player, &i)| {
                 // If heuristics_clone is Some, use the value; otherwise, return a default score
                 let score = heuristics_clone
                     .as_ref() // Access the reference to Option
                     .map(|heuristics| heuristics.get(i).cloned().unwrap_or(-69.0)) // Get the i-th score if exists
                     .unwrap_or(0.0); // Default to 0.0 if heuristics_clone is None
 
-                format!("Player {}: heuristic Score: {:.2}", i + 1, score)
+                format!(
+                    "Player {} ({}): heuristic Score: {:.2}",
+                    player + 1,
+                    game_state.snakes[i].id,
+                    score
+                )
             })
             .collect::<Vec<String>>()
             .join("\n");
@@ -102,7 +120,7 @@ impl TreeNode {
             id,
             visits,
             ucb,
-            isMostVisited: is_root,
+            is_most_visited: is_root,
             children: Vec::new(),
             body: body_with_extra_text,
             board,
@@ -110,16 +128,16 @@ impl TreeNode {
     }
 }
 
-fn game_state_to_board(game_state: &GameState) -> Board {
+fn game_state_to_board(game_state: &GameState, snake_order: &[usize]) -> Board {
     Board {
         height: game_state.height,
         width: game_state.width,
         food: game_state.food.iter().map(|f| f.index).collect(),
         hazards: game_state.hazards.iter().map(|h| h.index).collect(),
-        snakes: game_state
-            .snakes
+        snakes: snake_order
             .iter()
-            .map(|s| {
+            .map(|&i| {
+                let s = &game_state.snakes[i];
                 let body: Vec<usize> = s.body.iter().map(|p| p.index).collect();
                 Snake {
                     id: s.id.clone(),
@@ -132,11 +150,18 @@ fn game_state_to_board(game_state: &GameState) -> Board {
     }
 }
 
+/// Writes the search tree of `mcts` for the visualiser. Snakes are listed in
+/// `mcts.snake_order()`.
 pub fn generate_most_visited_path_with_alternatives_html_tree(
-    root_node: &Arc<Node>,
+    mcts: &MCTS,
 ) -> Result<(), std::io::Error> {
     println!("starting");
-    let tree_node = generate_tree_data(root_node);
+    println!("Search config:\n{}", mcts.config());
+    let settings = ExportSettings {
+        exploration_constant: mcts.config().exploration_constant,
+        snake_order: mcts.snake_order().arrange(&mcts.root.game_state),
+    };
+    let tree_node = generate_tree_data(&mcts.root, &settings);
 
     let timestamp = Utc::now().format("%Y%m%d_%H%M%S%.6f").to_string();
     let uuid = Uuid::new_v4().to_string();
@@ -161,26 +186,20 @@ pub fn generate_most_visited_path_with_alternatives_html_tree(
     Ok(())
 }
 
-fn generate_tree_data(root_node: &Arc<Node>) -> TreeNode {
+fn generate_tree_data(root_node: &Arc<Node>, settings: &ExportSettings) -> TreeNode {
     println!("getting data");
 
-    let root_tree_node = TreeNode::from_node(root_node, 1.414, true);
+    let root_tree_node = TreeNode::from_node(root_node, settings, true);
     println!("got data");
 
     let mut root_tree_node = root_tree_node;
-    traverse_and_build_tree(root_node, &mut root_tree_node);
+    traverse_and_build_tree(root_node, &mut root_tree_node, settings);
 
     root_tree_node
 }
 
-fn traverse_and_build_tree(node: &Arc<Node>, tree_node: &mut TreeNode) {
-    let children_nodes: Vec<_> = node
-        .children
-        .iter()
-        .map(|entry| entry.value().clone())
-        .collect();
-
-    let mut sorted_children = children_nodes;
+fn traverse_and_build_tree(node: &Arc<Node>, tree_node: &mut TreeNode, settings: &ExportSettings) {
+    let mut sorted_children = node.child_nodes();
     sorted_children.sort_by(|a, b| {
         let a_visits = a.visits.load(Ordering::Relaxed);
         let b_visits = b.visits.load(Ordering::Relaxed);
@@ -188,13 +207,13 @@ fn traverse_and_build_tree(node: &Arc<Node>, tree_node: &mut TreeNode) {
     });
 
     for (i, child_node) in sorted_children.iter().enumerate() {
-        let mut child_tree_node = TreeNode::from_node(child_node, 1.414, false);
+        let mut child_tree_node = TreeNode::from_node(child_node, settings, false);
 
         if i == 0 {
-            child_tree_node.isMostVisited = true;
+            child_tree_node.is_most_visited = true;
         }
 
-        traverse_and_build_tree(child_node, &mut child_tree_node);
+        traverse_and_build_tree(child_node, &mut child_tree_node, settings);
         tree_node.children.push(child_tree_node);
     }
 }
@@ -216,8 +235,8 @@ fn calculate_ucb_value(node: &Node, parent: Option<&Weak<Node>>, exploration_con
     // Load the total score atomically and convert it to f32 for calculation
     let total_score = node.total_score[node.current_player].load(Ordering::Relaxed) as f32;
 
-    // Adjust total_score if you scaled it during backpropagation (e.g., divided by 1000)
-    let adjusted_total_score = total_score / 1000.0;
+    // Adjust total_score for the scaling applied during backpropagation
+    let adjusted_total_score = total_score / SCORE_SCALE;
 
     // Calculate the exploitation term
     let exploitation = adjusted_total_score / node_visits;
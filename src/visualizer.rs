@@ -1,5 +1,6 @@
 // File: src/visualizer.rs
 
+use crate::analysis::BoardAnalysis;
 use crate::game_state::{GameState, Position, Snake};
 
 pub fn visualize_game_state(game_state: &GameState) -> String {
@@ -46,6 +47,9 @@ pub fn json_to_game_state(json: &serde_json::Value) -> GameState {
     let width = json["width"].as_u64().unwrap() as usize;
     let height = json["height"].as_u64().unwrap() as usize;
     let mut game = GameState::new(width, height);
+    game.turn = json["turn"].as_u64().unwrap_or(0) as u32;
+    game.mode = serde_json::from_value(json["mode"].clone()).unwrap_or_default();
+    game.settings = serde_json::from_value(json["settings"].clone()).unwrap_or_default();
 
     for snake_json in json["snakes"].as_array().unwrap() {
         let body: Vec<Position> = snake_json["body"]
@@ -61,6 +65,8 @@ pub fn json_to_game_state(json: &serde_json::Value) -> GameState {
             id: snake_json["id"].as_str().unwrap().to_string(),
             body: body.into(),
             health: snake_json["health"].as_u64().unwrap() as u8,
+            eliminated: serde_json::from_value(snake_json["eliminated"].clone()).unwrap(),
+            squad: snake_json["squad"].as_str().map(|squad| squad.to_string()),
         });
     }
 
@@ -82,10 +88,11 @@ pub fn json_to_game_state(json: &serde_json::Value) -> GameState {
         })
         .collect();
 
+    game.rehash();
     game
 }
 
-pub fn visualize_control(control: &[i8], width: usize, height: usize) -> String {
+pub fn visualize_control(control: &[i8], width: usize, _height: usize) -> String {
     control
         .chunks(width)
         .map(|row| {
@@ -100,3 +107,25 @@ pub fn visualize_control(control: &[i8], width: usize, height: usize) -> String
         .collect::<Vec<String>>()
         .join("\n")
 }
+
+/// `visualize_game_state` with the articulation points found by `analysis`
+/// marked as `+`.
+pub fn visualize_choke_points(game_state: &GameState, analysis: &BoardAnalysis) -> String {
+    visualize_game_state(game_state)
+        .lines()
+        .enumerate()
+        .map(|(y, row)| {
+            row.chars()
+                .enumerate()
+                .map(|(x, c)| {
+                    if analysis.articulation_points[y * game_state.width + x] {
+                        '+'
+                    } else {
+                        c
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
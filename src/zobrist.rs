@@ -0,0 +1,148 @@
+//! Zobrist-style hashing of `GameState`.
+//!
+//! A state's hash is the wrapping sum of one pseudo-random key per feature,
+//! so features can be added and removed in any order and stacked features
+//! (a tail duplicated after eating, two hazards on one cell) don't cancel out
+//! the way they would with XOR.
+//!
+//! Each body segment is keyed by its snake, its cell and the link to the next
+//! segment towards the tail, which pins down the whole body while still
+//! letting a move be applied by touching only the head and tail. Health is
+//! hashed in buckets so that positions that differ by a few points of health
+//! share a hash; the rest of the state is hashed exactly. `exact_hash` adds
+//! the exact health back, for caches whose results depend on it.
+//!
+//! `GameState` keeps its hash up to date as snakes move and grow. Everything
+//! else that changes at most once a turn (health, eliminations, food, hazards
+//! and turn parity) is re-hashed by `rules::step` and `rules::resolve_turn`.
+
+use crate::game_state::{Direction, GameState};
+
+/// Health values per bucket. Health 0 is always a bucket of its own.
+pub const HEALTH_BUCKET_SIZE: u8 = 10;
+
+// Links from a segment to the next one towards the tail, after the four
+// directions.
+const LINK_SAME: u64 = 4;
+const LINK_TAIL: u64 = 5;
+const LINK_OTHER: u64 = 6;
+
+const TAG_SEGMENT: u64 = 1;
+const TAG_STATUS: u64 = 2;
+const TAG_FOOD: u64 = 3;
+const TAG_HAZARD: u64 = 4;
+const TAG_TURN: u64 = 5;
+const TAG_PLAYER: u64 = 6;
+const TAG_HEALTH: u64 = 7;
+
+/// SplitMix64 finaliser.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+fn key(tag: u64, a: u64, b: u64, c: u64) -> u64 {
+    mix(mix(mix(mix(tag) ^ a) ^ b) ^ c)
+}
+
+/// Key of a body segment of `snake_index` at `cell`, linked to the next
+/// segment by `link`.
+pub fn segment_key(snake_index: usize, cell: usize, link: u64) -> u64 {
+    key(TAG_SEGMENT, snake_index as u64, cell as u64, link)
+}
+
+/// How the segment at `from` connects to the next segment at `to`.
+pub fn link(game_state: &GameState, from: usize, to: usize) -> u64 {
+    if from == to {
+        return LINK_SAME;
+    }
+    if from == usize::MAX || to == usize::MAX {
+        return LINK_OTHER;
+    }
+    Direction::ALL
+        .iter()
+        .find(|&&direction| game_state.neighbour(from, direction) == to)
+        .map(|direction| direction.index() as u64)
+        .unwrap_or(LINK_OTHER)
+}
+
+/// The link stored for the last segment of a body.
+pub fn tail_link() -> u64 {
+    LINK_TAIL
+}
+
+/// The hash of one snake's body.
+pub fn body_hash(game_state: &GameState, snake_index: usize) -> u64 {
+    let body = &game_state.snakes[snake_index].body;
+    let mut hash = 0u64;
+    for (i, segment) in body.iter().enumerate() {
+        let link = match body.get(i + 1) {
+            Some(next) => link(game_state, segment.index, next.index),
+            None => LINK_TAIL,
+        };
+        hash = hash.wrapping_add(segment_key(snake_index, segment.index, link));
+    }
+    hash
+}
+
+fn health_bucket(health: u8) -> u64 {
+    health.div_ceil(HEALTH_BUCKET_SIZE) as u64
+}
+
+/// The hash of everything that changes at most once a turn: each snake's
+/// health bucket and elimination, food, hazards and turn parity.
+pub fn turn_hash(game_state: &GameState) -> u64 {
+    let mut hash = key(TAG_TURN, (game_state.turn % 2) as u64, 0, 0);
+
+    for (i, snake) in game_state.snakes.iter().enumerate() {
+        let status = key(
+            TAG_STATUS,
+            i as u64,
+            health_bucket(snake.health),
+            snake.is_eliminated() as u64,
+        );
+        hash = hash.wrapping_add(status);
+    }
+    for food in &game_state.food {
+        hash = hash.wrapping_add(food_key(food.index));
+    }
+    for hazard in &game_state.hazards {
+        hash = hash.wrapping_add(hazard_key(hazard.index));
+    }
+    hash
+}
+
+pub fn food_key(cell: usize) -> u64 {
+    key(TAG_FOOD, cell as u64, 0, 0)
+}
+
+pub fn hazard_key(cell: usize) -> u64 {
+    key(TAG_HAZARD, cell as u64, 0, 0)
+}
+
+/// Distinguishes sequential search nodes that share a position but not the
+/// snake to move.
+pub fn player_key(player: usize) -> u64 {
+    key(TAG_PLAYER, player as u64, 0, 0)
+}
+
+/// `game_state`'s hash with every snake's exact health added, so positions
+/// that share a health bucket get different keys.
+pub fn exact_hash(game_state: &GameState) -> u64 {
+    game_state
+        .snakes
+        .iter()
+        .enumerate()
+        .fold(game_state.hash(), |hash, (i, snake)| {
+            hash.wrapping_add(key(TAG_HEALTH, i as u64, snake.health as u64, 0))
+        })
+}
+
+/// The hash of `game_state` computed from scratch.
+pub fn hash_game_state(game_state: &GameState) -> u64 {
+    (0..game_state.snakes.len()).fold(turn_hash(game_state), |hash, i| {
+        hash.wrapping_add(body_hash(game_state, i))
+    })
+}
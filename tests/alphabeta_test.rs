@@ -0,0 +1,252 @@
+// File: tests/alphabeta_test.rs
+
+use battlesnake::alphabeta::{AlphaBeta, MultiplayerStrategy};
+use battlesnake::config::{Engine, SearchConfig};
+use battlesnake::game_state::Direction;
+use battlesnake::visualizer::{json_to_game_state, visualize_game_state};
+use serde_json::json;
+use std::time::Duration;
+
+const SOFT: Duration = Duration::from_secs(5);
+const HARD: Duration = Duration::from_secs(10);
+
+fn config(max_depth: u32) -> SearchConfig {
+    let mut config = SearchConfig {
+        engine: Engine::AlphaBeta,
+        ..SearchConfig::default()
+    };
+    config.alpha_beta.max_depth = max_depth;
+    config
+}
+
+#[test]
+fn test_alpha_beta_avoids_losing_moves() {
+    struct TestCase {
+        name: &'static str,
+        input: serde_json::Value,
+        snake_id: &'static str,
+        expected_moves: Vec<Direction>,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            name: "Cornered with a single way out",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 100 },
+                    { "id": "snake2", "body": [0, 1, 2], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            snake_id: "snake2",
+            expected_moves: vec![Direction::Down],
+        },
+        TestCase {
+            // Moving left meets the longer snake's head
+            name: "Head to head with a longer snake",
+            input: json!({
+                "width": 7,
+                "height": 7,
+                "snakes": [
+                    { "id": "snake1", "body": [24, 25, 26], "health": 100 },
+                    { "id": "snake2", "body": [22, 29, 36, 43, 44], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            snake_id: "snake1",
+            expected_moves: vec![Direction::Up, Direction::Down],
+        },
+        TestCase {
+            // Going up walks into a square walled in by the other snake
+            name: "Dead end",
+            input: json!({
+                "width": 7,
+                "height": 7,
+                "snakes": [
+                    { "id": "snake1", "body": [24, 25, 26], "health": 100 },
+                    { "id": "snake2", "body": [15, 16, 9, 10, 11, 18, 19, 20], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            snake_id: "snake1",
+            expected_moves: vec![Direction::Down, Direction::Left],
+        },
+    ];
+
+    for case in test_cases {
+        let game_state = json_to_game_state(&case.input);
+        println!("Running test case: {}", case.name);
+        println!("{}", visualize_game_state(&game_state));
+
+        let result = AlphaBeta::new(&config(4)).search(&game_state, case.snake_id, SOFT, HARD);
+        println!("{}", result);
+
+        assert!(result.depth >= 1, "{}", case.name);
+        assert!(
+            result
+                .best_move
+                .is_some_and(|best| case.expected_moves.contains(&best)),
+            "{}: got {:?}",
+            case.name,
+            result.best_move
+        );
+    }
+}
+
+#[test]
+fn test_alpha_beta_is_deterministic() {
+    let game_state = json_to_game_state(&json!({
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [24, 25, 26], "health": 100 },
+            { "id": "snake2", "body": [10, 3, 4], "health": 100 }
+        ],
+        "food": [30],
+        "hazards": []
+    }));
+    let alpha_beta = AlphaBeta::new(&config(3));
+
+    let first = alpha_beta.search(&game_state, "snake1", SOFT, HARD);
+    let second = alpha_beta.search(&game_state, "snake1", SOFT, HARD);
+
+    assert_eq!(first.depth, 3);
+    assert_eq!(first.best_move, second.best_move);
+    assert_eq!(first.value, second.value);
+    assert_eq!(first.moves, second.moves);
+    assert_eq!(first.nodes, second.nodes);
+}
+
+#[test]
+fn test_alpha_beta_stops_once_every_line_has_ended() {
+    // Both snakes starve within three turns
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [6, 7, 8], "health": 2 },
+            { "id": "snake2", "body": [16, 17, 18], "health": 3 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+
+    let result = AlphaBeta::new(&config(10)).search(&game_state, "snake2", SOFT, HARD);
+
+    assert!(result.solved);
+    assert!(result.depth < 10);
+    assert_eq!(result.value, SearchConfig::default().terminal.win);
+}
+
+#[test]
+fn test_alpha_beta_without_our_snake() {
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [6, 7, 8], "health": 100 },
+            { "id": "snake2", "body": [16, 17, 18], "health": 0 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+    let alpha_beta = AlphaBeta::new(&config(3));
+
+    for snake_id in ["snake2", "missing"] {
+        let result = alpha_beta.search(&game_state, snake_id, SOFT, HARD);
+        assert_eq!(result.best_move, None, "{}", snake_id);
+        assert_eq!(result.nodes, 0);
+    }
+}
+
+#[test]
+fn test_multiplayer_strategies() {
+    let game_state = json_to_game_state(&json!({
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [24, 25, 26], "health": 100 },
+            { "id": "snake2", "body": [8, 1, 2], "health": 100 },
+            { "id": "snake3", "body": [40, 41, 48], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+    let safe_moves = game_state.get_safe_moves(0);
+
+    for strategy in [
+        MultiplayerStrategy::Paranoid,
+        MultiplayerStrategy::MaxN,
+        MultiplayerStrategy::BestReply,
+    ] {
+        let mut config = config(2);
+        config.alpha_beta.multiplayer = strategy;
+
+        let result = AlphaBeta::new(&config).search(&game_state, "snake1", SOFT, HARD);
+        println!("{:?}: {}", strategy, result);
+
+        assert_eq!(result.depth, 2, "{:?}", strategy);
+        assert_eq!(result.moves.len(), safe_moves.len(), "{:?}", strategy);
+        assert!(
+            safe_moves.contains(&result.best_move.unwrap()),
+            "{:?}",
+            strategy
+        );
+    }
+}
+
+#[test]
+fn test_search_stops_at_the_deadline() {
+    let game_state = json_to_game_state(&json!({
+        "width": 11,
+        "height": 11,
+        "snakes": [
+            { "id": "snake1", "body": [60, 61, 62], "health": 100 },
+            { "id": "snake2", "body": [20, 21, 22], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+
+    let result = AlphaBeta::new(&config(64)).search(
+        &game_state,
+        "snake1",
+        Duration::from_millis(50),
+        Duration::from_millis(100),
+    );
+
+    assert!(result.best_move.is_some());
+    assert!(result.depth < 64);
+    assert!(!result.solved);
+}
+
+#[test]
+fn test_auto_engine_switches_on_snake_count() {
+    let mut game_state = json_to_game_state(&json!({
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [24, 25, 26], "health": 100 },
+            { "id": "snake2", "body": [8, 1, 2], "health": 100 },
+            { "id": "snake3", "body": [40, 41, 48], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+    let config = SearchConfig {
+        engine: Engine::Auto,
+        ..SearchConfig::default()
+    };
+
+    assert_eq!(config.engine_for(&game_state), Engine::Mcts);
+    game_state.snakes[2].health = 0;
+    assert_eq!(config.engine_for(&game_state), Engine::AlphaBeta);
+
+    let mcts_only = SearchConfig::default();
+    assert_eq!(mcts_only.engine_for(&game_state), Engine::Mcts);
+}
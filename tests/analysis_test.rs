@@ -0,0 +1,177 @@
+// File: tests/analysis_test.rs
+
+use battlesnake::analysis::{BoardAnalysis, BoardGraph};
+use battlesnake::game_state::GameState;
+use battlesnake::visualizer::{json_to_game_state, visualize_choke_points};
+use proptest::prelude::*;
+use serde_json::json;
+
+#[test]
+fn test_board_analysis() {
+    struct TestCase {
+        name: &'static str,
+        input: serde_json::Value,
+        expected_choke_points: Vec<usize>,
+        expected_components: usize,
+        expected_corridor_width: Vec<usize>,
+        expected_overlay: &'static str,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            name: "Open board",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [],
+                "food": [],
+                "hazards": []
+            }),
+            expected_choke_points: vec![],
+            expected_components: 1,
+            expected_corridor_width: vec![5; 25],
+            expected_overlay: ".....\n.....\n.....\n.....\n.....",
+        },
+        TestCase {
+            // The only way between the top and the bottom is down the right
+            // edge
+            name: "Single gap",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [10, 11, 12, 13], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            expected_choke_points: vec![9, 14, 19],
+            expected_components: 4,
+            expected_corridor_width: vec![
+                2, 2, 2, 2, 5, 2, 2, 2, 2, 5, 0, 0, 0, 0, 1, 2, 2, 2, 2, 5, 2, 2, 2, 2, 5,
+            ],
+            expected_overlay: ".....\n....+\nAaaa+\n....+\n.....",
+        },
+        TestCase {
+            // Two walls leave one-wide corridors joined down the right edge
+            name: "Corridors",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [5, 6, 7, 8], "health": 100 },
+                    { "id": "snake2", "body": [15, 16, 17, 18], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            expected_choke_points: vec![1, 2, 3, 4, 9, 11, 12, 13, 14, 19, 21, 22, 23, 24],
+            expected_components: 16,
+            expected_corridor_width: vec![
+                1, 1, 1, 1, 5, 0, 0, 0, 0, 1, 1, 1, 1, 1, 5, 0, 0, 0, 0, 1, 1, 1, 1, 1, 5,
+            ],
+            expected_overlay: ".++++\nAaaa+\n.++++\nBbbb+\n.++++",
+        },
+    ];
+
+    for case in test_cases {
+        let game_state = json_to_game_state(&case.input);
+        let analysis = BoardAnalysis::new(&game_state);
+        let overlay = visualize_choke_points(&game_state, &analysis);
+        println!("Test case: {}", case.name);
+        println!("{}", overlay);
+
+        assert_eq!(
+            analysis.choke_points(),
+            case.expected_choke_points,
+            "{}",
+            case.name
+        );
+        assert_eq!(
+            analysis.components.len(),
+            case.expected_components,
+            "{}",
+            case.name
+        );
+        assert_eq!(
+            analysis.corridor_width, case.expected_corridor_width,
+            "{}",
+            case.name
+        );
+        assert_eq!(overlay, case.expected_overlay, "{}", case.name);
+    }
+}
+
+#[test]
+fn test_taking_a_choke_point_splits_the_board() {
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [10, 11, 12, 13], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+    let analysis = BoardAnalysis::new(&game_state);
+
+    assert!(analysis.splits_board(14));
+    assert_eq!(analysis.regions_if_taken(14), vec![10, 10]);
+    assert_eq!(analysis.regions_if_taken(9), vec![11, 9]);
+    assert!(!analysis.splits_board(0));
+    assert_eq!(analysis.regions_if_taken(0), vec![20]);
+}
+
+#[test]
+fn test_wrapped_boards_have_no_edges() {
+    // The wall across the board is open at both ends once the edges wrap
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [10, 11, 12, 13], "health": 100 }
+        ],
+        "food": [],
+        "hazards": [],
+        "mode": "wrapped"
+    }));
+    let analysis = BoardAnalysis::new(&game_state);
+
+    assert!(analysis.choke_points().is_empty());
+    assert_eq!(analysis.regions_if_taken(14), vec![20]);
+}
+
+/// A board with a one-square snake on each of `taken`.
+fn board_with_taken_squares(size: usize, taken: &[usize]) -> GameState {
+    let mut game_state = GameState::new(size, size);
+    for (i, &index) in taken.iter().enumerate() {
+        game_state.add_snake(format!("snake{}", i), vec![index], 100);
+    }
+    game_state
+}
+
+proptest! {
+    #[test]
+    fn articulation_points_are_the_squares_that_split_the_board(
+        taken in prop::collection::vec(0usize..36, 0..14)
+    ) {
+        let game_state = board_with_taken_squares(6, &taken);
+        let analysis = BoardAnalysis::new(&game_state);
+        let graph = BoardGraph::from_game_state(&game_state);
+        let regions = graph.region_sizes(None).len();
+
+        for index in 0..36 {
+            let splits = graph.free[index] && graph.region_sizes(Some(index)).len() > regions;
+            prop_assert_eq!(analysis.splits_board(index), splits, "square {}", index);
+        }
+        // Every edge belongs to exactly one component, so components only
+        // share articulation points
+        for (i, a) in analysis.components.iter().enumerate() {
+            for b in &analysis.components[i + 1..] {
+                for square in a.iter().filter(|square| b.contains(square)) {
+                    prop_assert!(analysis.splits_board(*square));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+// File: tests/battlesnake_api_test.rs
+
+use battlesnake::battlesnake_api::{
+    direction_from_move, direction_to_move, BattlesnakeRequest, Coord,
+};
+use battlesnake::game_state::Direction;
+use battlesnake::rules::{bounded_neighbour, GameMode, Settings};
+use proptest::prelude::*;
+use serde_json::json;
+
+fn snake(id: &str, body: serde_json::Value) -> serde_json::Value {
+    let head = body[0].clone();
+    let length = body.as_array().unwrap().len();
+    json!({
+        "id": id,
+        "name": id,
+        "health": 90,
+        "body": body,
+        "head": head,
+        "length": length,
+        "latency": "0",
+        "shout": "",
+        "squad": ""
+    })
+}
+
+fn request(ruleset: serde_json::Value) -> BattlesnakeRequest {
+    let you = snake("you", json!([{ "x": 1, "y": 1 }, { "x": 1, "y": 0 }]));
+    serde_json::from_value(json!({
+        "game": {
+            "id": "game-id",
+            "ruleset": ruleset,
+            "map": "standard",
+            "source": "custom",
+            "timeout": 500
+        },
+        "turn": 7,
+        "board": {
+            "height": 11,
+            "width": 11,
+            "food": [],
+            "hazards": [],
+            "snakes": [you]
+        },
+        "you": you
+    }))
+    .expect("Failed to deserialize request")
+}
+
+#[test]
+fn test_ruleset_settings_flow_into_game_state() {
+    let request = request(json!({
+        "name": "royale",
+        "version": "v1.2.3",
+        "settings": {
+            "foodSpawnChance": 25,
+            "minimumFood": 2,
+            "hazardDamagePerTurn": 30,
+            "royale": { "shrinkEveryNTurns": 10 },
+            "squad": {
+                "allowBodyCollisions": false,
+                "sharedElimination": true,
+                "sharedHealth": false,
+                "sharedLength": true
+            }
+        }
+    }));
+
+    let game_state = request.to_game_state();
+
+    assert_eq!(game_state.mode, GameMode::Royale);
+    assert_eq!(game_state.turn, 7);
+    assert_eq!(game_state.settings.food_spawn_chance, 25);
+    assert_eq!(game_state.settings.minimum_food, 2);
+    assert_eq!(game_state.settings.hazard_damage_per_turn, 30);
+    assert_eq!(game_state.settings.royale.shrink_every_n_turns, 10);
+    assert!(!game_state.settings.squad.allow_body_collisions);
+    assert!(game_state.settings.squad.shared_elimination);
+    assert!(!game_state.settings.squad.shared_health);
+    assert!(game_state.settings.squad.shared_length);
+}
+
+#[test]
+fn test_missing_settings_use_engine_defaults() {
+    let request = request(json!({ "name": "standard", "version": "v1.2.3" }));
+
+    let game_state = request.to_game_state();
+
+    assert_eq!(game_state.mode, GameMode::Standard);
+    assert_eq!(game_state.settings, Settings::default());
+}
+
+#[test]
+fn test_you_is_always_the_first_snake() {
+    let mut request = request(json!({ "name": "standard", "version": "v1.2.3" }));
+    let first = snake("first", json!([{ "x": 5, "y": 5 }, { "x": 5, "y": 4 }]));
+    let last = snake("last", json!([{ "x": 9, "y": 9 }, { "x": 9, "y": 8 }]));
+    request.board.snakes = serde_json::from_value(json!([
+        first,
+        snake("you", json!([{ "x": 1, "y": 1 }, { "x": 1, "y": 0 }])),
+        last
+    ]))
+    .unwrap();
+
+    let game_state = request.to_game_state();
+    let ids: Vec<&str> = game_state.snakes.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, vec!["you", "first", "last"]);
+
+    let order = request.snake_order();
+    assert_eq!(order.original_index("first"), Some(0));
+    assert_eq!(order.original_index("you"), Some(1));
+    assert_eq!(order.original_index("last"), Some(2));
+    assert_eq!(order.original_index("gone"), None);
+    assert_eq!(order.arrange(&game_state), vec![1, 0, 2]);
+}
+
+#[test]
+fn test_api_directions_are_board_directions() {
+    // you's neck is below its head, so it is heading up
+    let request = request(json!({ "name": "standard", "version": "v1.2.3" }));
+    let game_state = request.to_game_state();
+
+    assert_eq!(
+        game_state.snakes[0].head().index,
+        Coord { x: 1, y: 1 }.to_index(11, 11)
+    );
+    assert_eq!(game_state.default_move(0), Direction::Up);
+    assert_eq!(direction_to_move(game_state.default_move(0)), "up");
+    assert_eq!(Coord { x: 0, y: 10 }.to_index(11, 11), 0);
+    assert_eq!(direction_from_move("sideways"), None);
+}
+
+fn board_and_coord() -> impl Strategy<Value = (usize, usize, Coord)> {
+    (1usize..=25, 1usize..=25).prop_flat_map(|(width, height)| {
+        (0..width, 0..height).prop_map(move |(x, y)| (width, height, Coord { x, y }))
+    })
+}
+
+fn direction() -> impl Strategy<Value = Direction> {
+    prop::sample::select(Direction::ALL.to_vec())
+}
+
+proptest! {
+    #[test]
+    fn prop_coords_round_trip_through_board_indices((width, height, coord) in board_and_coord()) {
+        let index = coord.to_index(width, height);
+        prop_assert!(index < width * height);
+        prop_assert_eq!(Coord::from_index(index, width, height), coord);
+        prop_assert_eq!(Coord::from_index(index, width, height).to_index(width, height), index);
+    }
+
+    #[test]
+    fn prop_directions_round_trip_through_move_names(direction in direction()) {
+        prop_assert_eq!(direction_from_move(direction_to_move(direction)), Some(direction));
+    }
+
+    #[test]
+    fn prop_board_moves_match_api_moves(
+        (width, height, coord) in board_and_coord(),
+        direction in direction(),
+    ) {
+        let (x, y) = (coord.x as i64, coord.y as i64);
+        let (x, y) = match direction_to_move(direction) {
+            "up" => (x, y + 1),
+            "down" => (x, y - 1),
+            "left" => (x - 1, y),
+            _ => (x + 1, y),
+        };
+        let expected = if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+            usize::MAX
+        } else {
+            Coord { x: x as usize, y: y as usize }.to_index(width, height)
+        };
+
+        prop_assert_eq!(
+            bounded_neighbour(width, height, coord.to_index(width, height), direction),
+            expected
+        );
+    }
+}
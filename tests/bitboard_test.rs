@@ -0,0 +1,304 @@
+// File: tests/bitboard_test.rs
+
+mod common;
+
+use battlesnake::bitboard::{Bitboard11, Bitboard19, BitboardError};
+use battlesnake::game_state::{Direction, GameState};
+use battlesnake::visualizer::json_to_game_state;
+use common::without_food_spawns;
+use rand::seq::SliceRandom;
+use serde_json::json;
+
+struct TestCase {
+    name: &'static str,
+    initial_state: serde_json::Value,
+    turns: Vec<Vec<Direction>>, // One move per snake index, per turn
+}
+
+/// Food and hazard order is not part of the position.
+fn normalized(game_state: &GameState) -> serde_json::Value {
+    let mut game_state = game_state.clone();
+    game_state.food.sort_by_key(|p| p.index);
+    game_state.hazards.sort_by_key(|p| p.index);
+    serde_json::to_value(game_state).unwrap()
+}
+
+fn create_test_cases() -> Vec<TestCase> {
+    use Direction::*;
+
+    vec![
+        TestCase {
+            name: "Longer snake wins a head-to-head",
+            initial_state: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [11, 10], "health": 100 },
+                    { "id": "snake2", "body": [13, 14, 9], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            turns: vec![vec![Right, Left]],
+        },
+        TestCase {
+            name: "Body collision and moving into a tail",
+            initial_state: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [6, 7, 12, 11], "health": 100 },
+                    { "id": "snake2", "body": [2, 3, 4], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            turns: vec![vec![Down, Down], vec![Left, Down]],
+        },
+        TestCase {
+            name: "Eating, stacked hazards and food on a hazard",
+            initial_state: json!({
+                "width": 5,
+                "height": 5,
+                "settings": { "hazardDamagePerTurn": 30 },
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 90 },
+                    { "id": "snake2", "body": [2, 1, 0], "health": 50 }
+                ],
+                "food": [11, 3],
+                "hazards": [11, 7, 7, 6]
+            }),
+            turns: vec![vec![Left, Right], vec![Up, Down], vec![Right, Down]],
+        },
+        TestCase {
+            name: "Starving and leaving the board",
+            initial_state: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 1 },
+                    { "id": "snake2", "body": [0, 1, 2], "health": 100 },
+                    { "id": "snake3", "body": [24, 23, 22], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            turns: vec![vec![Up, Up, Left]],
+        },
+        TestCase {
+            name: "Wrapped snakes cross the edges",
+            initial_state: json!({
+                "mode": "wrapped",
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [0, 1, 2], "health": 100 },
+                    { "id": "snake2", "body": [24, 23, 22], "health": 100 }
+                ],
+                "food": [4],
+                "hazards": []
+            }),
+            turns: vec![vec![Left, Down], vec![Up, Right]],
+        },
+        TestCase {
+            name: "Constrictor snakes grow every turn",
+            initial_state: json!({
+                "mode": "constrictor",
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13], "health": 100 },
+                    { "id": "snake2", "body": [0, 1], "health": 100 }
+                ],
+                "food": [7],
+                "hazards": []
+            }),
+            turns: vec![vec![Up, Down], vec![Left, Right], vec![Down, Right]],
+        },
+        TestCase {
+            name: "Squadmates pass through each other and share",
+            initial_state: json!({
+                "mode": "squad",
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 60, "squad": "a" },
+                    { "id": "snake2", "body": [6, 11, 16, 21], "health": 90, "squad": "a" },
+                    { "id": "snake3", "body": [0, 1, 2], "health": 100, "squad": "b" },
+                    { "id": "snake4", "body": [4, 3], "health": 100, "squad": "b" }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            turns: vec![vec![Left, Up, Down, Down], vec![Up, Right, Down, Down]],
+        },
+    ]
+}
+
+#[test]
+fn test_bitboard_follows_game_state_rules() {
+    for case in create_test_cases() {
+        let mut game_state = without_food_spawns(json_to_game_state(&case.initial_state));
+        let mut bitboard = Bitboard11::from_game_state(&game_state).unwrap();
+        assert_eq!(
+            normalized(&bitboard.to_game_state()),
+            normalized(&game_state),
+            "Failed round trip: {}",
+            case.name
+        );
+
+        for (turn, moves) in case.turns.iter().enumerate() {
+            game_state.step(moves);
+            bitboard.step(moves).unwrap();
+            assert_eq!(
+                normalized(&bitboard.to_game_state()),
+                normalized(&game_state),
+                "Failed: {} on turn {}",
+                case.name,
+                turn
+            );
+            assert_eq!(bitboard.is_game_over(), game_state.is_game_over());
+        }
+    }
+}
+
+#[test]
+fn test_random_playouts_match_game_state() {
+    let mut rng = rand::thread_rng();
+
+    let modes = [
+        "standard",
+        "wrapped",
+        "constrictor",
+        "royale",
+        "solo",
+        "squad",
+    ];
+    for mode in modes {
+        for _ in 0..50 {
+            let mut game_state = without_food_spawns(json_to_game_state(&json!({
+                "mode": mode,
+                "width": 7,
+                "height": 7,
+                // The royale border grows on a random side, which the two
+                // states would pick differently
+                "settings": { "royale": { "shrinkEveryNTurns": 0 } },
+                "snakes": [
+                    { "id": "snake1", "body": [8, 8, 8], "health": 100, "squad": "red" },
+                    { "id": "snake2", "body": [12, 12, 12], "health": 100, "squad": "blue" },
+                    { "id": "snake3", "body": [36, 36, 36], "health": 100, "squad": "red" },
+                    { "id": "snake4", "body": [40, 40, 40], "health": 100, "squad": "blue" }
+                ],
+                "food": [24, 2, 46, 21],
+                "hazards": [0, 6, 6]
+            })));
+            let mut bitboard = Bitboard11::from_game_state(&game_state).unwrap();
+
+            while !game_state.is_game_over() && game_state.turn < 100 {
+                let moves: Vec<Direction> = (0..game_state.snakes.len())
+                    .map(|i| {
+                        let safe_moves = game_state.get_safe_moves(i);
+                        let bitboard_moves: Vec<Direction> = bitboard.safe_moves(i).collect();
+                        assert_eq!(safe_moves, bitboard_moves);
+                        safe_moves
+                            .choose(&mut rng)
+                            .copied()
+                            .unwrap_or(Direction::Up)
+                    })
+                    .collect();
+
+                game_state.step(&moves);
+                bitboard.step(&moves).unwrap();
+                assert_eq!(
+                    normalized(&bitboard.to_game_state()),
+                    normalized(&game_state),
+                    "Diverged in {} after moves {:?}",
+                    mode,
+                    moves
+                );
+            }
+            assert_eq!(bitboard.is_game_over(), game_state.is_game_over());
+        }
+    }
+}
+
+#[test]
+fn test_board_size_limits() {
+    let large = json_to_game_state(&json!({
+        "width": 19,
+        "height": 19,
+        "snakes": [
+            { "id": "snake1", "body": [0, 1, 2], "health": 100 },
+            { "id": "snake2", "body": [360, 359, 358], "health": 100 }
+        ],
+        "food": [180],
+        "hazards": [200, 300]
+    }));
+
+    assert_eq!(
+        Bitboard11::from_game_state(&large).unwrap_err(),
+        BitboardError::BoardTooLarge {
+            width: 19,
+            height: 19
+        }
+    );
+
+    let mut bitboard = Bitboard19::try_from(&large).unwrap();
+    assert_eq!(normalized(&GameState::from(&bitboard)), normalized(&large));
+    assert!(bitboard.is_food(180));
+    assert!(bitboard.is_hazard(300));
+
+    let mut game_state = without_food_spawns(large);
+    bitboard.settings = game_state.settings.clone();
+    game_state.step(&[Direction::Down, Direction::Up]);
+    bitboard.step(&[Direction::Down, Direction::Up]).unwrap();
+    assert_eq!(
+        normalized(&bitboard.to_game_state()),
+        normalized(&game_state)
+    );
+    assert_eq!(bitboard.head(0), 19);
+    assert_eq!(bitboard.head(1), 341);
+}
+
+#[test]
+fn test_too_many_snakes() {
+    let mut game_state = GameState::new(11, 11);
+    for i in 0..9 {
+        game_state.add_snake(format!("snake{}", i), vec![i * 11], 100);
+    }
+    assert_eq!(
+        Bitboard11::from_game_state(&game_state).unwrap_err(),
+        BitboardError::TooManySnakes(9)
+    );
+}
+
+#[test]
+fn test_snakes_too_long_to_grow() {
+    // Constrictor snakes grow every turn, and snake1 already fills its body
+    let mut body = vec![24; 128];
+    body[0] = 17;
+    let mut game_state = without_food_spawns(json_to_game_state(&json!({
+        "mode": "constrictor",
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": body, "health": 100 },
+            { "id": "snake2", "body": [0, 0, 0], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    })));
+
+    let mut bitboard = Bitboard11::from_game_state(&game_state).unwrap();
+    assert_eq!(
+        bitboard.step(&[Direction::Up, Direction::Right]),
+        Err(BitboardError::SnakeTooLong {
+            id: "snake1".to_string(),
+            length: 129
+        })
+    );
+
+    // The game plays on as a GameState
+    game_state.step(&[Direction::Up, Direction::Right]);
+    assert_eq!(game_state.snakes[0].length(), 129);
+}
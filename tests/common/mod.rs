@@ -0,0 +1,9 @@
+use battlesnake::game_state::GameState;
+
+/// Food spawns at random squares, so tests that compare positions play
+/// without it.
+pub fn without_food_spawns(mut game_state: GameState) -> GameState {
+    game_state.settings.minimum_food = 0;
+    game_state.settings.food_spawn_chance = 0;
+    game_state
+}
@@ -0,0 +1,239 @@
+// File: tests/config_test.rs
+
+use battlesnake::alphabeta::MultiplayerStrategy;
+use battlesnake::config::{
+    ConfigError, Engine, FinalMovePolicy, Heuristic, SearchConfig, TerminalScores,
+};
+use battlesnake::heuristic::ControlRule;
+use battlesnake::search::{Parallelism, SearchMode};
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    move |name| vars.get(name).cloned()
+}
+
+#[test]
+fn test_config_round_trips_through_toml_and_json() {
+    let config = SearchConfig {
+        engine: Engine::AlphaBeta,
+        mode: SearchMode::Sequential,
+        exploration_constant: 0.9,
+        parallelism: Parallelism::LeafParallel,
+        threads: Some(3),
+        max_nodes: Some(10_000),
+        ..SearchConfig::default()
+    };
+
+    assert_eq!(
+        SearchConfig::from_toml(&config.to_toml()),
+        Ok(config.clone())
+    );
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(SearchConfig::from_json(&json), Ok(config.clone()));
+
+    let default = SearchConfig::default();
+    assert_eq!(default.mode, SearchMode::default());
+    assert_eq!(SearchConfig::from_toml(&default.to_string()), Ok(default));
+}
+
+#[test]
+fn test_config_files_only_need_the_settings_they_change() {
+    struct TestCase {
+        name: &'static str,
+        toml: &'static str,
+        expected: SearchConfig,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            name: "Empty file",
+            toml: "",
+            expected: SearchConfig::default(),
+        },
+        TestCase {
+            name: "Top level settings",
+            toml: r#"
+                mode = "sequential"
+                exploration_constant = 2.0
+                parallelism = "root-parallel"
+                max_nodes = 500
+            "#,
+            expected: SearchConfig {
+                mode: SearchMode::Sequential,
+                exploration_constant: 2.0,
+                parallelism: Parallelism::RootParallel,
+                max_nodes: Some(500),
+                ..SearchConfig::default()
+            },
+        },
+        TestCase {
+            name: "Nested tables",
+            toml: r#"
+                [terminal]
+                draw = 0.5
+
+                [alpha_beta]
+                multiplayer = "paranoid"
+
+                [control]
+                rule = "head-to-head"
+
+                [time]
+                move_time_ms = 250
+            "#,
+            expected: {
+                let mut config = SearchConfig::default();
+                config.terminal.draw = 0.5;
+                config.alpha_beta.multiplayer = MultiplayerStrategy::Paranoid;
+                config.control.rule = ControlRule::HeadToHead;
+                config.time.move_time_ms = Some(250);
+                config
+            },
+        },
+    ];
+
+    for case in test_cases {
+        assert_eq!(
+            SearchConfig::from_toml(case.toml),
+            Ok(case.expected),
+            "{}",
+            case.name
+        );
+    }
+}
+
+#[test]
+fn test_invalid_configs_are_rejected() {
+    let invalid = [
+        "explorashun_constant = 1.0",
+        "parallelism = \"gpu\"",
+        "exploration_constant = -1.0",
+        "threads = 0",
+        "[terminal]\nloss = -1.0",
+        "[alpha_beta]\nmax_depth = 0",
+        "[matrix_game]\nsolver_iterations = 0",
+        "engine = \"matrix\"",
+        "[composite]\nfood = -0.5",
+        "heuristic = \"network\"",
+        "[composite]\nterritory = 0.0\nlength = 0.0\nhealth = 0.0\nfood = 0.0\nhazard = 0.0",
+    ];
+
+    for toml in invalid {
+        assert!(SearchConfig::from_toml(toml).is_err(), "{}", toml);
+    }
+    assert!(matches!(
+        SearchConfig::from_json("{\"terminal\": {\"win\": 2.0}}"),
+        Err(ConfigError::Invalid(_))
+    ));
+}
+
+#[test]
+fn test_environment_overrides() {
+    let mut config = SearchConfig::default();
+    config
+        .apply_env(env(&[
+            ("SEARCH_MODE", "Sequential"),
+            ("SEARCH_EXPLORATION_CONSTANT", "0.7"),
+            ("SEARCH_PARALLELISM", "root_parallel"),
+            ("SEARCH_THREADS", "4"),
+            ("SEARCH_HEURISTIC", "composite"),
+            ("CONTROL_FOOD_AWARE", "true"),
+            ("CONTROL_RULE", "Least_Health"),
+            ("NETWORK_WEIGHTS", "weights/value.json"),
+            ("COMPOSITE_LENGTH_WEIGHT", "2"),
+            ("COMPOSITE_HAZARD_WEIGHT", "0"),
+            ("SEARCH_MAX_NODES", "20000"),
+            ("SEARCH_MOVE_TIME_MS", "300"),
+            ("TIME_SAFETY_MARGIN_MS", "80"),
+            ("SEARCH_DRAW_SCORE", "0.25"),
+            ("SEARCH_FINAL_MOVE", "secure_child"),
+            ("SEARCH_LOSS_SCORE", ""),
+            ("SEARCH_ENGINE", "auto"),
+            ("ALPHA_BETA_MAX_DEPTH", "12"),
+            ("ALPHA_BETA_MULTIPLAYER", "max_n"),
+            ("ALPHA_BETA_MAX_SNAKES", "3"),
+            ("MATRIX_GAME_MAX_DEPTH", "6"),
+            ("MATRIX_GAME_SOLVER_ITERATIONS", "500"),
+        ]))
+        .unwrap();
+
+    assert_eq!(config.mode, SearchMode::Sequential);
+    assert_eq!(config.heuristic, Heuristic::Composite);
+    assert!(config.control.food_aware);
+    assert_eq!(config.control.rule, ControlRule::LeastHealth);
+    assert_eq!(
+        config.network.weights.as_deref(),
+        Some("weights/value.json")
+    );
+    assert_eq!(config.composite.length, 2.0);
+    assert_eq!(config.composite.hazard, 0.0);
+    assert_eq!(config.composite.territory, 1.0);
+    assert_eq!(config.engine, Engine::Auto);
+    assert_eq!(config.alpha_beta.max_depth, 12);
+    assert_eq!(config.alpha_beta.multiplayer, MultiplayerStrategy::MaxN);
+    assert_eq!(config.alpha_beta.max_snakes, 3);
+    assert_eq!(config.matrix_game.max_depth, 6);
+    assert_eq!(config.matrix_game.solver_iterations, 500);
+    assert_eq!(config.final_move, FinalMovePolicy::SecureChild);
+    assert_eq!(config.exploration_constant, 0.7);
+    assert_eq!(config.parallelism, Parallelism::RootParallel);
+    assert_eq!(config.num_threads(), 4);
+    assert_eq!(config.max_nodes, Some(20000));
+    assert_eq!(config.time.move_time(), Some(Duration::from_millis(300)));
+    assert_eq!(
+        config.time.settings().safety_margin,
+        Duration::from_millis(80)
+    );
+    assert_eq!(
+        config.terminal,
+        TerminalScores {
+            draw: 0.25,
+            ..TerminalScores::default()
+        }
+    );
+
+    let invalid = [
+        ("SEARCH_THREADS", "many"),
+        ("SEARCH_MODE", "alternating"),
+        ("SEARCH_WIN_SCORE", "5"),
+        ("ALPHA_BETA_MULTIPLAYER", "everyone"),
+        ("COMPOSITE_FOOD_WEIGHT", "-1"),
+        ("CONTROL_FOOD_AWARE", "sometimes"),
+        ("CONTROL_RULE", "last-to-arrive"),
+    ];
+    for (name, value) in invalid {
+        let mut config = SearchConfig::default();
+        assert!(config.apply_env(env(&[(name, value)])).is_err(), "{}", name);
+    }
+}
+
+#[test]
+fn test_config_files_load_by_extension() {
+    let dir = std::env::temp_dir().join(format!("search-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let toml_path = dir.join("search.toml");
+    std::fs::write(&toml_path, "threads = 2\n").unwrap();
+    let json_path = dir.join("search.json");
+    std::fs::write(&json_path, "{\"threads\": 5}").unwrap();
+
+    assert_eq!(
+        SearchConfig::from_file(&toml_path).unwrap().threads,
+        Some(2)
+    );
+    assert_eq!(
+        SearchConfig::from_file(&json_path).unwrap().threads,
+        Some(5)
+    );
+    assert!(matches!(
+        SearchConfig::from_file(dir.join("missing.toml")),
+        Err(ConfigError::Io(_))
+    ));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
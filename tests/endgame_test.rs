@@ -0,0 +1,334 @@
+// File: tests/endgame_test.rs
+
+use battlesnake::config::TerminalScores;
+use battlesnake::endgame::{decided_scores, doomed_snakes, isolated_region, longest_survival};
+use battlesnake::search::{SearchMode, MCTS};
+use battlesnake::visualizer::{json_to_game_state, visualize_game_state};
+use serde_json::json;
+use std::time::Duration;
+
+#[test]
+fn test_longest_survival() {
+    struct TestCase {
+        name: &'static str,
+        input: serde_json::Value,
+        region: Option<Vec<usize>>,
+        turns: u32,
+        doomed: bool,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            // Square 0 is walled in by the body, which is too long to move
+            // out of the way in time
+            name: "Pocket of one square",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [5, 10, 11, 6, 1, 2, 3], "health": 100 },
+                    { "id": "snake2", "body": [22, 23, 24], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            region: Some(vec![0]),
+            turns: 1,
+            doomed: true,
+        },
+        TestCase {
+            // The tail moves off square 1, which opens onto the rest of the
+            // board
+            name: "Escaping past the tail",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [5, 10, 11, 6, 1], "health": 100 },
+                    { "id": "snake2", "body": [22, 23, 24], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            region: Some(vec![0]),
+            turns: 6,
+            doomed: false,
+        },
+        TestCase {
+            // Starving before the tail opens the way out
+            name: "Starving in the pocket",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [5, 10, 11, 6, 1], "health": 2 },
+                    { "id": "snake2", "body": [22, 23, 24], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            region: Some(vec![0]),
+            turns: 1,
+            doomed: true,
+        },
+        TestCase {
+            // Eating first refills the snake's health
+            name: "Food in the pocket",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [5, 10, 11, 6, 1], "health": 3 },
+                    { "id": "snake2", "body": [22, 23, 24], "health": 100 }
+                ],
+                "food": [0],
+                "hazards": []
+            }),
+            region: Some(vec![0]),
+            turns: 6,
+            doomed: false,
+        },
+        TestCase {
+            name: "Open board",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 100 },
+                    { "id": "snake2", "body": [22, 23, 24], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            region: None,
+            turns: 0,
+            doomed: false,
+        },
+    ];
+
+    for case in test_cases {
+        let game_state = json_to_game_state(&case.input);
+        println!("Running test case: {}", case.name);
+        println!("{}", visualize_game_state(&game_state));
+
+        let region = isolated_region(&game_state, 0);
+        assert_eq!(region, case.region, "{}", case.name);
+        let Some(region) = region else {
+            assert_eq!(
+                doomed_snakes(&game_state),
+                vec![None, None],
+                "{}",
+                case.name
+            );
+            continue;
+        };
+
+        let survival = longest_survival(&game_state, 0, &region);
+        println!("{:?}", survival);
+        assert!(survival.exact, "{}", case.name);
+        assert_eq!(survival.turns, case.turns, "{}", case.name);
+        if case.doomed {
+            assert_eq!(survival.path.len() as u32, case.turns, "{}", case.name);
+        }
+        assert_eq!(survival.is_doomed(), case.doomed, "{}", case.name);
+
+        let expected = if case.doomed { Some(case.turns) } else { None };
+        assert_eq!(
+            doomed_snakes(&game_state),
+            vec![expected, None],
+            "{}",
+            case.name
+        );
+    }
+}
+
+#[test]
+fn test_decided_scores() {
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [5, 10, 11, 6, 1], "health": 3 },
+            { "id": "snake2", "body": [22, 23, 24], "health": 100 },
+            { "id": "snake3", "body": [17, 18, 19], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+    let scores = TerminalScores {
+        win: 1.0,
+        loss: 0.0,
+        draw: 0.5,
+    };
+
+    struct TestCase {
+        name: &'static str,
+        doomed: Vec<Option<u32>>,
+        expected: Option<Vec<f32>>,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            name: "Nobody doomed",
+            doomed: vec![None, None, None],
+            expected: None,
+        },
+        TestCase {
+            name: "Two snakes still free",
+            doomed: vec![Some(2), None, None],
+            expected: None,
+        },
+        TestCase {
+            name: "One snake still free",
+            doomed: vec![Some(2), None, Some(9)],
+            expected: Some(vec![0.0, 1.0, 0.0]),
+        },
+        TestCase {
+            name: "Everyone doomed",
+            doomed: vec![Some(2), Some(5), Some(9)],
+            expected: Some(vec![0.0, 0.0, 1.0]),
+        },
+        TestCase {
+            name: "Everyone doomed, lasting equally long",
+            doomed: vec![Some(2), Some(9), Some(9)],
+            expected: Some(vec![0.5, 0.5, 0.5]),
+        },
+    ];
+
+    for case in test_cases {
+        assert_eq!(
+            decided_scores(&game_state, &case.doomed, &scores),
+            case.expected,
+            "{}",
+            case.name
+        );
+    }
+}
+
+#[test]
+fn test_decided_scores_for_a_lone_free_snake() {
+    // snake2's body runs down the third column, from its head at `head`
+    let wall: Vec<usize> = (0..11).map(|row| row * 11 + 2).collect();
+    let state = |head: usize, snake1: Vec<usize>, health: u8, hazards: Vec<usize>| {
+        let mut body = vec![head];
+        body.extend(&wall);
+        json_to_game_state(&json!({
+            "width": 11,
+            "height": 11,
+            "snakes": [
+                { "id": "snake1", "body": snake1, "health": 100 },
+                { "id": "snake2", "body": body, "health": health }
+            ],
+            "food": [],
+            "hazards": hazards
+        }))
+    };
+    let scores = TerminalScores {
+        win: 1.0,
+        loss: 0.0,
+        draw: 0.5,
+    };
+    let won = Some(vec![0.0, 1.0]);
+
+    struct TestCase {
+        name: &'static str,
+        game_state: battlesnake::game_state::GameState,
+        expected: Option<Vec<f32>>,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            name: "Sharing the board with health to spare",
+            game_state: state(3, vec![60, 61, 62], 100, vec![]),
+            expected: won.clone(),
+        },
+        TestCase {
+            name: "Starving first",
+            game_state: state(3, vec![60, 61, 62], 5, vec![]),
+            expected: None,
+        },
+        TestCase {
+            name: "Burning up in hazards",
+            game_state: state(3, vec![60, 61, 62], 50, vec![4]),
+            expected: None,
+        },
+        TestCase {
+            name: "Sealed into a region that's solved",
+            game_state: state(1, vec![60, 61, 62], 100, vec![]),
+            expected: won,
+        },
+        TestCase {
+            name: "Sealed into a region too large to solve",
+            game_state: state(3, vec![22, 33, 44], 100, vec![]),
+            expected: None,
+        },
+    ];
+
+    for case in test_cases {
+        println!("{}", visualize_game_state(&case.game_state));
+        assert_eq!(
+            decided_scores(&case.game_state, &[Some(9), None], &scores),
+            case.expected,
+            "{}",
+            case.name
+        );
+    }
+}
+
+#[test]
+fn test_decided_scores_in_solo() {
+    let solo = |body: serde_json::Value, health: u8| {
+        json_to_game_state(&json!({
+            "mode": "solo",
+            "width": 5,
+            "height": 5,
+            "snakes": [{ "id": "snake1", "body": body, "health": health }],
+            "food": [],
+            "hazards": []
+        }))
+    };
+    let scores = TerminalScores::default();
+
+    // Alone on the board with room to spare, so the game goes on
+    let free = solo(json!([12, 13, 14]), 100);
+    assert_eq!(doomed_snakes(&free), vec![None]);
+    assert_eq!(decided_scores(&free, &[None], &scores), None);
+    assert!(!MCTS::with_mode(free, SearchMode::Sequential)
+        .root
+        .is_terminal());
+
+    // Starving in its pocket is a loss, not a win for outlasting nobody
+    let starving = solo(json!([5, 10, 11, 6, 1]), 2);
+    let doomed = doomed_snakes(&starving);
+    assert!(doomed[0].is_some());
+    assert_eq!(
+        decided_scores(&starving, &doomed, &scores),
+        Some(vec![scores.loss])
+    );
+    assert!(MCTS::with_mode(starving, SearchMode::Sequential)
+        .root
+        .is_terminal());
+}
+
+#[test]
+fn test_search_treats_settled_endgames_as_terminal() {
+    // snake1 starves in its pocket, so snake2 has won
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [5, 10, 11, 6, 1], "health": 2 },
+            { "id": "snake2", "body": [22, 23, 24], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+
+    let mcts = MCTS::with_mode(game_state, SearchMode::Simultaneous);
+    assert!(mcts.root.is_terminal());
+    mcts.run(Duration::from_millis(20), 1);
+
+    let scores = mcts.root_scores();
+    assert_eq!(scores[0].mean_score, TerminalScores::default().loss);
+    assert_eq!(scores[1].mean_score, TerminalScores::default().win);
+}
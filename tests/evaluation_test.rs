@@ -0,0 +1,324 @@
+// File: tests/evaluation_test.rs
+
+use battlesnake::config::{CompositeWeights, Heuristic, SearchConfig};
+use battlesnake::evaluation::{evaluator_for, CompositeEvaluator, EvaluationTerms, Evaluator};
+use battlesnake::game_state::GameState;
+use battlesnake::heuristic::{
+    calculate_control_percentages, calculate_control_percentages_with, ControlRule,
+};
+use battlesnake::search::MCTS;
+use battlesnake::visualizer::{json_to_game_state, visualize_game_state};
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn assert_terms_close(actual: &EvaluationTerms, expected: &EvaluationTerms, name: &str) {
+    let pairs = [
+        (actual.territory, expected.territory),
+        (actual.length, expected.length),
+        (actual.health, expected.health),
+        (actual.food, expected.food),
+        (actual.hazard, expected.hazard),
+    ];
+    for (a, e) in pairs {
+        assert!(
+            (a - e).abs() < 1e-4,
+            "{}: {:?} != {:?}",
+            name,
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_composite_terms() {
+    struct TestCase {
+        name: &'static str,
+        input: serde_json::Value,
+        expected: Vec<EvaluationTerms>,
+    }
+
+    // snake2 is boxed into the corner by its own body and controls 5
+    // squares; snake1 controls the other 44, food included. With no food to
+    // reach, snake2's health counts for nothing
+    let snake2 = EvaluationTerms {
+        territory: 5.0 / 49.0,
+        length: 3.0 / 7.0,
+        health: 0.0,
+        food: 0.0,
+        hazard: 1.0,
+    };
+
+    let test_cases = vec![
+        TestCase {
+            name: "Longer snake next to its food",
+            input: json!({
+                "width": 7,
+                "height": 7,
+                "snakes": [
+                    { "id": "snake1", "body": [24, 25, 26, 27], "health": 100 },
+                    { "id": "snake2", "body": [0, 1, 2], "health": 100 }
+                ],
+                "food": [22],
+                "hazards": []
+            }),
+            expected: vec![
+                EvaluationTerms {
+                    territory: 44.0 / 49.0,
+                    length: 4.0 / 7.0,
+                    health: 1.0,
+                    food: 1.0 - 2.0 / 14.0,
+                    hazard: 1.0,
+                },
+                snake2,
+            ],
+        },
+        TestCase {
+            // The food is two moves away with two health left
+            name: "Starving before reaching food",
+            input: json!({
+                "width": 7,
+                "height": 7,
+                "snakes": [
+                    { "id": "snake1", "body": [24, 25, 26, 27], "health": 2 },
+                    { "id": "snake2", "body": [0, 1, 2], "health": 100 }
+                ],
+                "food": [22],
+                "hazards": []
+            }),
+            expected: vec![
+                EvaluationTerms {
+                    territory: 44.0 / 49.0,
+                    length: 4.0 / 7.0,
+                    health: 0.0,
+                    food: 1.0 - 2.0 / 14.0,
+                    hazard: 1.0,
+                },
+                snake2,
+            ],
+        },
+        TestCase {
+            // Plenty of health, but no food to spend it reaching
+            name: "No food in reach",
+            input: json!({
+                "width": 7,
+                "height": 7,
+                "snakes": [
+                    { "id": "snake1", "body": [24, 25, 26, 27], "health": 50 },
+                    { "id": "snake2", "body": [0, 1, 2], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            expected: vec![
+                EvaluationTerms {
+                    territory: 44.0 / 49.0,
+                    length: 4.0 / 7.0,
+                    health: 0.0,
+                    food: 0.0,
+                    hazard: 1.0,
+                },
+                snake2,
+            ],
+        },
+        TestCase {
+            name: "Hazards in the territory",
+            input: json!({
+                "width": 7,
+                "height": 7,
+                "snakes": [
+                    { "id": "snake1", "body": [24, 25, 26, 27], "health": 100 },
+                    { "id": "snake2", "body": [0, 1, 2], "health": 100 }
+                ],
+                "food": [22],
+                "hazards": [46, 47, 48]
+            }),
+            expected: vec![
+                EvaluationTerms {
+                    territory: 44.0 / 49.0,
+                    length: 4.0 / 7.0,
+                    health: 1.0,
+                    food: 1.0 - 2.0 / 14.0,
+                    hazard: 1.0 - 3.0 / 44.0,
+                },
+                snake2,
+            ],
+        },
+        TestCase {
+            name: "Eliminated snake",
+            input: json!({
+                "width": 7,
+                "height": 7,
+                "snakes": [
+                    { "id": "snake1", "body": [24, 25, 26, 27], "health": 100 },
+                    { "id": "snake2", "body": [0, 1, 2], "health": 0 }
+                ],
+                "food": [22],
+                "hazards": []
+            }),
+            expected: vec![
+                EvaluationTerms {
+                    territory: 1.0,
+                    length: 1.0,
+                    health: 1.0,
+                    food: 1.0 - 2.0 / 14.0,
+                    hazard: 1.0,
+                },
+                EvaluationTerms::default(),
+            ],
+        },
+    ];
+
+    let evaluator = CompositeEvaluator::new(CompositeWeights::default());
+    for case in test_cases {
+        let game_state = json_to_game_state(&case.input);
+        println!("Test case: {}", case.name);
+        println!("{}", visualize_game_state(&game_state));
+
+        let terms = evaluator.terms(&game_state);
+        println!("Terms: {:?}", terms);
+
+        assert_eq!(terms.len(), case.expected.len(), "{}", case.name);
+        for (actual, expected) in terms.iter().zip(&case.expected) {
+            assert_terms_close(actual, expected, case.name);
+        }
+        for score in evaluator.evaluate(&game_state) {
+            assert!((0.0..=1.0).contains(&score), "{}: {}", case.name, score);
+        }
+    }
+}
+
+#[test]
+fn test_composite_scores_are_weighted_means() {
+    let game_state = json_to_game_state(&json!({
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [24, 25, 26, 27], "health": 100 },
+            { "id": "snake2", "body": [0, 1, 2], "health": 100 }
+        ],
+        "food": [22],
+        "hazards": []
+    }));
+
+    // Territory alone is the control heuristic
+    let territory_only = CompositeEvaluator::new(CompositeWeights {
+        territory: 1.0,
+        length: 0.0,
+        health: 0.0,
+        food: 0.0,
+        hazard: 0.0,
+    });
+    assert_eq!(
+        territory_only.evaluate(&game_state),
+        calculate_control_percentages(&game_state)
+    );
+
+    // Only the ratios of the weights matter
+    let weights = CompositeWeights::default();
+    let doubled = CompositeWeights {
+        territory: weights.territory * 2.0,
+        length: weights.length * 2.0,
+        health: weights.health * 2.0,
+        food: weights.food * 2.0,
+        hazard: weights.hazard * 2.0,
+    };
+    let scores = CompositeEvaluator::new(weights).evaluate(&game_state);
+    let doubled_scores = CompositeEvaluator::new(doubled).evaluate(&game_state);
+    for (a, b) in scores.iter().zip(&doubled_scores) {
+        assert!((a - b).abs() < 1e-6);
+    }
+    assert!(scores[0] > scores[1]);
+}
+
+#[test]
+fn test_evaluator_follows_the_config() {
+    let game_state = json_to_game_state(&json!({
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [24, 25, 26, 27], "health": 100 },
+            { "id": "snake2", "body": [0, 1, 2], "health": 100 }
+        ],
+        "food": [22],
+        "hazards": []
+    }));
+
+    let control = evaluator_for(&SearchConfig::default());
+    assert_eq!(
+        control.evaluate(&game_state),
+        calculate_control_percentages(&game_state)
+    );
+
+    let config = SearchConfig {
+        heuristic: Heuristic::Composite,
+        ..SearchConfig::default()
+    };
+    assert_eq!(
+        evaluator_for(&config).evaluate(&game_state),
+        CompositeEvaluator::new(config.composite).evaluate(&game_state)
+    );
+
+    let mut config = SearchConfig::default();
+    config.control.rule = ControlRule::HeadToHead;
+    assert_eq!(
+        evaluator_for(&config).evaluate(&game_state),
+        calculate_control_percentages_with(&game_state, &config.control)
+    );
+}
+
+/// Scores every snake the same and counts its calls.
+struct CountingEvaluator {
+    calls: AtomicU64,
+}
+
+impl Evaluator for CountingEvaluator {
+    fn evaluate(&self, state: &GameState) -> Vec<f32> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        vec![0.5; state.snakes.len()]
+    }
+}
+
+#[test]
+fn test_mcts_uses_its_evaluator() {
+    let game_state = json_to_game_state(&json!({
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [24, 25, 26], "health": 100 },
+            { "id": "snake2", "body": [8, 1, 2], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+    let evaluator = Arc::new(CountingEvaluator {
+        calls: AtomicU64::new(0),
+    });
+    let config = SearchConfig {
+        max_nodes: Some(50),
+        ..SearchConfig::default()
+    };
+
+    let mut mcts = MCTS::new(game_state, config);
+    mcts.set_evaluator(evaluator.clone());
+    mcts.run(Duration::from_secs(5), 1);
+
+    let calls = evaluator.calls.load(Ordering::Relaxed);
+    assert!(calls > 0);
+    assert!(calls <= mcts.evaluations.load(Ordering::Relaxed));
+    // Leaves where the game goes on get the evaluator's scores
+    let mut evaluated = 0;
+    let mut queue = vec![Arc::clone(&mcts.root)];
+    while let Some(node) = queue.pop() {
+        if let Some(scores) = node.heuristic() {
+            if !node.game_state.is_game_over() {
+                assert_eq!(scores, [0.5, 0.5]);
+                evaluated += 1;
+            }
+        }
+        queue.extend(node.child_nodes());
+    }
+    assert!(evaluated > 0);
+}
@@ -1,9 +1,11 @@
 // File: tests/game_state_test.rs
 
+mod common;
+
 use battlesnake::game_state::Direction;
 use battlesnake::visualizer::{json_to_game_state, visualize_game_state};
+use common::without_food_spawns;
 use serde_json::json;
-use std::collections::HashMap;
 
 #[derive(Debug)]
 struct TestCase {
@@ -33,6 +35,7 @@ fn create_test_cases() -> Vec<TestCase> {
             }),
             snake_moves: vec!["up".to_string()], // Move for snake1
             expected_state: json!({
+                "turn": 1,
                 "width": 5,
                 "height": 5,
                 "snakes": [
@@ -64,12 +67,14 @@ fn create_test_cases() -> Vec<TestCase> {
             }),
             snake_moves: vec!["up".to_string()], // Move for snake1
             expected_state: json!({
+                "turn": 1,
                 "width": 5,
                 "height": 5,
                 "snakes": [{
                     "id": "snake1",
                     "body": [usize::MAX, 0, 1],
-                    "health": 0
+                    "health": 0,
+                    "eliminated": { "cause": "wall-collision", "by": null, "turn": 1 }
                 }],
                 "food": [],
                 "hazards": []
@@ -98,18 +103,21 @@ fn create_test_cases() -> Vec<TestCase> {
             }),
             snake_moves: vec!["up".to_string(), "down".to_string()], // Moves for snake1 and snake2
             expected_state: json!({
+                "turn": 1,
                 "width": 5,
                 "height": 5,
                 "snakes": [
                     {
                         "id": "snake1",
                         "body": [7, 12, 13],
-                        "health": 0
+                        "health": 0,
+                        "eliminated": { "cause": "snake-collision", "by": "snake2", "turn": 1 }
                     },
                     {
                         "id": "snake2",
                         "body": [12, 7, 6],
-                        "health": 0
+                        "health": 0,
+                        "eliminated": { "cause": "snake-collision", "by": "snake1", "turn": 1 }
                     }
                 ],
                 "food": [],
@@ -134,13 +142,15 @@ fn create_test_cases() -> Vec<TestCase> {
             }),
             snake_moves: vec!["left".to_string()], // Move for snake1
             expected_state: json!({
+                "turn": 1,
                 "width": 5,
                 "height": 5,
                 "snakes": [
                     {
                         "id": "snake1",
                         "body": [11, 12, 13],
-                        "health": 0
+                        "health": 0,
+                        "eliminated": { "cause": "out-of-health", "by": null, "turn": 1 }
                     }
                 ],
                 "food": [],
@@ -165,6 +175,7 @@ fn create_test_cases() -> Vec<TestCase> {
             }),
             snake_moves: vec!["right".to_string()], // Move for snake1
             expected_state: json!({
+                "turn": 1,
                 "width": 7,
                 "height": 7,
                 "snakes": [
@@ -201,18 +212,21 @@ fn create_test_cases() -> Vec<TestCase> {
             }),
             snake_moves: vec!["right".to_string(), "left".to_string()], // Moves for snake1 and snake2
             expected_state: json!({
+                "turn": 1,
                 "width": 7,
                 "height": 7,
                 "snakes": [
                     {
                         "id": "snake1",
                         "body": [3, 2, 1],
-                        "health": 0
+                        "health": 0,
+                        "eliminated": { "cause": "head-collision", "by": "snake2", "turn": 1 }
                     },
                     {
                         "id": "snake2",
                         "body": [3, 4, 5],
-                        "health": 0
+                        "health": 0,
+                        "eliminated": { "cause": "head-collision", "by": "snake1", "turn": 1 }
                     }
                 ],
                 "food": [],
@@ -237,13 +251,15 @@ fn create_test_cases() -> Vec<TestCase> {
             }),
             snake_moves: vec!["down".to_string()], // Move for snake1
             expected_state: json!({
+                "turn": 1,
                 "width": 5,
                 "height": 5,
                 "snakes": [
                     {
                         "id": "snake1",
                         "body": [11, 6, 7, 8, 13, 12, 11],
-                        "health": 0
+                        "health": 0,
+                        "eliminated": { "cause": "snake-self-collision", "by": "snake1", "turn": 1 }
                     }
                 ],
                 "food": [1],
@@ -273,13 +289,15 @@ fn create_test_cases() -> Vec<TestCase> {
             }),
             snake_moves: vec!["up".to_string(), "right".to_string()], // Moves for snake1 and snake2
             expected_state: json!({
+                "turn": 1,
                 "width": 5,
                 "height": 5,
                 "snakes": [
                     {
                         "id": "snake1",
                         "body": [7, 12, 13],
-                        "health": 0 // Collides with snake2's body
+                        "health": 0, // Collides with snake2's body
+                        "eliminated": { "cause": "snake-collision", "by": "snake2", "turn": 1 }
                     },
                     {
                         "id": "snake2",
@@ -301,7 +319,7 @@ fn test_game_state_simulation() {
         println!("Test Case: {}\n", case.name);
 
         // Convert initial state JSON to GameState
-        let mut game_state = json_to_game_state(&case.initial_state);
+        let mut game_state = without_food_spawns(json_to_game_state(&case.initial_state));
 
         // Visualize initial state
         println!("Initial State:");
@@ -323,7 +341,7 @@ fn test_game_state_simulation() {
         game_state.resolve_collisions();
 
         // Convert expected state JSON to GameState
-        let expected_game_state = json_to_game_state(&case.expected_state);
+        let expected_game_state = without_food_spawns(json_to_game_state(&case.expected_state));
 
         // Visualize expected state
         println!("\nExpected State:");
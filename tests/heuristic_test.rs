@@ -1,6 +1,10 @@
 // File: tests/heuristic_test.rs
 
-use battlesnake::heuristic::{calculate_control_percentages, calculate_snake_control};
+use battlesnake::heuristic::{
+    calculate_control_percentages, calculate_hazard_control, calculate_head_to_head_control,
+    calculate_reachable_areas, calculate_snake_control, calculate_snake_control_with,
+    ControlOptions, ControlRule, ReachableArea,
+};
 use battlesnake::visualizer::{json_to_game_state, visualize_control, visualize_game_state};
 use serde_json::json;
 
@@ -9,6 +13,9 @@ struct TestCase {
     input: serde_json::Value,
     expected_control: Vec<i8>,
     expected_percentages: Vec<f32>,
+    /// Control from `calculate_head_to_head_control`, checked when set.
+    expected_head_to_head_control: Option<Vec<i8>>,
+    expected_dangerous: Vec<usize>,
 }
 
 fn create_test_cases() -> Vec<TestCase> {
@@ -37,6 +44,8 @@ fn create_test_cases() -> Vec<TestCase> {
                 0, 0, 0, 0, -1, 0, 0, 0, -1, 1, 0, 0, -1, 1, 1, 0, -1, 1, 1, 1, -1, 1, 1, 1, 1,
             ],
             expected_percentages: vec![40.0, 40.0],
+            expected_head_to_head_control: None,
+            expected_dangerous: vec![],
         },
         TestCase {
             name: "Single snake scenario",
@@ -55,6 +64,8 @@ fn create_test_cases() -> Vec<TestCase> {
             }),
             expected_control: vec![0, 0, 0, 0, 0, 0, 0, 0, 0],
             expected_percentages: vec![100.0],
+            expected_head_to_head_control: None,
+            expected_dangerous: vec![],
         },
         TestCase {
             name: "Single snake can get out",
@@ -73,6 +84,8 @@ fn create_test_cases() -> Vec<TestCase> {
             }),
             expected_control: vec![0, 0, 0, 0, 0, 0, 0, 0, 0],
             expected_percentages: vec![100.0],
+            expected_head_to_head_control: None,
+            expected_dangerous: vec![],
         },
         TestCase {
             name: "Three snake scenario",
@@ -104,6 +117,8 @@ fn create_test_cases() -> Vec<TestCase> {
                 2, 1, -1, 2, 2, 2, 2, 1, 1, -1, -1, 2, 2, 1, 1, 1, -1, -1, -1, 1, 1, 1, 1,
             ],
             expected_percentages: vec![20.408163, 20.408163, 34.69388],
+            expected_head_to_head_control: None,
+            expected_dangerous: vec![],
         },
         TestCase {
             name: "Three snake scenario",
@@ -135,8 +150,71 @@ fn create_test_cases() -> Vec<TestCase> {
                 2, 1, -1, 2, 2, 2, 2, 1, 1, -1, -1, 2, 2, 1, 1, 1, -1, -1, -1, 1, 1, 1, 1,
             ],
             expected_percentages: vec![20.408163, 20.408163, 34.69388],
+            expected_head_to_head_control: None,
+            expected_dangerous: vec![],
+        },
+        TestCase {
+            // Both snakes reach the square between their heads first; the
+            // longer one would win the collision there
+            name: "Longer snake wins contested squares",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    {
+                        "id": "snake1",
+                        "body": [11, 16, 21],
+                        "health": 100
+                    },
+                    {
+                        "id": "snake2",
+                        "body": [13, 18, 23, 24],
+                        "health": 100
+                    }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            expected_control: vec![
+                0, 0, 0, 1, 1, 0, 0, 0, 1, 1, 0, 0, 0, 1, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1,
+            ],
+            expected_percentages: vec![0.68, 0.32],
+            expected_head_to_head_control: Some(vec![
+                0, 0, 1, 1, 1, 0, 0, 1, 1, 1, 0, 0, 1, 1, 1, 0, 1, 1, 1, 1, 0, 1, 1, 1, 1,
+            ]),
+            expected_dangerous: vec![12],
+        },
+        TestCase {
+            // Equally long snakes would both die meeting in the middle
+            // column, so nobody controls it
+            name: "Equal lengths leave contested squares unclaimed",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    {
+                        "id": "snake1",
+                        "body": [11, 16, 21],
+                        "health": 100
+                    },
+                    {
+                        "id": "snake2",
+                        "body": [13, 18, 23],
+                        "health": 100
+                    }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            expected_control: vec![
+                0, 0, 0, 1, 1, 0, 0, 0, 1, 1, 0, 0, 0, 1, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1,
+            ],
+            expected_percentages: vec![0.68, 0.32],
+            expected_head_to_head_control: Some(vec![
+                0, 0, -1, 1, 1, 0, 0, -1, 1, 1, 0, 0, -1, 1, 1, 0, 0, -1, 1, 1, 0, 0, -1, 1, 1,
+            ]),
+            expected_dangerous: vec![],
         },
-        // Add more test cases as needed
     ]
 }
 
@@ -190,6 +268,415 @@ fn test_snake_control_calculation() {
         //     );
         // }
 
+        let head_to_head = calculate_head_to_head_control(&game_state);
+        println!("Head-to-head control:");
+        println!(
+            "{}",
+            visualize_control(&head_to_head.control, game_state.width, game_state.height)
+        );
+
+        assert_eq!(
+            calculate_snake_control_with(
+                &game_state,
+                &ControlOptions {
+                    rule: ControlRule::HeadToHead,
+                    ..ControlOptions::default()
+                }
+            ),
+            head_to_head.control,
+            "Test case '{}' failed: head-to-head rule differs",
+            case.name
+        );
+        if let Some(expected) = &case.expected_head_to_head_control {
+            assert_eq!(
+                &head_to_head.control, expected,
+                "Test case '{}' failed for head-to-head control",
+                case.name
+            );
+        }
+        let dangerous: Vec<usize> = (0..head_to_head.dangerous.len())
+            .filter(|&i| head_to_head.dangerous[i])
+            .collect();
+        assert_eq!(
+            dangerous, case.expected_dangerous,
+            "Test case '{}' failed for dangerous squares",
+            case.name
+        );
+
         println!("\n");
     }
 }
+
+#[test]
+fn test_body_segments_free_up_as_the_snake_passes() {
+    // snake1 is coiled with its neck on square 1, which stays occupied for
+    // six more moves while its tail end on 10 and 11 frees up first. Going
+    // back past the neck along the top row is slower than snake2 coming up
+    // the right-hand side. Freeing the neck after two moves, as control
+    // used to, gave snake1 squares 3 and 4 as well.
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [6, 1, 2, 7, 12, 11, 10], "health": 100 },
+            { "id": "snake2", "body": [16, 21, 22], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+    println!("{}", visualize_game_state(&game_state));
+
+    let control = calculate_snake_control(&game_state);
+    println!(
+        "{}",
+        visualize_control(&control, game_state.width, game_state.height)
+    );
+    assert_eq!(
+        control,
+        vec![
+            0, 0, 0, 1, 1, //
+            0, 0, 0, 1, 1, //
+            0, 0, 0, 1, 1, //
+            1, 1, 1, 1, 1, //
+            1, 1, 1, 1, 1, //
+        ]
+    );
+}
+
+#[test]
+fn test_reachable_areas() {
+    struct TestCase {
+        name: &'static str,
+        input: serde_json::Value,
+        expected: Vec<ReachableArea>,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            // The other snake's body doesn't block snake1 for good
+            name: "Open board",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [0, 1, 2], "health": 100 },
+                    { "id": "snake2", "body": [24, 23, 22], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            expected: vec![
+                ReachableArea {
+                    area: 24,
+                    trapped: false,
+                },
+                ReachableArea {
+                    area: 24,
+                    trapped: false,
+                },
+            ],
+        },
+        TestCase {
+            // Square 0 is walled in by a body that is still there two
+            // moves later
+            name: "Trapped in a pocket",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [5, 10, 11, 6, 1, 2, 3], "health": 100 },
+                    { "id": "snake2", "body": [24, 23, 22], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            expected: vec![
+                ReachableArea {
+                    area: 1,
+                    trapped: true,
+                },
+                ReachableArea {
+                    area: 24,
+                    trapped: false,
+                },
+            ],
+        },
+        TestCase {
+            // The tail leaves square 1 in time to get out past it
+            name: "Escaping past the tail",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [5, 10, 11, 6, 1], "health": 100 },
+                    { "id": "snake2", "body": [24, 23, 22], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            expected: vec![
+                ReachableArea {
+                    area: 24,
+                    trapped: false,
+                },
+                ReachableArea {
+                    area: 24,
+                    trapped: false,
+                },
+            ],
+        },
+        TestCase {
+            name: "Eliminated snake",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [0, 1, 2], "health": 100 },
+                    { "id": "snake2", "body": [24, 23, 22], "health": 0 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            expected: vec![
+                ReachableArea {
+                    area: 24,
+                    trapped: false,
+                },
+                ReachableArea {
+                    area: 0,
+                    trapped: true,
+                },
+            ],
+        },
+    ];
+
+    for case in test_cases {
+        let game_state = json_to_game_state(&case.input);
+        println!("Test case: {}", case.name);
+        println!("{}", visualize_game_state(&game_state));
+
+        let areas = calculate_reachable_areas(&game_state);
+        println!("Calculated areas: {:?}", areas);
+
+        assert_eq!(areas, case.expected, "Test case '{}' failed", case.name);
+    }
+}
+
+#[test]
+fn test_food_aware_control() {
+    struct TestCase {
+        name: &'static str,
+        input: serde_json::Value,
+        expected_control: Vec<i8>,
+        expected_food_aware_control: Vec<i8>,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            // With two health left snake1 only gets one move away
+            name: "Starving snake",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [0, 1, 2], "health": 2 },
+                    { "id": "snake2", "body": [24, 23, 22], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            expected_control: vec![
+                0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 0, 1, 1, 1, 0, 0, 1, 1, 1,
+            ],
+            expected_food_aware_control: vec![
+                0, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+            ],
+        },
+        TestCase {
+            // The food on the way restores snake1's health
+            name: "Starving snake next to food",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [0, 1, 2], "health": 2 },
+                    { "id": "snake2", "body": [24, 23, 22], "health": 100 }
+                ],
+                "food": [5],
+                "hazards": []
+            }),
+            expected_control: vec![
+                0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 0, 1, 1, 1, 0, 0, 1, 1, 1,
+            ],
+            expected_food_aware_control: vec![
+                0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 0, 1, 1, 1, 0, 0, 1, 1, 1,
+            ],
+        },
+        TestCase {
+            // After eating at 7, snake1's body leaves 8 a move later than
+            // snake1 could otherwise get there, and snake2 arrives first
+            name: "Growing after eating",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [6, 11, 12, 13, 8, 3], "health": 100 },
+                    { "id": "snake2", "body": [19, 24, 23], "health": 100 }
+                ],
+                "food": [7],
+                "hazards": []
+            }),
+            expected_control: vec![
+                0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 1, 1, 1, 1, 0, 1, 1, 1, 1,
+            ],
+            expected_food_aware_control: vec![
+                0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 1, 1, 1, 1, 0, 1, 1, 1, 1, 0, 1, 1, 1, 1,
+            ],
+        },
+    ];
+
+    let food_aware = ControlOptions {
+        food_aware: true,
+        ..ControlOptions::default()
+    };
+    for case in test_cases {
+        let game_state = json_to_game_state(&case.input);
+        println!("Test case: {}", case.name);
+        println!("{}", visualize_game_state(&game_state));
+
+        let control = calculate_snake_control(&game_state);
+        let food_aware_control = calculate_snake_control_with(&game_state, &food_aware);
+        println!(
+            "{}",
+            visualize_control(&food_aware_control, game_state.width, game_state.height)
+        );
+
+        assert_eq!(
+            control, case.expected_control,
+            "Test case '{}' failed for control calculation",
+            case.name
+        );
+        assert_eq!(
+            calculate_snake_control_with(&game_state, &ControlOptions::default()),
+            control,
+            "Test case '{}' failed: default options changed control",
+            case.name
+        );
+        assert_eq!(
+            food_aware_control, case.expected_food_aware_control,
+            "Test case '{}' failed for food aware control",
+            case.name
+        );
+    }
+}
+
+#[test]
+fn test_hazard_control() {
+    struct TestCase {
+        name: &'static str,
+        input: serde_json::Value,
+        expected_control: Vec<i8>,
+        expected_health: Vec<u8>,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            name: "No hazards",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [0, 1, 2], "health": 100 },
+                    { "id": "snake2", "body": [24, 23, 22], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            expected_control: vec![
+                0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 0, 1, 1, 1, 0, 0, 1, 1, 1,
+            ],
+            expected_health: vec![
+                100, 97, 96, 95, 96, 99, 98, 97, 96, 97, 98, 97, 96, 97, 98, 97, 96, 97, 98, 99,
+                96, 95, 96, 97, 100,
+            ],
+        },
+        TestCase {
+            // snake2 has to cross the sauce to get anywhere, which leaves it
+            // only the square it crosses into
+            name: "Boxed in by hazards",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [0, 1, 2], "health": 100 },
+                    { "id": "snake2", "body": [24, 23, 22], "health": 100 }
+                ],
+                "food": [],
+                "hazards": [15, 16, 17, 18, 19, 20, 21]
+            }),
+            expected_control: vec![
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1,
+            ],
+            expected_health: vec![
+                100, 97, 96, 95, 94, 99, 98, 97, 96, 95, 98, 97, 96, 95, 94, 83, 82, 81, 80, 85,
+                68, 67, 80, 79, 100,
+            ],
+        },
+        TestCase {
+            // Every way out of the corner goes through a hazard that would
+            // finish snake1 off
+            name: "Deadly hazards",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [0, 1, 2], "health": 10 },
+                    { "id": "snake2", "body": [24, 23, 22], "health": 100 }
+                ],
+                "food": [],
+                "hazards": [5, 6, 7, 8]
+            }),
+            expected_control: vec![
+                0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+            ],
+            expected_health: vec![
+                10, 93, 94, 95, 96, 79, 80, 81, 82, 97, 94, 95, 96, 97, 98, 95, 96, 97, 98, 99, 94,
+                95, 96, 97, 100,
+            ],
+        },
+    ];
+
+    for case in test_cases {
+        let game_state = json_to_game_state(&case.input);
+        println!("Test case: {}", case.name);
+        println!("{}", visualize_game_state(&game_state));
+
+        let hazard_control = calculate_hazard_control(&game_state);
+        println!(
+            "{}",
+            visualize_control(&hazard_control.control, game_state.width, game_state.height)
+        );
+
+        assert_eq!(
+            hazard_control.control, case.expected_control,
+            "Test case '{}' failed for control calculation",
+            case.name
+        );
+        assert_eq!(
+            hazard_control.health, case.expected_health,
+            "Test case '{}' failed for health",
+            case.name
+        );
+
+        let least_health = ControlOptions {
+            rule: ControlRule::LeastHealth,
+            ..ControlOptions::default()
+        };
+        assert_eq!(
+            calculate_snake_control_with(&game_state, &least_health),
+            case.expected_control,
+            "Test case '{}' failed: least-health rule differs",
+            case.name
+        );
+    }
+}
@@ -0,0 +1,208 @@
+// File: tests/matrix_game_test.rs
+
+use battlesnake::config::{Engine, SearchConfig};
+use battlesnake::game_state::Direction;
+use battlesnake::matrix_game::{solve_zero_sum, MatrixGameSearch};
+use battlesnake::visualizer::{json_to_game_state, visualize_game_state};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::json;
+use std::time::Duration;
+
+const SOFT: Duration = Duration::from_secs(5);
+const HARD: Duration = Duration::from_secs(10);
+
+fn assert_close(actual: &[f32], expected: &[f32], name: &str) {
+    assert_eq!(actual.len(), expected.len(), "{}", name);
+    for (a, e) in actual.iter().zip(expected) {
+        assert!(
+            (a - e).abs() < 0.02,
+            "{}: {:?} != {:?}",
+            name,
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_solve_zero_sum() {
+    struct TestCase {
+        name: &'static str,
+        payoffs: Vec<Vec<f32>>,
+        row: Vec<f32>,
+        column: Vec<f32>,
+        value: f32,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            name: "Saddle point",
+            payoffs: vec![vec![3.0, 1.0, 4.0], vec![2.0, 2.0, 3.0]],
+            row: vec![0.0, 1.0],
+            column: vec![0.0, 1.0, 0.0],
+            value: 2.0,
+        },
+        TestCase {
+            name: "Matching pennies",
+            payoffs: vec![vec![1.0, -1.0], vec![-1.0, 1.0]],
+            row: vec![0.5, 0.5],
+            column: vec![0.5, 0.5],
+            value: 0.0,
+        },
+        TestCase {
+            name: "Rock paper scissors",
+            payoffs: vec![
+                vec![0.0, -1.0, 1.0],
+                vec![1.0, 0.0, -1.0],
+                vec![-1.0, 1.0, 0.0],
+            ],
+            row: vec![1.0 / 3.0; 3],
+            column: vec![1.0 / 3.0; 3],
+            value: 0.0,
+        },
+        TestCase {
+            // The row player's mix makes the column player indifferent:
+            // 4p - (1 - p) = -2p + 3(1 - p) gives p = 0.4
+            name: "Uneven mix",
+            payoffs: vec![vec![4.0, -2.0], vec![-1.0, 3.0]],
+            row: vec![0.4, 0.6],
+            column: vec![0.5, 0.5],
+            value: 1.0,
+        },
+        TestCase {
+            name: "Single move each",
+            payoffs: vec![vec![0.25]],
+            row: vec![1.0],
+            column: vec![1.0],
+            value: 0.25,
+        },
+    ];
+
+    for case in test_cases {
+        let equilibrium = solve_zero_sum(&case.payoffs, 2000);
+
+        assert_close(&equilibrium.row, &case.row, case.name);
+        assert_close(&equilibrium.column, &case.column, case.name);
+        assert!(
+            (equilibrium.value - case.value).abs() < 0.02,
+            "{}: {}",
+            case.name,
+            equilibrium.value
+        );
+    }
+}
+
+#[test]
+fn test_matrix_game_search_avoids_losing_moves() {
+    struct TestCase {
+        name: &'static str,
+        input: serde_json::Value,
+        snake_id: &'static str,
+        expected_moves: Vec<Direction>,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            name: "Cornered with a single way out",
+            input: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 100 },
+                    { "id": "snake2", "body": [0, 1, 2], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            snake_id: "snake2",
+            expected_moves: vec![Direction::Down],
+        },
+        TestCase {
+            // Moving left risks meeting the longer snake's head
+            name: "Head to head with a longer snake",
+            input: json!({
+                "width": 7,
+                "height": 7,
+                "snakes": [
+                    { "id": "snake1", "body": [24, 25, 26], "health": 100 },
+                    { "id": "snake2", "body": [22, 29, 36, 43, 44], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            snake_id: "snake1",
+            expected_moves: vec![Direction::Up, Direction::Down],
+        },
+    ];
+
+    let mut config = SearchConfig {
+        engine: Engine::MatrixGame,
+        ..SearchConfig::default()
+    };
+    config.matrix_game.max_depth = 2;
+    let search = MatrixGameSearch::new(&config);
+
+    for case in test_cases {
+        let game_state = json_to_game_state(&case.input);
+        println!("Running test case: {}", case.name);
+        println!("{}", visualize_game_state(&game_state));
+
+        let result = search.search(&game_state, case.snake_id, SOFT, HARD);
+        println!("{}", result);
+
+        assert_eq!(result.depth, 2, "{}", case.name);
+        let total: f32 = result.strategy.iter().map(|p| p.probability).sum();
+        assert!((total - 1.0).abs() < 1e-3, "{}", case.name);
+        let total: f32 = result.opponent_strategy.iter().map(|p| p.probability).sum();
+        assert!((total - 1.0).abs() < 1e-3, "{}", case.name);
+
+        assert!(
+            result
+                .best_move
+                .is_some_and(|best| case.expected_moves.contains(&best)),
+            "{}: got {:?}",
+            case.name,
+            result.best_move
+        );
+        // Losing moves are never played
+        for choice in &result.strategy {
+            if !case.expected_moves.contains(&choice.direction) {
+                assert!(choice.probability < 0.01, "{}: {:?}", case.name, choice);
+            }
+        }
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let sampled = result.sample_move(&mut rng).unwrap();
+            assert!(case.expected_moves.contains(&sampled), "{}", case.name);
+        }
+    }
+}
+
+#[test]
+fn test_matrix_game_is_only_for_duels() {
+    let mut game_state = json_to_game_state(&json!({
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [24, 25, 26], "health": 100 },
+            { "id": "snake2", "body": [8, 1, 2], "health": 100 },
+            { "id": "snake3", "body": [40, 41, 48], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+    let config = SearchConfig {
+        engine: Engine::MatrixGame,
+        ..SearchConfig::default()
+    };
+
+    assert_eq!(config.engine_for(&game_state), Engine::Mcts);
+    let result = MatrixGameSearch::new(&config).search(&game_state, "snake1", SOFT, HARD);
+    assert_eq!(result.depth, 0);
+    assert!(result.strategy.is_empty());
+    assert!(result.best_move.is_some());
+
+    game_state.snakes[2].health = 0;
+    assert_eq!(config.engine_for(&game_state), Engine::MatrixGame);
+}
@@ -0,0 +1,247 @@
+// File: tests/network_test.rs
+
+use battlesnake::config::{Heuristic, NetworkConfig, SearchConfig};
+use battlesnake::evaluation::{evaluator_for, try_evaluator_for, Evaluator};
+use battlesnake::game_state::GameState;
+use battlesnake::heuristic::calculate_control_percentages;
+use battlesnake::network::{
+    load_cached, DenseLayer, Network, NetworkError, NetworkEvaluator, NetworkWeights, INPUT_PLANES,
+};
+use battlesnake::search::MCTS;
+use battlesnake::visualizer::json_to_game_state;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::json;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn sample_game_state() -> GameState {
+    json_to_game_state(&json!({
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [24, 25, 26], "health": 80 },
+            { "id": "snake2", "body": [8, 1, 2, 3], "health": 50 }
+        ],
+        "food": [30],
+        "hazards": [0, 48]
+    }))
+}
+
+/// A network without conv layers, so its features are the planes' means.
+fn linear_network(value_weights: Vec<f32>, policy_bias: Vec<f32>) -> Network {
+    Network::new(NetworkWeights {
+        conv: vec![],
+        value: DenseLayer {
+            inputs: INPUT_PLANES,
+            outputs: 1,
+            weights: value_weights,
+            bias: vec![0.0],
+        },
+        policy: DenseLayer {
+            inputs: INPUT_PLANES,
+            outputs: 4,
+            weights: vec![0.0; INPUT_PLANES * 4],
+            bias: policy_bias,
+        },
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_encode() {
+    let game_state = sample_game_state();
+    let board_size = 49;
+    let planes = Network::encode(&game_state, 1);
+    let plane = |p: usize| &planes[p * board_size..(p + 1) * board_size];
+    let marked =
+        |p: usize| -> Vec<usize> { (0..board_size).filter(|&i| plane(p)[i] > 0.0).collect() };
+
+    assert_eq!(planes.len(), INPUT_PLANES * board_size);
+    // snake2's point of view
+    assert_eq!(marked(0), vec![8]);
+    assert_eq!(marked(1), vec![1, 2, 3, 8]);
+    assert_eq!(marked(2), vec![3]);
+    assert_eq!(marked(3), vec![24]);
+    assert_eq!(marked(4), vec![24, 25, 26]);
+    assert_eq!(marked(5), vec![26]);
+    assert_eq!(marked(6), vec![30]);
+    assert_eq!(marked(7), vec![0, 48]);
+    assert!(plane(8).iter().all(|&h| h == 0.5));
+    assert!(plane(9).iter().all(|&h| h == 0.8));
+}
+
+#[test]
+fn test_predictions() {
+    struct TestCase {
+        name: &'static str,
+        value_weights: Vec<f32>,
+        policy_bias: Vec<f32>,
+        expected_values: Vec<f32>,
+        expected_priors: [f32; 4],
+    }
+
+    let sigmoid = |x: f32| 1.0 / (1.0 + (-x).exp());
+    let mut health_only = vec![0.0; INPUT_PLANES];
+    health_only[8] = 1.0;
+    let mut health_difference = vec![0.0; INPUT_PLANES];
+    health_difference[8] = 4.0;
+    health_difference[9] = -4.0;
+
+    let test_cases = vec![
+        TestCase {
+            name: "Untrained",
+            value_weights: vec![0.0; INPUT_PLANES],
+            policy_bias: vec![0.0; 4],
+            expected_values: vec![0.5, 0.5],
+            expected_priors: [0.25; 4],
+        },
+        TestCase {
+            name: "Health",
+            value_weights: health_only,
+            policy_bias: vec![2.0f32.ln(), 0.0, 0.0, 0.0],
+            expected_values: vec![sigmoid(0.8), sigmoid(0.5)],
+            expected_priors: [0.4, 0.2, 0.2, 0.2],
+        },
+        TestCase {
+            name: "Health against the other snake",
+            value_weights: health_difference,
+            policy_bias: vec![0.0; 4],
+            expected_values: vec![sigmoid(1.2), sigmoid(-1.2)],
+            expected_priors: [0.25; 4],
+        },
+    ];
+
+    let game_state = sample_game_state();
+    for case in test_cases {
+        let network = linear_network(case.value_weights, case.policy_bias);
+        let predictions = network.predict(&game_state);
+
+        assert_eq!(predictions.len(), 2, "{}", case.name);
+        for (prediction, expected) in predictions.iter().zip(&case.expected_values) {
+            assert!(
+                (prediction.value - expected).abs() < 1e-5,
+                "{}: {:?}",
+                case.name,
+                prediction
+            );
+            for (prior, expected) in prediction.priors.iter().zip(&case.expected_priors) {
+                assert!(
+                    (prior - expected).abs() < 1e-5,
+                    "{}: {:?}",
+                    case.name,
+                    prediction
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_random_networks_give_probabilities() {
+    let mut game_state = sample_game_state();
+    let network = Network::random(&[8, 8], &mut StdRng::seed_from_u64(3));
+
+    for prediction in network.predict(&game_state) {
+        assert!((0.0..=1.0).contains(&prediction.value));
+        let total: f32 = prediction.priors.iter().sum();
+        assert!((total - 1.0).abs() < 1e-5);
+    }
+
+    game_state.snakes[1].health = 0;
+    let predictions = network.predict(&game_state);
+    assert_eq!(predictions[1].value, 0.0);
+    assert_eq!(predictions[1].priors, [0.0; 4]);
+}
+
+#[test]
+fn test_weights_files() {
+    let network = Network::random(&[4], &mut StdRng::seed_from_u64(5));
+    let path = std::env::temp_dir().join(format!("network_test_{}.json", std::process::id()));
+    std::fs::write(&path, network.to_json()).unwrap();
+
+    let loaded = Network::from_file(&path).unwrap();
+    assert_eq!(loaded, network);
+    let game_state = sample_game_state();
+    assert_eq!(loaded.predict(&game_state), network.predict(&game_state));
+
+    // The network heuristic loads the file; MCTS searches with it
+    let config = SearchConfig {
+        heuristic: Heuristic::Network,
+        network: NetworkConfig {
+            weights: Some(path.to_string_lossy().into_owned()),
+        },
+        max_nodes: Some(50),
+        ..SearchConfig::default()
+    };
+    assert_eq!(
+        evaluator_for(&config).evaluate(&game_state),
+        NetworkEvaluator::new(Arc::new(network)).evaluate(&game_state)
+    );
+    let mcts = MCTS::new(game_state, config);
+    mcts.run(Duration::from_secs(5), 1);
+    assert!(mcts.evaluations.load(Ordering::Relaxed) > 0);
+    assert!(mcts.get_best_move_for_snake("snake1").is_some());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_bad_weights_are_rejected() {
+    assert!(matches!(
+        Network::from_file("/nonexistent/weights.json"),
+        Err(NetworkError::Io(_))
+    ));
+    assert!(matches!(
+        Network::from_json("{\"conv\": []}"),
+        Err(NetworkError::Parse(_))
+    ));
+
+    let mut weights = Network::random(&[4], &mut StdRng::seed_from_u64(7))
+        .weights()
+        .clone();
+    weights.conv[0].in_channels = 3;
+    assert!(matches!(Network::new(weights), Err(NetworkError::Shape(_))));
+
+    let mut weights = Network::random(&[4], &mut StdRng::seed_from_u64(7))
+        .weights()
+        .clone();
+    weights.policy.bias.pop();
+    assert!(matches!(Network::new(weights), Err(NetworkError::Shape(_))));
+
+    // The config is rejected without weights, and the search keeps to the
+    // control heuristic
+    let mut config = SearchConfig {
+        heuristic: Heuristic::Network,
+        ..SearchConfig::default()
+    };
+    assert!(matches!(
+        try_evaluator_for(&config),
+        Err(NetworkError::NoWeights)
+    ));
+    config.network.weights = Some("/nonexistent/weights.json".to_string());
+    assert!(matches!(
+        try_evaluator_for(&config),
+        Err(NetworkError::Io(_))
+    ));
+    let game_state = sample_game_state();
+    assert_eq!(
+        evaluator_for(&config).evaluate(&game_state),
+        calculate_control_percentages(&game_state)
+    );
+}
+
+#[test]
+fn test_failed_loads_are_retried() {
+    let path = std::env::temp_dir().join(format!("network_retry_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    assert!(matches!(load_cached(&path), Err(NetworkError::Io(_))));
+
+    // Once the file turns up it loads, and stays loaded
+    let network = Network::random(&[4], &mut StdRng::seed_from_u64(9));
+    std::fs::write(&path, network.to_json()).unwrap();
+    assert_eq!(*load_cached(&path).unwrap(), network);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(*load_cached(&path).unwrap(), network);
+}
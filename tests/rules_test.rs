@@ -0,0 +1,512 @@
+// File: tests/rules_test.rs
+
+mod common;
+
+use battlesnake::game_state::Direction;
+use battlesnake::heuristic::calculate_snake_control;
+use battlesnake::rules::GameMode;
+use battlesnake::visualizer::{json_to_game_state, visualize_game_state};
+use common::without_food_spawns;
+use serde_json::json;
+
+struct TestCase {
+    name: &'static str,
+    initial_state: serde_json::Value,
+    moves: Vec<Direction>, // One move per snake index
+    expected_state: serde_json::Value,
+}
+
+fn create_test_cases() -> Vec<TestCase> {
+    vec![
+        TestCase {
+            name: "Starving snake is saved by food",
+            initial_state: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 1 }
+                ],
+                "food": [11],
+                "hazards": []
+            }),
+            moves: vec![Direction::Left],
+            expected_state: json!({
+                "turn": 1,
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [11, 12, 13, 13], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+        },
+        TestCase {
+            name: "Hazard damage is applied after the health reduction",
+            initial_state: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 90 }
+                ],
+                "food": [],
+                "hazards": [11]
+            }),
+            moves: vec![Direction::Left],
+            expected_state: json!({
+                "turn": 1,
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [11, 12, 13], "health": 75 }
+                ],
+                "food": [],
+                "hazards": [11]
+            }),
+        },
+        TestCase {
+            name: "Stacked hazards deal damage once per hazard",
+            initial_state: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 90 }
+                ],
+                "food": [],
+                "hazards": [11, 11]
+            }),
+            moves: vec![Direction::Left],
+            expected_state: json!({
+                "turn": 1,
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [11, 12, 13], "health": 61 }
+                ],
+                "food": [],
+                "hazards": [11, 11]
+            }),
+        },
+        TestCase {
+            name: "Hazard damage comes from the game settings",
+            initial_state: json!({
+                "settings": { "hazardDamagePerTurn": 50 },
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 90 }
+                ],
+                "food": [],
+                "hazards": [11]
+            }),
+            moves: vec![Direction::Left],
+            expected_state: json!({
+                "settings": { "hazardDamagePerTurn": 50 },
+                "turn": 1,
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [11, 12, 13], "health": 39 }
+                ],
+                "food": [],
+                "hazards": [11]
+            }),
+        },
+        TestCase {
+            name: "Food on a hazard cancels the damage",
+            initial_state: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 10 }
+                ],
+                "food": [11],
+                "hazards": [11]
+            }),
+            moves: vec![Direction::Left],
+            expected_state: json!({
+                "turn": 1,
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [11, 12, 13, 13], "health": 100 }
+                ],
+                "food": [],
+                "hazards": [11]
+            }),
+        },
+        TestCase {
+            name: "Hazard eliminates a snake that runs out of health",
+            initial_state: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 10 }
+                ],
+                "food": [],
+                "hazards": [11]
+            }),
+            moves: vec![Direction::Left],
+            expected_state: json!({
+                "turn": 1,
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    {
+                        "id": "snake1",
+                        "body": [11, 12, 13],
+                        "health": 0,
+                        "eliminated": { "cause": "hazard", "by": null, "turn": 1 }
+                    }
+                ],
+                "food": [],
+                "hazards": [11]
+            }),
+        },
+        TestCase {
+            name: "Longer snake wins a head-to-head",
+            initial_state: json!({
+                "width": 7,
+                "height": 7,
+                "snakes": [
+                    { "id": "snake1", "body": [2, 1, 0, 7], "health": 90 },
+                    { "id": "snake2", "body": [4, 5, 6], "health": 90 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            moves: vec![Direction::Right, Direction::Left],
+            expected_state: json!({
+                "turn": 1,
+                "width": 7,
+                "height": 7,
+                "snakes": [
+                    { "id": "snake1", "body": [3, 2, 1, 0], "health": 89 },
+                    {
+                        "id": "snake2",
+                        "body": [3, 4, 5],
+                        "health": 0,
+                        "eliminated": { "cause": "head-collision", "by": "snake1", "turn": 1 }
+                    }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+        },
+        TestCase {
+            name: "Snakes eat before collisions are checked",
+            initial_state: json!({
+                "width": 7,
+                "height": 7,
+                "snakes": [
+                    { "id": "snake1", "body": [2, 1, 0], "health": 90 },
+                    { "id": "snake2", "body": [4, 5, 6], "health": 90 }
+                ],
+                "food": [3],
+                "hazards": []
+            }),
+            moves: vec![Direction::Right, Direction::Left],
+            expected_state: json!({
+                "turn": 1,
+                "width": 7,
+                "height": 7,
+                "snakes": [
+                    {
+                        "id": "snake1",
+                        "body": [3, 2, 1, 1],
+                        "health": 0,
+                        "eliminated": { "cause": "head-collision", "by": "snake2", "turn": 1 }
+                    },
+                    {
+                        "id": "snake2",
+                        "body": [3, 4, 5, 5],
+                        "health": 0,
+                        "eliminated": { "cause": "head-collision", "by": "snake1", "turn": 1 }
+                    }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+        },
+        TestCase {
+            name: "Bodies of snakes eliminated this turn are not obstacles",
+            initial_state: json!({
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [11, 10, 15], "health": 90 },
+                    { "id": "snake2", "body": [2, 7, 12, 17], "health": 90 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            moves: vec![Direction::Right, Direction::Up],
+            expected_state: json!({
+                "turn": 1,
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 11, 10], "health": 89 },
+                    {
+                        "id": "snake2",
+                        "body": [usize::MAX, 2, 7, 12],
+                        "health": 0,
+                        "eliminated": { "cause": "wall-collision", "by": null, "turn": 1 }
+                    }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+        },
+        TestCase {
+            name: "Wrapped snake moves across the top edge",
+            initial_state: json!({
+                "mode": "wrapped",
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [0, 1, 2], "health": 90 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            moves: vec![Direction::Up],
+            expected_state: json!({
+                "mode": "wrapped",
+                "turn": 1,
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [20, 0, 1], "health": 89 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+        },
+        TestCase {
+            name: "Constrictor snakes always grow and food is removed",
+            initial_state: json!({
+                "mode": "constrictor",
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 90 }
+                ],
+                "food": [0],
+                "hazards": []
+            }),
+            moves: vec![Direction::Left],
+            expected_state: json!({
+                "mode": "constrictor",
+                "turn": 1,
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [11, 12, 13, 13], "health": 100 }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+        },
+        TestCase {
+            name: "Squadmates pass through each other and share health and length",
+            initial_state: json!({
+                "mode": "squad",
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [12, 13, 14], "health": 50, "squad": "a" },
+                    { "id": "snake2", "body": [6, 7, 8, 9], "health": 90, "squad": "a" }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+            moves: vec![Direction::Up, Direction::Down],
+            expected_state: json!({
+                "mode": "squad",
+                "turn": 1,
+                "width": 5,
+                "height": 5,
+                "snakes": [
+                    { "id": "snake1", "body": [7, 12, 13, 13], "health": 89, "squad": "a" },
+                    { "id": "snake2", "body": [11, 6, 7, 8], "health": 89, "squad": "a" }
+                ],
+                "food": [],
+                "hazards": []
+            }),
+        },
+    ]
+}
+
+#[test]
+fn test_rules_pipeline() {
+    let test_cases = create_test_cases();
+
+    for case in test_cases {
+        println!("Test Case: {}\n", case.name);
+
+        let mut game_state = without_food_spawns(json_to_game_state(&case.initial_state));
+        println!("Initial State:");
+        println!("{}", visualize_game_state(&game_state));
+
+        game_state.step(&case.moves);
+
+        let expected_game_state = without_food_spawns(json_to_game_state(&case.expected_state));
+        println!("\nExpected State:");
+        println!("{}", visualize_game_state(&expected_game_state));
+        println!("\nActual State:");
+        println!("{}", visualize_game_state(&game_state));
+
+        let actual_state_json =
+            serde_json::to_value(&game_state).expect("Failed to serialize actual GameState");
+        let expected_state_json = serde_json::to_value(&expected_game_state)
+            .expect("Failed to serialize expected GameState");
+
+        assert_eq!(
+            actual_state_json, expected_state_json,
+            "Test case '{}' failed",
+            case.name
+        );
+    }
+}
+
+#[test]
+fn test_game_over_depends_on_mode() {
+    let cases = [
+        ("standard", vec![100, 0], true),
+        ("standard", vec![100, 100], false),
+        ("solo", vec![100], false),
+        ("solo", vec![0], true),
+        ("squad", vec![100, 100], true),
+    ];
+
+    for (mode, healths, expected) in cases {
+        let snakes: Vec<serde_json::Value> = healths
+            .iter()
+            .enumerate()
+            .map(|(i, health)| {
+                json!({
+                    "id": format!("snake{}", i),
+                    "body": [i * 5, i * 5 + 1],
+                    "health": health,
+                    "squad": "a"
+                })
+            })
+            .collect();
+        let game_state = json_to_game_state(&json!({
+            "mode": mode,
+            "width": 5,
+            "height": 5,
+            "snakes": snakes,
+            "food": [],
+            "hazards": []
+        }));
+
+        assert_eq!(
+            game_state.is_game_over(),
+            expected,
+            "Game over mismatch for {} with healths {:?}",
+            mode,
+            healths
+        );
+    }
+}
+
+#[test]
+fn test_wrapped_edges_are_not_walls() {
+    let mut game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [0, 1, 2], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+    assert_eq!(game_state.get_safe_moves(0), vec![Direction::Down]);
+
+    game_state.mode = GameMode::Wrapped;
+    assert_eq!(
+        game_state.get_safe_moves(0),
+        vec![Direction::Up, Direction::Down, Direction::Left]
+    );
+}
+
+#[test]
+fn test_constrictor_bodies_are_never_controlled() {
+    let mut game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [12, 13, 14], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+    assert_eq!(calculate_snake_control(&game_state)[14], 0);
+
+    game_state.mode = GameMode::Constrictor;
+    let control = calculate_snake_control(&game_state);
+    assert_eq!(control[13], -1);
+    assert_eq!(control[14], -1);
+}
+
+#[test]
+fn test_royale_hazards_shrink_one_side() {
+    let mut game_state = json_to_game_state(&json!({
+        "mode": "royale",
+        "turn": 24,
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [12, 13, 14], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+
+    game_state.step(&[Direction::Left]);
+
+    let mut hazards: Vec<usize> = game_state.hazards.iter().map(|h| h.index).collect();
+    hazards.sort();
+    let edges = [
+        vec![0, 5, 10, 15, 20],
+        vec![4, 9, 14, 19, 24],
+        vec![0, 1, 2, 3, 4],
+        vec![20, 21, 22, 23, 24],
+    ];
+    assert!(
+        edges.contains(&hazards),
+        "Hazards should cover exactly one edge, got {:?}",
+        hazards
+    );
+}
+
+#[test]
+fn test_food_is_topped_up_to_the_minimum() {
+    let mut game_state = json_to_game_state(&json!({
+        "settings": { "minimumFood": 3, "foodSpawnChance": 0 },
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [12, 13, 14], "health": 100 }
+        ],
+        "food": [0],
+        "hazards": []
+    }));
+
+    game_state.step(&[Direction::Left]);
+
+    let mut food: Vec<usize> = game_state.food.iter().map(|f| f.index).collect();
+    food.sort();
+    food.dedup();
+    assert_eq!(food.len(), 3, "Expected 3 distinct food, got {:?}", food);
+    assert!(food.contains(&0));
+    for index in [11, 12, 13] {
+        assert!(
+            !food.contains(&index),
+            "Food spawned on the snake at {}",
+            index
+        );
+    }
+}
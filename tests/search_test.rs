@@ -1,13 +1,19 @@
 // File: tests/mcts_test.rs
 
-use battlesnake::game_state::{Direction, GameState};
-use battlesnake::search::{Node, MCTS};
+mod common;
+
+use battlesnake::config::{FinalMovePolicy, SearchConfig};
+use battlesnake::game_state::{Direction, SnakeOrder};
+use battlesnake::heuristic::calculate_control_percentages;
+use battlesnake::search::{Node, Parallelism, SearchMode, TreeStore, MCTS};
+use battlesnake::transposition::TranspositionTable;
 use battlesnake::tree::generate_most_visited_path_with_alternatives_html_tree;
 use battlesnake::visualizer::{json_to_game_state, visualize_game_state};
+use common::without_food_spawns;
 use serde_json::json;
-use std::cell::RefCell;
-use std::rc::Rc;
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::time::Duration;
 struct TestCase {
     name: &'static str,
@@ -128,7 +134,11 @@ fn test_mcts_move_selection() {
         println!("Initial game state:");
         println!("{}", visualize_game_state(&game_state));
 
-        let mcts = MCTS::new(game_state.clone());
+        let config = SearchConfig {
+            mode: SearchMode::Sequential,
+            ..SearchConfig::default()
+        };
+        let mcts = MCTS::new(game_state.clone(), config);
         let duration = Duration::from_millis(400); // Adjust as needed
 
         mcts.run(duration, 12);
@@ -151,12 +161,12 @@ fn test_mcts_move_selection() {
         }
 
         // Get the best move for our snake
-        let best_move = mcts.get_best_move_for_snake(&case.snake_id);
+        let best_move = mcts.get_best_move_for_snake(case.snake_id);
 
         println!("Calculated best move: {:?}", best_move);
         println!("Expected move: {:?}", case.expected_move);
 
-        if let Err(e) = generate_most_visited_path_with_alternatives_html_tree(root) {
+        if let Err(e) = generate_most_visited_path_with_alternatives_html_tree(&mcts) {
             eprintln!("Error generating move tree: {:?}", e);
         }
 
@@ -212,3 +222,688 @@ fn find_longest_path(node: &Arc<Node>) -> Vec<Arc<Node>> {
     full_path.extend(max_path);
     full_path
 }
+
+#[test]
+fn test_simultaneous_children_are_keyed_by_joint_move() {
+    let game_state = json_to_game_state(&json!({
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [8, 9, 10], "health": 100 },
+            { "id": "snake2", "body": [40, 39, 38], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+
+    let mcts = MCTS::with_mode(game_state.clone(), SearchMode::Simultaneous);
+    mcts.run(Duration::from_millis(100), 2);
+
+    let root = &mcts.root;
+    assert_eq!(root.move_stats.len(), 2);
+    for (i, stats) in root.move_stats.iter().enumerate() {
+        let moves: Vec<Direction> = stats.iter().map(|s| s.direction).collect();
+        assert_eq!(moves, game_state.get_safe_moves(i));
+    }
+    assert!(root.children.is_empty());
+    assert!(!root.joint_children.is_empty());
+
+    for entry in root.joint_children.iter() {
+        let joint_move = entry.key();
+        assert_eq!(joint_move.len(), 2);
+
+        // Both snakes moved in the same step
+        let mut expected = game_state.clone();
+        expected.step(joint_move);
+        let child = entry.value();
+        assert_eq!(child.joint_move.as_ref(), Some(joint_move));
+        for (actual, expected) in child.game_state.snakes.iter().zip(&expected.snakes) {
+            assert_eq!(actual.body, expected.body);
+            assert_eq!(actual.health, expected.health);
+        }
+    }
+
+    // Every visit below the root went through exactly one joint move, and each
+    // snake's move statistics account for all of them
+    let child_visits: u32 = root
+        .joint_children
+        .iter()
+        .map(|entry| entry.value().visits.load(Ordering::Relaxed))
+        .sum();
+    for stats in &root.move_stats {
+        let move_visits: u32 = stats.iter().map(|s| s.visits.load(Ordering::Relaxed)).sum();
+        assert_eq!(move_visits, child_visits);
+    }
+}
+
+#[test]
+fn test_simultaneous_search_avoids_losing_head_to_head() {
+    // snake1 can move right into 14, which the longer snake2 can also reach.
+    let game_state = json_to_game_state(&json!({
+        "width": 11,
+        "height": 11,
+        "snakes": [
+            { "id": "snake1", "body": [13, 12, 11], "health": 100 },
+            { "id": "snake2", "body": [15, 16, 17, 18, 19], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+
+    let mcts = MCTS::with_mode(game_state.clone(), SearchMode::Simultaneous);
+    mcts.run(Duration::from_millis(300), 4);
+
+    let best_move = mcts.get_best_move_for_snake("snake1");
+    println!("Calculated best move: {:?}", best_move);
+    let best_move = best_move.expect("Expected a move for snake1");
+
+    assert!(game_state.get_safe_moves(0).contains(&best_move));
+    assert_ne!(best_move, Direction::Right);
+}
+
+#[test]
+fn test_sequential_search_avoids_dead_end() {
+    // Up leads snake1 into the corner, walled in by snake2's stacked tail
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [5, 6, 7], "health": 100 },
+            { "id": "snake2", "body": [2, 1, 1, 1, 1], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+
+    let mcts = MCTS::with_mode(game_state, SearchMode::Sequential);
+    mcts.run(Duration::from_millis(200), 2);
+
+    assert_eq!(
+        mcts.get_best_move_for_snake("snake1"),
+        Some(Direction::Down)
+    );
+}
+
+fn tree_reuse_state() -> battlesnake::game_state::GameState {
+    json_to_game_state(&json!({
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [8, 9, 10], "health": 100 },
+            { "id": "snake2", "body": [40, 39, 38], "health": 100 }
+        ],
+        "food": [24],
+        "hazards": []
+    }))
+}
+
+#[test]
+fn test_search_does_not_spawn_food() {
+    let mut game_state = tree_reuse_state();
+    game_state.settings.food_spawn_chance = 100;
+    game_state.settings.minimum_food = 5;
+
+    for mode in [SearchMode::Simultaneous, SearchMode::Sequential] {
+        let mcts = MCTS::with_mode(game_state.clone(), mode);
+        mcts.run(Duration::from_millis(50), 2);
+
+        let mut queue = vec![Arc::clone(&mcts.root)];
+        while let Some(node) = queue.pop() {
+            for food in &node.game_state.food {
+                assert!(game_state.food.contains(food), "{:?}", mode);
+            }
+            queue.extend(node.child_nodes());
+        }
+    }
+}
+
+#[test]
+fn test_tree_store_reroots_at_observed_position() {
+    for mode in [SearchMode::Simultaneous, SearchMode::Sequential] {
+        let game_state = tree_reuse_state();
+        let store = TreeStore::new();
+        let config = SearchConfig {
+            mode,
+            ..SearchConfig::default()
+        };
+
+        let mcts = store.take("game", &game_state, &config);
+        assert_eq!(mcts.root.visits.load(Ordering::Relaxed), 0);
+        mcts.run(Duration::from_millis(100), 2);
+        store.insert("game", mcts);
+
+        let mut observed = without_food_spawns(game_state.clone());
+        observed.step(&[Direction::Down, Direction::Up]);
+
+        let mcts = store.take("game", &observed, &config);
+        assert!(
+            mcts.root.visits.load(Ordering::Relaxed) > 0,
+            "{:?}: expected the searched subtree to be reused",
+            mode
+        );
+        assert!(mcts.root.game_state.matches_position(&observed));
+        assert!(store.is_empty());
+    }
+}
+
+#[test]
+fn test_tree_store_grafts_spawned_food_onto_the_new_root() {
+    for mode in [SearchMode::Simultaneous, SearchMode::Sequential] {
+        let game_state = tree_reuse_state();
+        let store = TreeStore::new();
+        let config = SearchConfig {
+            mode,
+            ..SearchConfig::default()
+        };
+
+        let mcts = store.take("game", &game_state, &config);
+        mcts.run(Duration::from_millis(100), 2);
+        store.insert("game", mcts);
+
+        // Food spawned that the tree never simulated
+        let mut observed = without_food_spawns(game_state.clone());
+        observed.step(&[Direction::Down, Direction::Up]);
+        observed.add_food(0);
+
+        let mcts = store.take("game", &observed, &config);
+        assert!(
+            mcts.root.visits.load(Ordering::Relaxed) > 0,
+            "{:?}: expected the searched subtree to be reused",
+            mode
+        );
+        assert!(mcts.root.game_state.same_food_and_hazards(&observed));
+        assert!(!mcts.root.child_nodes().is_empty());
+
+        let root_moves = mcts.root_moves("snake1");
+        assert!(root_moves.iter().any(|m| m.visits > 0), "{:?}", mode);
+    }
+}
+
+#[test]
+fn test_tree_store_falls_back_to_fresh_tree() {
+    let game_state = tree_reuse_state();
+    let store = TreeStore::new();
+
+    let config = SearchConfig::default();
+
+    let mcts = store.take("game", &game_state, &config);
+    mcts.run(Duration::from_millis(50), 2);
+    store.insert("game", mcts);
+
+    // A position the tree never reached
+    let mut observed = without_food_spawns(game_state.clone());
+    observed.step(&[Direction::Down, Direction::Up]);
+    observed.snakes[0].health = 50;
+
+    let mcts = store.take("game", &observed, &config);
+    assert_eq!(mcts.root.visits.load(Ordering::Relaxed), 0);
+    assert!(mcts.root.game_state.matches_position(&observed));
+
+    store.insert("game", mcts);
+    assert_eq!(store.len(), 1);
+    store.remove("game");
+    assert!(store.is_empty());
+}
+
+#[test]
+fn test_run_until_reports_root_moves() {
+    let game_state = tree_reuse_state();
+
+    for mode in [SearchMode::Simultaneous, SearchMode::Sequential] {
+        let mcts = MCTS::with_mode(game_state.clone(), mode);
+        mcts.run_until(2, |progress, _| progress.root_visits() >= 200);
+
+        let root_moves = mcts.root_moves("snake1");
+        assert!(!root_moves.is_empty());
+        for root_move in &root_moves {
+            assert!(game_state.get_safe_moves(0).contains(&root_move.direction));
+            assert!((0.0..=1.0).contains(&root_move.mean_score));
+        }
+        assert!(mcts.root_moves("missing").is_empty());
+    }
+}
+
+#[test]
+fn test_tree_store_searches_on_the_game_transposition_table() {
+    for mode in [SearchMode::Simultaneous, SearchMode::Sequential] {
+        let game_state = tree_reuse_state();
+        let store = TreeStore::new();
+        let config = SearchConfig {
+            mode,
+            ..SearchConfig::default()
+        };
+
+        let mcts = store.take("game", &game_state, &config);
+        let table = Arc::clone(mcts.transpositions().expect("a table for the game"));
+        mcts.run(Duration::from_millis(100), 1);
+        // Transposed leaves reuse the cached evaluation
+        assert!(!table.is_empty(), "{:?}", mode);
+        assert!(
+            (table.evaluations_len() as u64) < mcts.evaluations.load(Ordering::Relaxed),
+            "{:?}: expected evaluation cache hits",
+            mode
+        );
+        store.insert("game", mcts);
+
+        // A fresh tree for the same game keeps the table
+        let mut observed = without_food_spawns(game_state.clone());
+        observed.step(&[Direction::Down, Direction::Up]);
+        observed.snakes[0].health = 50;
+        let mcts = store.take("game", &observed, &config);
+        assert_eq!(mcts.root.visits.load(Ordering::Relaxed), 0);
+        assert!(Arc::ptr_eq(mcts.transpositions().unwrap(), &table));
+
+        store.remove("game");
+        let mcts = store.take("game", &game_state, &config);
+        assert!(!Arc::ptr_eq(mcts.transpositions().unwrap(), &table));
+    }
+}
+
+#[test]
+fn test_transposed_nodes_share_statistics() {
+    for mode in [SearchMode::Sequential, SearchMode::Simultaneous] {
+        let table = Arc::new(TranspositionTable::default());
+        let config = SearchConfig {
+            mode,
+            ..SearchConfig::default()
+        };
+        let mcts = MCTS::with_transpositions(tree_reuse_state(), config, Arc::clone(&table));
+        // One thread, so no expansion is raced and every visit stays in the tree
+        mcts.run(Duration::from_millis(200), 1);
+
+        assert!(!table.is_empty());
+        assert!(table.evaluations_len() > 0);
+
+        // Each entry has exactly the visits of the nodes that share it
+        let mut visits_by_key: HashMap<u64, u32> = HashMap::new();
+        let mut queue = vec![Arc::clone(&mcts.root)];
+        while let Some(node) = queue.pop() {
+            *visits_by_key.entry(node.key).or_default() += node.visits.load(Ordering::Relaxed);
+            queue.extend(node.child_nodes());
+        }
+        for (key, visits) in visits_by_key {
+            let entry = table.get(key).expect("Every node has an entry");
+            assert_eq!(entry.visits.load(Ordering::Relaxed), visits);
+        }
+    }
+}
+
+#[test]
+fn test_nodes_are_evaluated_exactly_once_under_contention() {
+    // A small board, so many threads keep landing on the same leaves
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [6, 7, 8], "health": 100 },
+            { "id": "snake2", "body": [18, 17, 16], "health": 100 }
+        ],
+        "food": [12],
+        "hazards": []
+    }));
+
+    for mode in [SearchMode::Sequential, SearchMode::Simultaneous] {
+        let mcts = MCTS::with_mode(game_state.clone(), mode);
+        mcts.run(Duration::from_millis(300), 16);
+
+        let mut evaluated = 0;
+        let mut queue = vec![Arc::clone(&mcts.root)];
+        while let Some(node) = queue.pop() {
+            if let Some(heuristic) = node.heuristic() {
+                evaluated += 1;
+                assert_eq!(heuristic.len(), node.game_state.snakes.len());
+                if !node.is_terminal() {
+                    assert_eq!(
+                        heuristic,
+                        calculate_control_percentages(&node.game_state).as_slice()
+                    );
+                }
+            }
+            queue.extend(node.child_nodes());
+        }
+
+        assert!(evaluated > 0);
+        assert_eq!(mcts.evaluations.load(Ordering::Relaxed), evaluated);
+    }
+}
+
+#[test]
+fn test_virtual_losses_are_released_after_search() {
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [6, 7, 8], "health": 100 },
+            { "id": "snake2", "body": [18, 17, 16], "health": 100 }
+        ],
+        "food": [12],
+        "hazards": []
+    }));
+
+    for mode in [SearchMode::Sequential, SearchMode::Simultaneous] {
+        let mcts = MCTS::with_mode(game_state.clone(), mode);
+        mcts.run(Duration::from_millis(300), 16);
+
+        let iterations = mcts.iterations.load(Ordering::Relaxed);
+        assert!(iterations > 0);
+        assert_eq!(mcts.root.visits.load(Ordering::Relaxed) as u64, iterations);
+
+        // Only the thread that claimed a node creates its children, so every
+        // node created is a distinct child in the tree
+        let mut children = 0;
+        let mut queue = vec![Arc::clone(&mcts.root)];
+        while let Some(node) = queue.pop() {
+            assert_eq!(node.virtual_loss.load(Ordering::Relaxed), 0);
+            for stats in &node.move_stats {
+                for stat in stats {
+                    assert_eq!(stat.virtual_loss.load(Ordering::Relaxed), 0);
+                }
+            }
+            if mode == SearchMode::Sequential {
+                assert_eq!(node.is_expanded(), !node.children.is_empty());
+            }
+            let child_nodes = node.child_nodes();
+            children += child_nodes.len() as u64;
+            queue.extend(child_nodes);
+        }
+        assert!(children > 0);
+        assert_eq!(mcts.nodes.load(Ordering::Relaxed), children, "{:?}", mode);
+    }
+}
+
+#[test]
+fn test_parallelism_strategies_search_the_root() {
+    let game_state = json_to_game_state(&json!({
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [8, 9, 10], "health": 100 },
+            { "id": "snake2", "body": [40, 39, 38], "health": 100 }
+        ],
+        "food": [24],
+        "hazards": []
+    }));
+
+    for parallelism in [
+        Parallelism::SharedTree,
+        Parallelism::RootParallel,
+        Parallelism::LeafParallel,
+    ] {
+        for mode in [SearchMode::Sequential, SearchMode::Simultaneous] {
+            let config = SearchConfig {
+                mode,
+                parallelism,
+                ..SearchConfig::default()
+            };
+            let mcts = MCTS::new(game_state.clone(), config);
+            mcts.run(Duration::from_millis(200), 4);
+
+            let root_visits = mcts.root.visits.load(Ordering::Relaxed);
+            assert!(root_visits > 0, "{:?} {:?}", parallelism, mode);
+            assert_eq!(
+                mcts.iterations.load(Ordering::Relaxed),
+                root_visits as u64,
+                "{:?} {:?}",
+                parallelism,
+                mode
+            );
+
+            // Every iteration, from every tree, goes through one root move
+            let root_moves = mcts.root_moves("snake1");
+            let move_visits: u32 = root_moves.iter().map(|m| m.visits).sum();
+            assert_eq!(move_visits, root_visits, "{:?} {:?}", parallelism, mode);
+            assert!(mcts.get_best_move_for_snake("snake1").is_some());
+
+            let mut queue = vec![Arc::clone(&mcts.root)];
+            while let Some(node) = queue.pop() {
+                assert_eq!(node.virtual_loss.load(Ordering::Relaxed), 0);
+                queue.extend(node.child_nodes());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_root_parallel_stops_on_every_tree_visits() {
+    let game_state = json_to_game_state(&json!({
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [8, 9, 10], "health": 100 },
+            { "id": "snake2", "body": [40, 39, 38], "health": 100 }
+        ],
+        "food": [24],
+        "hazards": []
+    }));
+    let target_visits = 10_000;
+
+    for mode in [SearchMode::Sequential, SearchMode::Simultaneous] {
+        let config = SearchConfig {
+            mode,
+            parallelism: Parallelism::RootParallel,
+            ..SearchConfig::default()
+        };
+        let mcts = MCTS::new(game_state.clone(), config);
+        mcts.run_until(4, |progress, _| {
+            let visits: u32 = progress.root_moves("snake1").iter().map(|m| m.visits).sum();
+            visits >= target_visits
+        });
+
+        // Stopping on the calling thread's tree alone would take about four
+        // times as many visits
+        let root_visits = mcts.root.visits.load(Ordering::Relaxed);
+        assert!(root_visits >= target_visits, "{:?}", mode);
+        assert!(root_visits < 2 * target_visits, "{:?}", mode);
+    }
+}
+
+#[test]
+fn test_parse_parallelism() {
+    let cases = [
+        ("shared-tree", Some(Parallelism::SharedTree)),
+        ("root-parallel", Some(Parallelism::RootParallel)),
+        ("Leaf_Parallel", Some(Parallelism::LeafParallel)),
+        (" leaf-parallel ", Some(Parallelism::LeafParallel)),
+        ("gpu", None),
+    ];
+
+    for (input, expected) in cases {
+        let mut config = SearchConfig::default();
+        let parsed = config
+            .apply_env(|name| (name == "SEARCH_PARALLELISM").then(|| input.to_string()))
+            .map(|_| config.parallelism);
+        assert_eq!(parsed.ok(), expected, "{}", input);
+    }
+}
+
+#[test]
+fn test_search_stops_at_max_nodes() {
+    for mode in [SearchMode::Sequential, SearchMode::Simultaneous] {
+        let config = SearchConfig {
+            mode,
+            max_nodes: Some(50),
+            ..SearchConfig::default()
+        };
+        let mcts = MCTS::new(tree_reuse_state(), config);
+        let start = std::time::Instant::now();
+        mcts.run(Duration::from_secs(10), 2);
+
+        assert!(start.elapsed() < Duration::from_secs(5), "{:?}", mode);
+        assert!(mcts.node_limit_reached());
+    }
+}
+
+#[test]
+fn test_terminal_positions_use_configured_scores() {
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [6, 7, 8], "health": 100 },
+            { "id": "snake2", "body": [18, 17, 16], "health": 0 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+    let config = SearchConfig {
+        terminal: battlesnake::config::TerminalScores {
+            win: 0.9,
+            loss: 0.2,
+            draw: 0.5,
+        },
+        ..SearchConfig::default()
+    };
+
+    let mcts = MCTS::new(game_state, config);
+    mcts.run_until(1, |progress, _| progress.iterations() > 0);
+    assert_eq!(mcts.root.heuristic(), Some([0.9, 0.2].as_slice()));
+}
+
+#[test]
+fn test_final_move_policies() {
+    struct TestCase {
+        name: &'static str,
+        // Direction, visits and mean score of each of snake2's moves
+        stats: Vec<(Direction, u32, f32)>,
+        expected: Vec<(FinalMovePolicy, Direction)>,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            name: "Policies disagree",
+            stats: vec![
+                (Direction::Up, 100, 0.5),
+                (Direction::Down, 60, 0.7),
+                (Direction::Left, 5, 0.9),
+            ],
+            expected: vec![
+                (FinalMovePolicy::MaxVisits, Direction::Up),
+                (FinalMovePolicy::MaxMean, Direction::Left),
+                // Visits and value disagree, so visits win
+                (FinalMovePolicy::RobustMax, Direction::Up),
+                // Left's mean is too uncertain after 5 visits
+                (FinalMovePolicy::SecureChild, Direction::Down),
+            ],
+        },
+        TestCase {
+            name: "Visits and value agree",
+            stats: vec![
+                (Direction::Up, 40, 0.5),
+                (Direction::Down, 200, 0.7),
+                (Direction::Left, 0, 0.0),
+            ],
+            expected: vec![
+                (FinalMovePolicy::MaxVisits, Direction::Down),
+                (FinalMovePolicy::MaxMean, Direction::Down),
+                (FinalMovePolicy::RobustMax, Direction::Down),
+                (FinalMovePolicy::SecureChild, Direction::Down),
+            ],
+        },
+    ];
+
+    // snake2 is the second snake, with its neck to its right
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [0, 1, 2], "health": 100 },
+            { "id": "snake2", "body": [12, 13, 14], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+
+    for case in test_cases {
+        let mcts = MCTS::with_mode(game_state.clone(), SearchMode::Simultaneous);
+        for stat in &mcts.root.move_stats[1] {
+            let (_, visits, mean) = case
+                .stats
+                .iter()
+                .find(|(direction, _, _)| *direction == stat.direction)
+                .expect("snake2 has exactly these moves");
+            stat.visits.store(*visits, Ordering::Relaxed);
+            stat.total_score
+                .store((*visits as f32 * mean * 1000.0) as u32, Ordering::Relaxed);
+        }
+
+        for (policy, expected) in case.expected {
+            let selection = mcts.select_move_with_policy("snake2", policy);
+            assert_eq!(
+                selection.chosen,
+                Some(expected),
+                "{}: {:?}\n{}",
+                case.name,
+                policy,
+                selection
+            );
+            assert_eq!(selection.moves.len(), 3);
+        }
+    }
+}
+
+#[test]
+fn test_final_move_for_a_snake_that_moves_second() {
+    // snake2 is cornered with a single way out
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [12, 13, 14], "health": 100 },
+            { "id": "snake2", "body": [0, 1, 2], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+    let safe_moves = game_state.get_safe_moves(1);
+    assert_eq!(safe_moves.len(), 1);
+
+    for mode in [SearchMode::Sequential, SearchMode::Simultaneous] {
+        let mcts = MCTS::with_mode(game_state.clone(), mode);
+        mcts.run(Duration::from_millis(100), 2);
+
+        let root_moves = mcts.root_moves("snake2");
+        assert_eq!(root_moves.len(), 1, "{:?}", mode);
+        assert_eq!(root_moves[0].direction, safe_moves[0]);
+        assert!(root_moves[0].visits > 0);
+        assert_eq!(mcts.get_best_move_for_snake("snake2"), Some(safe_moves[0]));
+
+        // snake1 still gets its own moves
+        let ours = mcts.root_moves("snake1");
+        assert_eq!(ours.len(), game_state.get_safe_moves(0).len());
+    }
+}
+
+#[test]
+fn test_root_scores_follow_the_original_snake_order() {
+    // snake2 was listed first on the board but searched second
+    let game_state = json_to_game_state(&json!({
+        "width": 5,
+        "height": 5,
+        "snakes": [
+            { "id": "snake1", "body": [12, 13, 14], "health": 100 },
+            { "id": "snake2", "body": [0, 1, 2], "health": 100 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+
+    let mut mcts = MCTS::with_mode(game_state, SearchMode::Sequential);
+    mcts.set_snake_order(SnakeOrder::new(vec![
+        "snake2".to_string(),
+        "snake1".to_string(),
+    ]));
+    mcts.run(Duration::from_millis(50), 1);
+
+    let scores = mcts.root_scores();
+    let ids: Vec<&str> = scores.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, vec!["snake2", "snake1"]);
+    assert_eq!(scores[0].original_index, Some(0));
+    assert_eq!(scores[0].search_index, 1);
+    assert_eq!(scores[1].original_index, Some(1));
+    assert_eq!(scores[1].search_index, 0);
+
+    // the order survives advancing the tree
+    let observed = mcts.root.game_state.clone();
+    let mcts = mcts.advance_to(&observed).expect("The root matches itself");
+    assert_eq!(mcts.snake_order().original_index("snake2"), Some(0));
+}
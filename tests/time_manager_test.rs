@@ -0,0 +1,184 @@
+// File: tests/time_manager_test.rs
+
+use battlesnake::game_state::Direction;
+use battlesnake::search::RootMove;
+use battlesnake::time_manager::{SearchBudget, TimeManager, TimeSettings};
+use std::time::Duration;
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+fn root_move(direction: Direction, visits: u32, mean_score: f32) -> RootMove {
+    RootMove {
+        direction,
+        visits,
+        mean_score,
+    }
+}
+
+fn budget() -> SearchBudget {
+    SearchBudget {
+        soft: ms(300),
+        hard: ms(400),
+        close_value_margin: 0.02,
+        min_search_time: ms(10),
+        reused_visits: 0,
+    }
+}
+
+#[test]
+fn test_budget_subtracts_margin_and_network_latency() {
+    let manager = TimeManager::new(TimeSettings {
+        safety_margin: ms(50),
+        soft_fraction: 0.5,
+        latency_smoothing: 0.5,
+        ..TimeSettings::default()
+    });
+
+    // First turn: nothing is known about the network yet
+    let first = manager.budget("game", 500, "");
+    assert_eq!(first.hard, ms(450));
+    assert_eq!(first.soft, ms(225));
+    manager.record_response_time("game", ms(400));
+
+    // The engine saw 480ms, 80ms of which was the network
+    let second = manager.budget("game", 500, "480");
+    assert_eq!(manager.network_latency("game"), Some(ms(80)));
+    assert_eq!(second.hard, ms(370));
+    manager.record_response_time("game", ms(350));
+
+    // Smoothed halfway towards the new 40ms sample
+    manager.budget("game", 500, "390");
+    assert_eq!(manager.network_latency("game"), Some(ms(60)));
+
+    // A timed out move reports "0" and leaves the estimate alone
+    manager.record_response_time("game", ms(350));
+    manager.budget("game", 500, "0");
+    assert_eq!(manager.network_latency("game"), Some(ms(60)));
+
+    // Games are tracked separately and forgotten when they end
+    assert_eq!(manager.network_latency("other"), None);
+    manager.end_game("game");
+    assert_eq!(manager.network_latency("game"), None);
+}
+
+#[test]
+fn test_budget_never_drops_below_minimum() {
+    let manager = TimeManager::new(TimeSettings {
+        min_budget: ms(20),
+        ..TimeSettings::default()
+    });
+    manager.record_response_time("game", ms(10));
+    let budget = manager.budget("game", 100, "500");
+    assert_eq!(budget.hard, ms(20));
+}
+
+#[test]
+fn test_search_stopping() {
+    struct TestCase {
+        description: &'static str,
+        elapsed: Duration,
+        root_moves: Vec<RootMove>,
+        reused_visits: u32,
+        expected_stop: bool,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            description: "Hard limit always stops",
+            elapsed: ms(400),
+            root_moves: vec![
+                root_move(Direction::Up, 100, 0.50),
+                root_move(Direction::Left, 100, 0.50),
+            ],
+            reused_visits: 0,
+            expected_stop: true,
+        },
+        TestCase {
+            description: "Too early to judge",
+            elapsed: ms(5),
+            root_moves: vec![root_move(Direction::Up, 10, 0.5)],
+            reused_visits: 0,
+            expected_stop: false,
+        },
+        TestCase {
+            description: "Only one move to choose from",
+            elapsed: ms(20),
+            root_moves: vec![root_move(Direction::Up, 10, 0.5)],
+            reused_visits: 0,
+            expected_stop: true,
+        },
+        TestCase {
+            description: "Lead can no longer be overtaken, however close the values",
+            elapsed: ms(350),
+            // 1400 visits in 350ms projects 200 more by the hard limit
+            root_moves: vec![
+                root_move(Direction::Up, 1300, 0.50),
+                root_move(Direction::Left, 100, 0.50),
+            ],
+            reused_visits: 0,
+            expected_stop: true,
+        },
+        TestCase {
+            description: "Reused visits don't count towards the visit rate",
+            elapsed: ms(350),
+            // Only 200 of the visits are new, projecting 29 more by the hard
+            // limit, which a lead of 100 can't lose
+            root_moves: vec![
+                root_move(Direction::Up, 1300, 0.50),
+                root_move(Direction::Left, 1200, 0.50),
+            ],
+            reused_visits: 2300,
+            expected_stop: true,
+        },
+        TestCase {
+            description: "Lead could still be overtaken",
+            elapsed: ms(200),
+            root_moves: vec![
+                root_move(Direction::Up, 700, 0.6),
+                root_move(Direction::Left, 500, 0.5),
+            ],
+            reused_visits: 0,
+            expected_stop: false,
+        },
+        TestCase {
+            description: "Soft limit stops a clear decision",
+            elapsed: ms(300),
+            root_moves: vec![
+                root_move(Direction::Up, 700, 0.6),
+                root_move(Direction::Left, 500, 0.5),
+            ],
+            reused_visits: 0,
+            expected_stop: true,
+        },
+        TestCase {
+            description: "Close values extend past the soft limit",
+            elapsed: ms(300),
+            root_moves: vec![
+                root_move(Direction::Up, 700, 0.51),
+                root_move(Direction::Left, 500, 0.50),
+            ],
+            reused_visits: 0,
+            expected_stop: false,
+        },
+    ];
+
+    for case in test_cases {
+        assert_eq!(
+            budget()
+                .reusing(case.reused_visits)
+                .should_stop(case.elapsed, &case.root_moves),
+            case.expected_stop,
+            "Failed: {}",
+            case.description
+        );
+    }
+}
+
+#[test]
+fn test_budget_after_spent_time() {
+    let remaining = budget().after(ms(50));
+    assert_eq!(remaining.soft, ms(250));
+    assert_eq!(remaining.hard, ms(350));
+}
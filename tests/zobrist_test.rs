@@ -0,0 +1,171 @@
+// File: tests/zobrist_test.rs
+
+use battlesnake::game_state::{Direction, GameState};
+use battlesnake::visualizer::json_to_game_state;
+use battlesnake::zobrist::{exact_hash, hash_game_state};
+use rand::seq::SliceRandom;
+use serde_json::json;
+
+fn sample_state(mode: &str) -> GameState {
+    let mut game_state = json_to_game_state(&json!({
+        "mode": mode,
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [8, 8, 8], "health": 100, "squad": "a" },
+            { "id": "snake2", "body": [12, 12, 12], "health": 100, "squad": "a" },
+            { "id": "snake3", "body": [36, 36, 36], "health": 100, "squad": "b" },
+            { "id": "snake4", "body": [40, 40, 40], "health": 100, "squad": "b" }
+        ],
+        "food": [24, 2, 46],
+        "hazards": [0, 6, 6, 20]
+    }));
+    game_state.settings.royale.shrink_every_n_turns = 5;
+    game_state
+}
+
+#[test]
+fn test_incremental_hash_matches_full_hash() {
+    let mut rng = rand::thread_rng();
+
+    for mode in ["standard", "wrapped", "constrictor", "royale", "squad"] {
+        for _ in 0..20 {
+            let mut game_state = sample_state(mode);
+            assert_eq!(game_state.hash(), hash_game_state(&game_state));
+
+            while !game_state.is_game_over() && game_state.turn < 100 {
+                let moves: Vec<Direction> = (0..game_state.snakes.len())
+                    .map(|i| {
+                        *game_state
+                            .get_safe_moves(i)
+                            .choose(&mut rng)
+                            .unwrap_or(&Direction::Up)
+                    })
+                    .collect();
+                game_state.step(&moves);
+                assert_eq!(
+                    game_state.hash(),
+                    hash_game_state(&game_state),
+                    "Incremental hash diverged in {} on turn {}",
+                    mode,
+                    game_state.turn
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_move_orders_reaching_the_same_position_share_a_hash() {
+    use Direction::*;
+
+    let start = json_to_game_state(&json!({
+        "settings": { "minimumFood": 0, "foodSpawnChance": 0 },
+        "width": 7,
+        "height": 7,
+        "snakes": [
+            { "id": "snake1", "body": [24, 31, 38], "health": 90 },
+            { "id": "snake2", "body": [48, 47, 46], "health": 90 }
+        ],
+        "food": [],
+        "hazards": []
+    }));
+
+    let play = |moves: &[Direction]| {
+        let mut game_state = start.clone();
+        for &direction in moves {
+            game_state.step(&[direction, Up]);
+        }
+        game_state
+    };
+
+    // Both end with the body on [2, 9, 16]
+    let up_first = play(&[Up, Left, Up, Up]);
+    let left_first = play(&[Left, Up, Up, Up]);
+    assert_eq!(up_first.snakes[0].body, left_first.snakes[0].body);
+    assert_eq!(up_first.hash(), left_first.hash());
+
+    // Same head, different body
+    let bent = play(&[Up, Up, Left, Up]);
+    assert_eq!(bent.snakes[0].head(), up_first.snakes[0].head());
+    assert_ne!(bent.hash(), up_first.hash());
+}
+
+#[test]
+fn test_hash_distinguishes_positions() {
+    let base = sample_state("standard");
+
+    struct TestCase {
+        description: &'static str,
+        change: fn(&mut GameState),
+        same_hash: bool,
+    }
+
+    let test_cases = vec![
+        TestCase {
+            description: "Health within the same bucket",
+            change: |game_state| game_state.snakes[0].health = 95,
+            same_hash: true,
+        },
+        TestCase {
+            description: "Health in another bucket",
+            change: |game_state| game_state.snakes[0].health = 85,
+            same_hash: false,
+        },
+        TestCase {
+            description: "Dead snake",
+            change: |game_state| game_state.snakes[0].health = 0,
+            same_hash: false,
+        },
+        TestCase {
+            description: "Extra food",
+            change: |game_state| game_state.add_food(30),
+            same_hash: false,
+        },
+        TestCase {
+            description: "Stacked hazard",
+            change: |game_state| game_state.add_hazard(20),
+            same_hash: false,
+        },
+        TestCase {
+            description: "Turn parity",
+            change: |game_state| game_state.turn += 1,
+            same_hash: false,
+        },
+        TestCase {
+            description: "Two turns later",
+            change: |game_state| game_state.turn += 2,
+            same_hash: true,
+        },
+        TestCase {
+            description: "Snakes swapped",
+            change: |game_state| game_state.snakes.swap(0, 1),
+            same_hash: false,
+        },
+    ];
+
+    for case in test_cases {
+        let mut changed = base.clone();
+        (case.change)(&mut changed);
+        changed.rehash();
+        assert_eq!(
+            changed.hash() == base.hash(),
+            case.same_hash,
+            "Failed: {}",
+            case.description
+        );
+    }
+}
+
+#[test]
+fn test_exact_hash_keeps_health() {
+    let base = sample_state("standard");
+    let mut starving = base.clone();
+    starving.snakes[0].health = 91;
+    starving.rehash();
+
+    // Same bucket, so the same position for the search's statistics, but
+    // not for evaluations
+    assert_eq!(starving.hash(), base.hash());
+    assert_ne!(exact_hash(&starving), exact_hash(&base));
+}